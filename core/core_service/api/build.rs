@@ -5,6 +5,10 @@ fn main() {
     let _companies_services_file = "./proto/companies_services.proto";
     let _notes_services_proto_file = "./proto/notes_services.proto";
     let _tasks_services_proto_file = "./proto/tasks_services.proto";
+    let _subtasks_services_proto_file = "./proto/subtasks_services.proto";
+    let _knowledge_base_services_proto_file = "./proto/knowledge_base_services.proto";
+    let _access_tokens_services_proto_file = "./proto/access_tokens_services.proto";
+    let _trash_services_proto_file = "./proto/trash_services.proto";
 
     tonic_build::configure()
         .build_server(true)
@@ -16,18 +20,26 @@ fn main() {
                 _companies_services_file,
                 _notes_services_proto_file,
                 _tasks_services_proto_file,
+                _subtasks_services_proto_file,
+                _knowledge_base_services_proto_file,
+                _access_tokens_services_proto_file,
+                _trash_services_proto_file,
             ],
             &["./proto"], // Specify the proto directory explicitly
         )
         .unwrap_or_else(|e| panic!("protobuf compile error: {}", e));
 
     println!(
-        "cargo:rerun-if-changed={} {} {} {} {} {}",
+        "cargo:rerun-if-changed={} {} {} {} {} {} {} {} {} {}",
         _default_service_file,
         _user_proto_file,
         _projects_proto_file,
         _companies_services_file,
         _notes_services_proto_file,
         _tasks_services_proto_file,
+        _subtasks_services_proto_file,
+        _knowledge_base_services_proto_file,
+        _access_tokens_services_proto_file,
+        _trash_services_proto_file,
     );
 }
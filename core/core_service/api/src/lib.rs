@@ -1,8 +1,12 @@
 use colored::Colorize;
+use helai_api_core_service::access_tokens_service_server::AccessTokensServiceServer;
 use helai_api_core_service::companies_service_server::CompaniesServiceServer;
+use helai_api_core_service::knowledge_base_service_server::KnowledgeBaseServiceServer;
 use helai_api_core_service::notes_service_server::NotesServiceServer;
 use helai_api_core_service::projects_service_server::ProjectsServiceServer;
+use helai_api_core_service::subtasks_service_server::SubtasksServiceServer;
 use helai_api_core_service::tasks_service_server::TasksServiceServer;
+use helai_api_core_service::trash_service_server::TrashServiceServer;
 use helai_api_core_service::user_service_server::UserServiceServer;
 use http::Method;
 use std::{env, sync::Arc};
@@ -12,7 +16,18 @@ use tonic::transport::Server;
 
 use sea_orm::Database;
 
+use middleware::{
+    auth_layer::AuthLayer,
+    auth_token,
+    jwks_layer::JwksLayer,
+    login_throttle,
+    oidc::OidcProvider,
+    policy::RoleHierarchy,
+    signing_keys::SIGNING_KEYS,
+    trash_purge::{self, spawn_trash_purge},
+};
 use my_server::MyServer;
+use service::mailer::mailer_from_env;
 use tower_http::cors::Any;
 use tower_http::cors::CorsLayer;
 // use tonic_web::GrpcWebLayer;
@@ -42,8 +57,40 @@ pub async fn start() -> Result<(), Box<dyn std::error::Error>> {
     Migrator::up(&connection, None).await?;
     println!("📦 Database migrations applied");
 
+    // Load the token signing keys now so a broken key configuration stops the startup
+    SIGNING_KEYS.init()?;
+    println!("🔑 Token signing keys loaded");
+
+    // Read the remaining settings now too, so an invalid value can't fail a request later
+    auth_token::init_settings()?;
+    login_throttle::init_settings()?;
+    trash_purge::init_settings()?;
+    println!("⚙️ Settings loaded");
+
+    // Roles rarely change, so the hierarchy is loaded once at startup
+    let roles = RoleHierarchy::load(&connection).await?;
+    println!("🛡️ Role hierarchy loaded");
+
+    let mailer = mailer_from_env()?;
+    println!("✉️ Mail transport configured");
+
+    // Configure the external identity provider, if any; it is discovered on the first login
+    let oidc = OidcProvider::from_env()?.map(Arc::new);
+    if oidc.is_some() {
+        println!("🪪 OpenID Connect provider configured");
+    }
+
+    let connection = Arc::new(connection);
+
+    // Permanently delete the items that outlived their time in the trash
+    spawn_trash_purge(connection.clone());
+    println!("🗑️ Trash purge scheduled");
+
     let my_server = MyServer {
-        connection: Arc::new(connection),
+        connection: connection.clone(),
+        roles: Arc::new(roles),
+        mailer,
+        oidc,
         // other fields
     };
 
@@ -67,12 +114,20 @@ pub async fn start() -> Result<(), Box<dyn std::error::Error>> {
         .accept_http1(true)
         // .layer(GrpcWebLayer::new())
         .layer(cors)
+        // Serve the public token verification keys at /.well-known/jwks.json
+        .layer(JwksLayer)
         .layer(tonic_web::GrpcWebLayer::new())
+        // Validate the session token for every method outside of the public allow-list
+        .layer(AuthLayer::new(connection))
         .add_service(UserServiceServer::new(my_server.clone()))
         .add_service(ProjectsServiceServer::new(my_server.clone()))
         .add_service(CompaniesServiceServer::new(my_server.clone()))
         .add_service(TasksServiceServer::new(my_server.clone()))
         .add_service(NotesServiceServer::new(my_server.clone()))
+        .add_service(SubtasksServiceServer::new(my_server.clone()))
+        .add_service(KnowledgeBaseServiceServer::new(my_server.clone()))
+        .add_service(AccessTokensServiceServer::new(my_server.clone()))
+        .add_service(TrashServiceServer::new(my_server.clone()))
         .serve(addr)
         .await?;
 
@@ -1,15 +1,20 @@
 use core_database::{
-    entity::{notes, tasks, user_company},
+    entity::{
+        knowledge_base, notes, sea_orm_active_enums::AccessLevelType, subtasks, tasks, user_company,
+    },
     queries::{
         companies_query::CompaniesQuery,
         notes_query::NotesQuery,
         projects_query::{ProjectQuery, UserProject},
+        subtasks_query::SubtasksQuery,
         tasks_query::TasksQuery,
     },
 };
 use sea_orm::DbConn;
 use tonic::Status;
 
+use super::policy::{Action, RoleHierarchy};
+
 // If user have access to company return it
 pub async fn check_company_permission(
     conn: &DbConn,
@@ -56,6 +61,33 @@ pub async fn check_tasks_permission(
     }
 }
 
+// If user have access to subtask return it with the user's role.
+// Access is inherited from the parent task; otherwise it falls back to access granted on the subtask itself.
+pub async fn check_subtask_permission(
+    conn: &DbConn,
+    user_id: i32,
+    subtask_id: i32,
+) -> Result<(subtasks::Model, i32), Status> {
+    // Retrieve the subtask to find its parent task
+    let subtask = match SubtasksQuery::get_subtask_by_id(conn, subtask_id).await? {
+        Some(subtask) => subtask,
+        None => return Err(Status::permission_denied("permission_denied")),
+    };
+
+    // Access to the parent task grants access to all of its subtasks
+    if let Some((_, role_id)) =
+        TasksQuery::get_user_task_with_access_lvl(conn, user_id, subtask.task_id).await?
+    {
+        return Ok((subtask, role_id));
+    }
+
+    // Otherwise check for access granted directly on the subtask
+    match SubtasksQuery::get_user_subtask_access_lvl(conn, user_id, subtask_id).await? {
+        Some(role_id) => Ok((subtask, role_id)),
+        None => Err(Status::permission_denied("permission_denied")),
+    }
+}
+
 // First, verify that the note exists. Then, check access permissions based on the presence of a company ID:
 // - If the project or task is associated with a company, validate access through the company's permissions.
 // - Otherwise, ensure the user ID of the note matches the current user, allowing them to delete their own note.
@@ -72,3 +104,84 @@ pub async fn check_note_permission(
         None => Err(Status::permission_denied("permission_denied")),
     }
 }
+
+// Role of a user when reading knowledge base articles of a company or project.
+pub struct KnowledgeBaseReader {
+    pub role_id: i32,
+    pub is_company_member: bool,
+}
+
+// Resolve the user's role for the knowledge base of a company, or of one of its projects.
+// Project articles use the user's project role and fall back to the company role.
+// Returns `None` if the user has no access to the scope at all.
+pub async fn get_knowledge_base_reader(
+    conn: &DbConn,
+    user_id: i32,
+    company_id: i32,
+    project_id: Option<i32>,
+) -> Result<Option<KnowledgeBaseReader>, Status> {
+    let project_role_id = match project_id {
+        Some(project_id) => {
+            // The project must exist and belong to the same company
+            match ProjectQuery::get_project_by_id(conn, project_id).await? {
+                Some(project) if project.company_id == company_id => {}
+                _ => return Ok(None),
+            }
+
+            ProjectQuery::get_user_project(conn, user_id, project_id)
+                .await?
+                .map(|project| project.user_role.id)
+        }
+        None => None,
+    };
+
+    let user_company = CompaniesQuery::get_user_company(conn, user_id, company_id).await?;
+
+    let role_id = project_role_id.or(user_company.as_ref().map(|company| company.role_id));
+
+    Ok(role_id.map(|role_id| KnowledgeBaseReader {
+        role_id,
+        is_company_member: user_company.is_some(),
+    }))
+}
+
+// If user have access to the knowledge base of the company (or project) return the reader role.
+pub async fn check_knowledge_base_permission(
+    conn: &DbConn,
+    user_id: i32,
+    company_id: i32,
+    project_id: Option<i32>,
+) -> Result<KnowledgeBaseReader, Status> {
+    match get_knowledge_base_reader(conn, user_id, company_id, project_id).await? {
+        Some(reader) => Ok(reader),
+        None => Err(Status::permission_denied("permission_denied")),
+    }
+}
+
+// Check whether a reader may see an article:
+// - `full`: everyone with access to the company or project.
+// - `limited`: company members only, project-only collaborators are excluded.
+// - `restricted`: roles allowed to perform `Action::ArticleReadRestricted`.
+// On top of that the reader's role must be at least the article's minimum role (`role_id`).
+pub fn can_read_article(
+    roles: &RoleHierarchy,
+    article: &knowledge_base::Model,
+    reader: &KnowledgeBaseReader,
+) -> bool {
+    let level_allowed = match article.access_level {
+        AccessLevelType::Full => true,
+        AccessLevelType::Limited => reader.is_company_member,
+        AccessLevelType::Restricted => roles.has_level(
+            reader.role_id,
+            Action::ArticleReadRestricted.required_level(),
+        ),
+    };
+
+    let role_allowed = article.role_id.is_none_or(|min_role_id| {
+        roles
+            .level(min_role_id)
+            .is_some_and(|min_level| roles.has_level(reader.role_id, min_level))
+    });
+
+    level_allowed && role_allowed
+}
@@ -0,0 +1,505 @@
+use std::{
+    env,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use core_database::queries::{
+    access_tokens_query::AccessTokensQuery, sessions_query::SessionsQuery,
+};
+use core_debugger::tracing::{event, Level};
+use lazy_static::lazy_static;
+use sea_orm::DatabaseConnection;
+use tonic::{body::BoxBody, Status};
+use tower::{Layer, Service};
+
+use super::{
+    auth_token::{hash_token, SessionClaims, ACCESS_TOKEN_PREFIX},
+    scopes,
+};
+
+/// gRPC methods that can be called without a session token.
+const PUBLIC_METHODS: [&str; 10] = [
+    "/helai_api_core_service.UserService/AuthenticateWithPassword",
+    "/helai_api_core_service.UserService/CompleteTwoFactorLogin",
+    "/helai_api_core_service.UserService/StartOidcLogin",
+    "/helai_api_core_service.UserService/CompleteOidcLogin",
+    "/helai_api_core_service.UserService/RegisterUser",
+    "/helai_api_core_service.UserService/RefreshSessionToken",
+    "/helai_api_core_service.UserService/GetJwks",
+    "/helai_api_core_service.UserService/RequestPasswordReset",
+    "/helai_api_core_service.UserService/ConfirmPasswordReset",
+    "/helai_api_core_service.UserService/VerifyEmail",
+];
+
+/// gRPC methods unverified accounts may not call unless `UNVERIFIED_EMAIL_RESTRICTED_METHODS` says otherwise.
+const DEFAULT_UNVERIFIED_EMAIL_RESTRICTED_METHODS: &str =
+    "CompaniesService/CreateCompany,CompaniesService/AddUserToCompany";
+
+lazy_static! {
+    /// gRPC methods rejected with `Status::permission_denied` until the caller verified their email.
+    ///
+    /// Configured as a comma separated list of `Service/Method` names; an empty value lifts all limits.
+    static ref UNVERIFIED_EMAIL_RESTRICTED_METHODS: Vec<String> =
+        env::var("UNVERIFIED_EMAIL_RESTRICTED_METHODS")
+            .unwrap_or_else(|_| DEFAULT_UNVERIFIED_EMAIL_RESTRICTED_METHODS.to_string())
+            .split(',')
+            .map(|method| method.trim())
+            .filter(|method| !method.is_empty())
+            .map(|method| format!("/helai_api_core_service.{}", method))
+            .collect();
+}
+
+/// The authenticated caller of a request.
+///
+/// Inserted into the request extensions by `AuthLayer` for every method outside of the allow-list.
+#[derive(Clone, Debug)]
+pub struct AuthContext {
+    /// The ID of the authenticated user.
+    pub user_id: i64,
+    /// The unique ID of the token: the `jti` of a session token or the ID of a personal access token.
+    pub token_id: String,
+    /// The ID of the login session the token belongs to; empty for personal access tokens.
+    pub session_id: String,
+    /// Whether the user has verified their email address.
+    pub email_verified: bool,
+    /// The scopes of a personal access token; `None` for session tokens, which may call every method.
+    pub scopes: Option<Vec<String>>,
+}
+
+/// Tower layer validating the bearer token of every incoming gRPC request.
+///
+/// Requests to methods outside of `PUBLIC_METHODS` are rejected with `Status::unauthenticated`
+/// unless they carry a valid session token in the `authorization` header whose login session
+/// is still active, or an active personal access token with a scope covering the method.
+#[derive(Clone)]
+pub struct AuthLayer {
+    connection: Arc<DatabaseConnection>,
+}
+
+impl AuthLayer {
+    pub fn new(connection: Arc<DatabaseConnection>) -> Self {
+        AuthLayer { connection }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            connection: self.connection.clone(),
+        }
+    }
+}
+
+/// Service created by `AuthLayer`.
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    connection: Arc<DatabaseConnection>,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for AuthService<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        // Take the service that was driven to readiness and leave a fresh clone in its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let connection = self.connection.clone();
+
+        Box::pin(async move {
+            if !PUBLIC_METHODS.contains(&request.uri().path()) {
+                match authenticate(&connection, request.headers()).await {
+                    Ok(auth_context) => {
+                        event!(
+                            target: "hellai_app_core_events",
+                            Level::DEBUG,
+                            "Authenticated user {} (token {}, session {}) for {}",
+                            auth_context.user_id,
+                            auth_context.token_id,
+                            auth_context.session_id,
+                            request.uri().path()
+                        );
+
+                        // Personal access tokens may only call the methods their scopes cover
+                        if let Some(granted_scopes) = &auth_context.scopes {
+                            let allowed = scopes::required_scope(request.uri().path())
+                                .is_some_and(|scope| scopes::is_granted(granted_scopes, &scope));
+
+                            if !allowed {
+                                return Ok(
+                                    Status::permission_denied("insufficient_scope").into_http()
+                                );
+                            }
+                        }
+
+                        // Unverified accounts are limited until they confirm their email
+                        if !auth_context.email_verified
+                            && UNVERIFIED_EMAIL_RESTRICTED_METHODS
+                                .iter()
+                                .any(|method| method == request.uri().path())
+                        {
+                            return Ok(Status::permission_denied("email_not_verified").into_http());
+                        }
+
+                        request.extensions_mut().insert(auth_context);
+                    }
+                    Err(status) => {
+                        event!(
+                            target: "hellai_app_core_events",
+                            Level::DEBUG,
+                            "Rejected unauthenticated request to {}: {:?}",
+                            request.uri().path(),
+                            status
+                        );
+
+                        return Ok(status.into_http());
+                    }
+                }
+            }
+
+            inner.call(request).await
+        })
+    }
+}
+
+/// Validates the bearer token from the `authorization` header and checks that its session is still active.
+///
+/// Tokens starting with `ACCESS_TOKEN_PREFIX` are personal access tokens, all others session tokens.
+///
+/// # Arguments
+/// * `connection` - A reference to the database connection.
+/// * `headers` - The HTTP headers of the request.
+///
+/// # Returns
+/// * `Result<AuthContext, Status>` - Returns the authenticated caller.
+///
+/// # Errors
+/// * Returns `Status::unauthenticated` if the header is missing or malformed.
+/// * Returns the token error if the token is invalid or expired.
+/// * Returns `Status::unauthenticated` if the session or personal access token was revoked or has expired.
+/// * Returns `Status::unauthenticated` if the user's account was deactivated.
+async fn authenticate(
+    connection: &DatabaseConnection,
+    headers: &http::HeaderMap,
+) -> Result<AuthContext, Status> {
+    let token = headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| Status::unauthenticated("token_error"))?
+        .replace("Bearer ", "");
+
+    if token.starts_with(ACCESS_TOKEN_PREFIX) {
+        return authenticate_access_token(connection, &token).await;
+    }
+
+    let token_data = SessionClaims::from_token(token)?;
+
+    // The token is only as valid as the login session it was issued for
+    let session_user =
+        match SessionsQuery::touch_session(connection, &token_data.sid, token_data.sub as i32)
+            .await?
+        {
+            Some(session_user) => session_user,
+            None => return Err(Status::unauthenticated("session_revoked")),
+        };
+
+    // Deactivated accounts are locked out even with a valid session
+    if !session_user.is_active {
+        return Err(Status::unauthenticated("account_deactivated"));
+    }
+
+    Ok(AuthContext {
+        user_id: token_data.sub,
+        token_id: token_data.jti,
+        session_id: token_data.sid,
+        email_verified: session_user.email_verified,
+        scopes: None,
+    })
+}
+
+/// Looks up a personal access token by its hash and checks that it is still active.
+///
+/// # Errors
+/// * Returns `Status::unauthenticated` if the token is unknown, revoked or expired.
+/// * Returns `Status::unauthenticated` if the user's account was deactivated.
+async fn authenticate_access_token(
+    connection: &DatabaseConnection,
+    token: &str,
+) -> Result<AuthContext, Status> {
+    let token_user =
+        match AccessTokensQuery::touch_access_token(connection, &hash_token(token)).await? {
+            Some(token_user) => token_user,
+            None => return Err(Status::unauthenticated("invalid_token")),
+        };
+
+    if !token_user.is_active {
+        return Err(Status::unauthenticated("account_deactivated"));
+    }
+
+    Ok(AuthContext {
+        user_id: token_user.user_id as i64,
+        token_id: token_user.token_id.to_string(),
+        session_id: String::new(),
+        email_verified: token_user.email_verified,
+        scopes: Some(
+            token_user
+                .scopes
+                .split_whitespace()
+                .map(|scope| scope.to_string())
+                .collect(),
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, convert::Infallible};
+
+    use sea_orm::{DatabaseBackend, MockDatabase, Value};
+    use tonic::{body::empty_body, Code};
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+    use crate::middleware::auth_token::PersonalAccessToken;
+
+    /// The result of a request that passed `AuthLayer`: the caller the inner service saw.
+    type Outcome = Result<Option<AuthContext>, Status>;
+
+    /// Signs the test tokens with an HS256 secret unless the environment configures other keys.
+    fn use_test_keys() {
+        if env::var("JWT_SIGNING_KEY_FILE").is_err() && env::var("JWT_SECRET_KEY").is_err() {
+            env::set_var("JWT_SECRET_KEY", "auth-layer-test-secret");
+        }
+    }
+
+    /// The row `touch_session` returns for an active session.
+    fn session(email_verified: bool, is_active: bool) -> Vec<BTreeMap<String, Value>> {
+        vec![BTreeMap::from([
+            ("email_verified".to_string(), Value::from(email_verified)),
+            ("is_active".to_string(), Value::from(is_active)),
+        ])]
+    }
+
+    /// The row `touch_access_token` returns for an active personal access token.
+    fn access_token(scopes: &str) -> Vec<BTreeMap<String, Value>> {
+        vec![BTreeMap::from([
+            ("id".to_string(), Value::from(3)),
+            ("user_id".to_string(), Value::from(7)),
+            ("scopes".to_string(), Value::from(scopes)),
+            ("email_verified".to_string(), Value::from(true)),
+            ("is_active".to_string(), Value::from(true)),
+        ])]
+    }
+
+    fn session_token() -> String {
+        use_test_keys();
+
+        SessionClaims::new(7, "session".to_string())
+            .into_token()
+            .unwrap()
+    }
+
+    /// Sends a request through `AuthLayer` to a stand-in gRPC server.
+    ///
+    /// # Arguments
+    /// * `rows` - The rows the database returns for the token lookup, if it is expected to run.
+    /// * `token` - The bearer token of the request.
+    /// * `path` - The gRPC method path.
+    async fn call(
+        rows: Option<Vec<BTreeMap<String, Value>>>,
+        token: Option<&str>,
+        path: &str,
+    ) -> Outcome {
+        use_test_keys();
+
+        let mut db = MockDatabase::new(DatabaseBackend::Postgres);
+        if let Some(rows) = rows {
+            db = db.append_query_results([rows]);
+        }
+        let layer = AuthLayer::new(Arc::new(db.into_connection()));
+
+        let grpc_server = service_fn(|request: http::Request<()>| async move {
+            let mut response = http::Response::new(empty_body());
+            response
+                .extensions_mut()
+                .insert(request.extensions().get::<AuthContext>().cloned());
+
+            Ok::<_, Infallible>(response)
+        });
+
+        let mut request = http::Request::builder().uri(path);
+        if let Some(token) = token {
+            request = request.header(http::header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+
+        let response = layer
+            .layer(grpc_server)
+            .oneshot(request.body(()).unwrap())
+            .await
+            .unwrap();
+
+        match Status::from_header_map(response.headers()) {
+            Some(status) => Err(status),
+            None => Ok(response
+                .extensions()
+                .get::<Option<AuthContext>>()
+                .cloned()
+                .unwrap()),
+        }
+    }
+
+    fn assert_rejected(outcome: Outcome, code: Code, message: &str) {
+        let status = outcome.unwrap_err();
+
+        assert_eq!(status.code(), code);
+        assert_eq!(status.message(), message);
+    }
+
+    #[tokio::test]
+    async fn public_methods_need_no_token() {
+        for path in PUBLIC_METHODS {
+            let caller = call(None, None, path).await.unwrap();
+
+            assert!(caller.is_none(), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn other_methods_need_a_token() {
+        let outcome = call(
+            None,
+            None,
+            "/helai_api_core_service.UserService/GetUserData",
+        )
+        .await;
+
+        assert_rejected(outcome, Code::Unauthenticated, "token_error");
+    }
+
+    #[tokio::test]
+    async fn invalid_session_tokens_are_rejected() {
+        let outcome = call(
+            None,
+            Some("not-a-token"),
+            "/helai_api_core_service.UserService/GetUserData",
+        )
+        .await;
+
+        assert!(outcome.is_err());
+    }
+
+    #[tokio::test]
+    async fn valid_session_tokens_reach_the_service() {
+        let caller = call(
+            Some(session(true, true)),
+            Some(&session_token()),
+            "/helai_api_core_service.UserService/GetUserData",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(caller.user_id, 7);
+        assert_eq!(caller.session_id, "session");
+        assert!(caller.email_verified);
+        assert!(caller.scopes.is_none());
+    }
+
+    #[tokio::test]
+    async fn tokens_of_revoked_sessions_and_deactivated_accounts_are_rejected() {
+        let path = "/helai_api_core_service.UserService/GetUserData";
+
+        let revoked = call(Some(Vec::new()), Some(&session_token()), path).await;
+        let deactivated = call(Some(session(true, false)), Some(&session_token()), path).await;
+
+        assert_rejected(revoked, Code::Unauthenticated, "session_revoked");
+        assert_rejected(deactivated, Code::Unauthenticated, "account_deactivated");
+    }
+
+    #[tokio::test]
+    async fn unverified_accounts_are_limited() {
+        let restricted = call(
+            Some(session(false, true)),
+            Some(&session_token()),
+            "/helai_api_core_service.CompaniesService/CreateCompany",
+        )
+        .await;
+        let unrestricted = call(
+            Some(session(false, true)),
+            Some(&session_token()),
+            "/helai_api_core_service.UserService/GetUserData",
+        )
+        .await;
+
+        assert_rejected(restricted, Code::PermissionDenied, "email_not_verified");
+        assert!(!unrestricted.unwrap().unwrap().email_verified);
+    }
+
+    #[tokio::test]
+    async fn access_tokens_may_call_the_methods_their_scopes_cover() {
+        let token = PersonalAccessToken::generate().token;
+
+        let read = call(
+            Some(access_token("tasks:write")),
+            Some(&token),
+            "/helai_api_core_service.TasksService/GetTask",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(read.user_id, 7);
+        assert_eq!(read.token_id, "3");
+        assert_eq!(read.scopes, Some(vec!["tasks:write".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn access_tokens_are_limited_to_their_scopes() {
+        let token = PersonalAccessToken::generate().token;
+
+        let write = call(
+            Some(access_token("tasks:read notes:write")),
+            Some(&token),
+            "/helai_api_core_service.TasksService/CreateTask",
+        )
+        .await;
+        let account = call(
+            Some(access_token("tasks:write user:read")),
+            Some(&token),
+            "/helai_api_core_service.UserService/ChangePassword",
+        )
+        .await;
+
+        assert_rejected(write, Code::PermissionDenied, "insufficient_scope");
+        assert_rejected(account, Code::PermissionDenied, "insufficient_scope");
+    }
+
+    #[tokio::test]
+    async fn unknown_access_tokens_are_rejected() {
+        let outcome = call(
+            Some(Vec::new()),
+            Some(&PersonalAccessToken::generate().token),
+            "/helai_api_core_service.TasksService/GetTask",
+        )
+        .await;
+
+        assert_rejected(outcome, Code::Unauthenticated, "invalid_token");
+    }
+}
@@ -1,17 +1,75 @@
+use chrono::{DateTime, FixedOffset};
 use core_error::core_errors::CoreErrors;
-use lazy_static::lazy_static;
-use std::env;
 use std::time::SystemTime;
 
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
+use jsonwebtoken::{decode, decode_header, encode, Validation};
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 
-const SESSION_TOKEN_EXP: usize = 86400000; // 24 hours in ms
-const REFRESH_TOKEN_EXP: usize = 2592000000; // 30 days in ms
+use super::{
+    settings::{env_or_default, positive_env_or_default, Setting},
+    signing_keys::SIGNING_KEYS,
+};
 
-lazy_static! {
-    static ref JWT_SECRET: String =
-        env::var("JWT_SECRET_KEY").expect("JWT_SECRET_KEY environment variable is not set");
+const DEFAULT_SESSION_TOKEN_TTL: u64 = 86400; // 24 hours in seconds
+const DEFAULT_REFRESH_TOKEN_TTL: u64 = 2592000; // 30 days in seconds
+const DEFAULT_PASSWORD_RESET_TOKEN_TTL: u64 = 3600; // 1 hour in seconds
+const DEFAULT_EMAIL_VERIFICATION_TOKEN_TTL: u64 = 172800; // 48 hours in seconds
+const DEFAULT_TWO_FACTOR_CHALLENGE_TTL: u64 = 300; // 5 minutes in seconds
+const DEFAULT_COMPANY_INVITATION_TOKEN_TTL: u64 = 604800; // 7 days in seconds
+const DEFAULT_JWT_ISSUER: &str = "hellai-core-service";
+const DEFAULT_JWT_AUDIENCE: &str = "hellai-app";
+
+static JWT_ISSUER: Setting<String> =
+    Setting::new(|| env_or_default("JWT_ISSUER", DEFAULT_JWT_ISSUER.to_string()));
+static JWT_AUDIENCE: Setting<String> =
+    Setting::new(|| env_or_default("JWT_AUDIENCE", DEFAULT_JWT_AUDIENCE.to_string()));
+static SESSION_TOKEN_TTL: Setting<u64> =
+    Setting::new(|| positive_env_or_default("SESSION_TOKEN_TTL_SECS", DEFAULT_SESSION_TOKEN_TTL));
+static REFRESH_TOKEN_TTL: Setting<u64> =
+    Setting::new(|| positive_env_or_default("REFRESH_TOKEN_TTL_SECS", DEFAULT_REFRESH_TOKEN_TTL));
+static PASSWORD_RESET_TOKEN_TTL: Setting<u64> = Setting::new(|| {
+    positive_env_or_default(
+        "PASSWORD_RESET_TOKEN_TTL_SECS",
+        DEFAULT_PASSWORD_RESET_TOKEN_TTL,
+    )
+});
+static EMAIL_VERIFICATION_TOKEN_TTL: Setting<u64> = Setting::new(|| {
+    positive_env_or_default(
+        "EMAIL_VERIFICATION_TOKEN_TTL_SECS",
+        DEFAULT_EMAIL_VERIFICATION_TOKEN_TTL,
+    )
+});
+static TWO_FACTOR_CHALLENGE_TTL: Setting<u64> = Setting::new(|| {
+    positive_env_or_default(
+        "TWO_FACTOR_CHALLENGE_TTL_SECS",
+        DEFAULT_TWO_FACTOR_CHALLENGE_TTL,
+    )
+});
+static COMPANY_INVITATION_TOKEN_TTL: Setting<u64> = Setting::new(|| {
+    positive_env_or_default(
+        "COMPANY_INVITATION_TOKEN_TTL_SECS",
+        DEFAULT_COMPANY_INVITATION_TOKEN_TTL,
+    )
+});
+
+/// Reads the issuer, the audience and the token lifetimes.
+///
+/// # Errors
+/// * Returns `CoreErrors::DataValidationError` if one of them is invalid.
+pub fn init_settings() -> Result<(), CoreErrors> {
+    JWT_ISSUER.init()?;
+    JWT_AUDIENCE.init()?;
+    SESSION_TOKEN_TTL.init()?;
+    REFRESH_TOKEN_TTL.init()?;
+    PASSWORD_RESET_TOKEN_TTL.init()?;
+    EMAIL_VERIFICATION_TOKEN_TTL.init()?;
+    TWO_FACTOR_CHALLENGE_TTL.init()?;
+    COMPANY_INVITATION_TOKEN_TTL.init()?;
+
+    Ok(())
 }
 
 /// Our claims struct, it needs to derive `Serialize` and/or `Deserialize`
@@ -19,38 +77,44 @@ lazy_static! {
 pub struct SessionClaims {
     // The sub depicts the so-called subject, so “who,” in this case (user ID)
     pub sub: i64,
-    // Date when token expires
-    exp: usize,
+    // Who issued the token
+    iss: String,
+    // Who the token is intended for
+    aud: String,
+    // Date when token was issued (seconds since the epoch)
+    iat: u64,
+    // Date before which the token must not be accepted (seconds since the epoch)
+    nbf: u64,
+    // Date when token expires (seconds since the epoch)
+    exp: u64,
+    // Unique token ID
+    pub jti: String,
+    // ID of the login session the token belongs to
+    pub sid: String,
 }
 
 impl SessionClaims {
-    pub fn new(sub: i64) -> SessionClaims {
-        let now: usize = get_now_time_in_ms();
+    pub fn new(sub: i64, sid: String) -> SessionClaims {
+        let now = get_now_time_in_secs();
 
         SessionClaims {
             sub,
-            exp: now + SESSION_TOKEN_EXP,
+            iss: JWT_ISSUER.clone(),
+            aud: JWT_AUDIENCE.clone(),
+            iat: now,
+            nbf: now,
+            exp: now + *SESSION_TOKEN_TTL,
+            jti: Uuid::new_v4().to_string(),
+            sid,
         }
     }
 
     pub fn from_token(token: String) -> Result<SessionClaims, CoreErrors> {
-        let token = decode::<SessionClaims>(
-            &token,
-            &DecodingKey::from_secret(JWT_SECRET.as_ref()),
-            &Validation::default(),
-        )?;
-
-        Ok(token.claims)
+        decode_claims(&token)
     }
 
-    pub fn into_token(&self) -> Result<String, CoreErrors> {
-        let token = encode(
-            &Header::default(),
-            self,
-            &EncodingKey::from_secret(JWT_SECRET.as_ref()),
-        )?;
-
-        Ok(token)
+    pub fn into_token(self) -> Result<String, CoreErrors> {
+        encode_claims(&self)
     }
 }
 
@@ -59,47 +123,370 @@ impl SessionClaims {
 pub struct RefreshClaims {
     // The sub depicts the so-called subject, so “who,” in this case (user ID)
     pub sub: i64,
-    // Date when token expires
-    exp: usize,
+    // Who issued the token
+    iss: String,
+    // Who the token is intended for
+    aud: String,
+    // Date when token was issued (seconds since the epoch)
+    iat: u64,
+    // Date before which the token must not be accepted (seconds since the epoch)
+    nbf: u64,
+    // Date when token expires (seconds since the epoch)
+    exp: u64,
+    // Unique token ID
+    pub jti: String,
 
     is_refresh: bool,
 }
 
 impl RefreshClaims {
     pub fn new(sub: i64) -> RefreshClaims {
-        let now: usize = get_now_time_in_ms();
+        let now = get_now_time_in_secs();
 
         RefreshClaims {
-            exp: now + REFRESH_TOKEN_EXP,
             sub,
+            iss: JWT_ISSUER.clone(),
+            aud: JWT_AUDIENCE.clone(),
+            iat: now,
+            nbf: now,
+            exp: now + *REFRESH_TOKEN_TTL,
+            jti: Uuid::new_v4().to_string(),
             is_refresh: true,
         }
     }
 
     pub fn from_token(token: String) -> Result<RefreshClaims, CoreErrors> {
-        let token = decode::<RefreshClaims>(
-            &token,
-            &DecodingKey::from_secret(JWT_SECRET.as_ref()),
-            &Validation::default(),
-        )?;
+        decode_claims(&token)
+    }
+
+    // Date when token expires
+    pub fn expires_at(&self) -> DateTime<FixedOffset> {
+        timestamp_to_date_time(self.exp)
+    }
+
+    pub fn into_token(self) -> Result<String, CoreErrors> {
+        encode_claims(&self)
+    }
+}
+
+/// Claims of the challenge token issued after the password step of a login with two-factor authentication.
+///
+/// The token only proves the password was correct; it is exchanged for session and refresh tokens
+/// together with a TOTP or recovery code.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwoFactorChallengeClaims {
+    // The sub depicts the so-called subject, so “who,” in this case (user ID)
+    pub sub: i64,
+    // Who issued the token
+    iss: String,
+    // Who the token is intended for
+    aud: String,
+    // Date when token was issued (seconds since the epoch)
+    iat: u64,
+    // Date before which the token must not be accepted (seconds since the epoch)
+    nbf: u64,
+    // Date when token expires (seconds since the epoch)
+    exp: u64,
+    // Unique token ID
+    pub jti: String,
+
+    is_two_factor_challenge: bool,
+}
+
+impl TwoFactorChallengeClaims {
+    pub fn new(sub: i64) -> TwoFactorChallengeClaims {
+        let now = get_now_time_in_secs();
+
+        TwoFactorChallengeClaims {
+            sub,
+            iss: JWT_ISSUER.clone(),
+            aud: JWT_AUDIENCE.clone(),
+            iat: now,
+            nbf: now,
+            exp: now + *TWO_FACTOR_CHALLENGE_TTL,
+            jti: Uuid::new_v4().to_string(),
+            is_two_factor_challenge: true,
+        }
+    }
+
+    pub fn from_token(token: String) -> Result<TwoFactorChallengeClaims, CoreErrors> {
+        decode_claims(&token)
+    }
 
-        Ok(token.claims)
+    pub fn into_token(self) -> Result<String, CoreErrors> {
+        encode_claims(&self)
+    }
+}
+
+/// A random, opaque token that is sent to the user once, e.g. in a password reset or email verification link.
+///
+/// Only `token_hash` is stored, so a leaked database can't be used to redeem the token.
+pub struct OneTimeToken {
+    pub token: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<FixedOffset>,
+}
+
+impl OneTimeToken {
+    /// Generates a password reset token valid for `PASSWORD_RESET_TOKEN_TTL_SECS`.
+    pub fn password_reset() -> OneTimeToken {
+        OneTimeToken::new(*PASSWORD_RESET_TOKEN_TTL)
+    }
+
+    /// Generates an email verification token valid for `EMAIL_VERIFICATION_TOKEN_TTL_SECS`.
+    pub fn email_verification() -> OneTimeToken {
+        OneTimeToken::new(*EMAIL_VERIFICATION_TOKEN_TTL)
     }
 
-    pub fn into_token(&self) -> Result<String, CoreErrors> {
-        let token = encode(
-            &Header::default(),
-            self,
-            &EncodingKey::from_secret(JWT_SECRET.as_ref()),
-        )?;
+    /// Generates a company invitation token valid for `COMPANY_INVITATION_TOKEN_TTL_SECS`.
+    pub fn company_invitation() -> OneTimeToken {
+        OneTimeToken::new(*COMPANY_INVITATION_TOKEN_TTL)
+    }
+
+    fn new(ttl: u64) -> OneTimeToken {
+        let token = random_token();
 
-        return Ok(token);
+        OneTimeToken {
+            token_hash: hash_token(&token),
+            token,
+            expires_at: timestamp_to_date_time(get_now_time_in_secs() + ttl),
+        }
     }
 }
 
-fn get_now_time_in_ms() -> usize {
+/// Prefix of personal access tokens, which tells them apart from session tokens (JWTs).
+pub const ACCESS_TOKEN_PREFIX: &str = "hlp_";
+
+/// A random, opaque personal access token used by scripts and integrations instead of a session token.
+///
+/// Only `token_hash` is stored, so the token is shown to the user once, when it is created.
+pub struct PersonalAccessToken {
+    pub token: String,
+    pub token_hash: String,
+    /// The first characters of the token, which help users recognise it.
+    pub token_prefix: String,
+}
+
+impl PersonalAccessToken {
+    pub fn generate() -> PersonalAccessToken {
+        let token = format!("{}{}", ACCESS_TOKEN_PREFIX, random_token());
+
+        PersonalAccessToken {
+            token_hash: hash_token(&token),
+            token_prefix: token.chars().take(ACCESS_TOKEN_PREFIX.len() + 8).collect(),
+            token,
+        }
+    }
+}
+
+/// Returns the SHA-256 hash of a token, used to store tokens without keeping them in plain text.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Generates 32 random bytes, hex encoded.
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+
+    hex::encode(bytes)
+}
+
+/// Signs the claims with the current signing key.
+fn encode_claims<T: Serialize>(claims: &T) -> Result<String, CoreErrors> {
+    let token = encode(&SIGNING_KEYS.header(), claims, SIGNING_KEYS.encoding_key())?;
+
+    Ok(token)
+}
+
+/// Verifies the signature, lifetime, issuer and audience of a token and returns its claims.
+///
+/// The signature is checked with the verification key named by the token's `kid` header.
+fn decode_claims<T: DeserializeOwned>(token: &str) -> Result<T, CoreErrors> {
+    let (algorithm, decoding_key) = SIGNING_KEYS.decoding_key(&decode_header(token)?)?;
+
+    let mut validation = Validation::new(algorithm);
+    validation.validate_nbf = true;
+    validation.set_issuer(&[JWT_ISSUER.as_str()]);
+    validation.set_audience(&[JWT_AUDIENCE.as_str()]);
+    // `sub` is numeric, which jsonwebtoken never counts as present; serde requires it instead
+    validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud"]);
+
+    let token = decode::<T>(token, decoding_key, &validation)?;
+
+    Ok(token.claims)
+}
+
+/// Converts seconds since the epoch into a date time as stored in the database.
+pub(crate) fn timestamp_to_date_time(timestamp: u64) -> DateTime<FixedOffset> {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .fixed_offset()
+}
+
+pub(crate) fn get_now_time_in_secs() -> u64 {
     SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
-        .as_millis() as usize
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    /// Signs the test tokens with an HS256 secret unless the environment configures other keys.
+    ///
+    /// `SIGNING_KEYS` is loaded once per process, so every test sets the same secret.
+    fn use_test_keys() {
+        if env::var("JWT_SIGNING_KEY_FILE").is_err() && env::var("JWT_SECRET_KEY").is_err() {
+            env::set_var("JWT_SECRET_KEY", "auth-token-test-secret");
+        }
+    }
+
+    fn session_claims(iat: u64, nbf: u64, exp: u64) -> SessionClaims {
+        SessionClaims {
+            sub: 7,
+            iss: JWT_ISSUER.clone(),
+            aud: JWT_AUDIENCE.clone(),
+            iat,
+            nbf,
+            exp,
+            jti: Uuid::new_v4().to_string(),
+            sid: "session".to_string(),
+        }
+    }
+
+    #[test]
+    fn session_tokens_round_trip() {
+        use_test_keys();
+
+        let claims = SessionClaims::new(7, "session".to_string());
+        let jti = claims.jti.clone();
+
+        let decoded = SessionClaims::from_token(claims.into_token().unwrap()).unwrap();
+
+        assert_eq!(decoded.sub, 7);
+        assert_eq!(decoded.sid, "session");
+        assert_eq!(decoded.jti, jti);
+        assert_eq!(decoded.iss, *JWT_ISSUER);
+        assert_eq!(decoded.aud, *JWT_AUDIENCE);
+        assert_eq!(decoded.nbf, decoded.iat);
+        assert_eq!(decoded.exp - decoded.iat, *SESSION_TOKEN_TTL);
+    }
+
+    #[test]
+    fn refresh_tokens_expire_after_the_refresh_lifetime() {
+        use_test_keys();
+
+        let claims = RefreshClaims::new(7);
+        let expires_at = claims.expires_at();
+
+        let decoded = RefreshClaims::from_token(claims.into_token().unwrap()).unwrap();
+
+        assert_eq!(decoded.exp - decoded.iat, *REFRESH_TOKEN_TTL);
+        assert_eq!(expires_at.timestamp(), decoded.exp as i64);
+    }
+
+    #[test]
+    fn every_token_gets_a_new_id() {
+        let first = RefreshClaims::new(7);
+        let second = RefreshClaims::new(7);
+
+        assert_ne!(first.jti, second.jti);
+    }
+
+    #[test]
+    fn tokens_of_one_kind_are_rejected_as_another() {
+        use_test_keys();
+
+        let session_token = SessionClaims::new(7, "session".to_string())
+            .into_token()
+            .unwrap();
+        let refresh_token = RefreshClaims::new(7).into_token().unwrap();
+        let challenge_token = TwoFactorChallengeClaims::new(7).into_token().unwrap();
+
+        assert!(RefreshClaims::from_token(session_token.clone()).is_err());
+        assert!(TwoFactorChallengeClaims::from_token(session_token).is_err());
+        assert!(SessionClaims::from_token(refresh_token.clone()).is_err());
+        assert!(TwoFactorChallengeClaims::from_token(refresh_token).is_err());
+        assert!(SessionClaims::from_token(challenge_token.clone()).is_err());
+        assert!(RefreshClaims::from_token(challenge_token).is_err());
+    }
+
+    #[test]
+    fn expired_and_not_yet_valid_tokens_are_rejected() {
+        use_test_keys();
+
+        let now = get_now_time_in_secs();
+        let expired = session_claims(now - 7200, now - 7200, now - 3600);
+        let not_yet_valid = session_claims(now, now + 3600, now + 7200);
+
+        assert!(SessionClaims::from_token(expired.into_token().unwrap()).is_err());
+        assert!(SessionClaims::from_token(not_yet_valid.into_token().unwrap()).is_err());
+    }
+
+    #[test]
+    fn tokens_of_other_issuers_and_audiences_are_rejected() {
+        use_test_keys();
+
+        let now = get_now_time_in_secs();
+        let mut other_issuer = session_claims(now, now, now + 3600);
+        other_issuer.iss = "someone-else".to_string();
+        let mut other_audience = session_claims(now, now, now + 3600);
+        other_audience.aud = "another-app".to_string();
+
+        assert!(SessionClaims::from_token(other_issuer.into_token().unwrap()).is_err());
+        assert!(SessionClaims::from_token(other_audience.into_token().unwrap()).is_err());
+    }
+
+    #[test]
+    fn tampered_tokens_are_rejected() {
+        use_test_keys();
+
+        let token = SessionClaims::new(7, "session".to_string())
+            .into_token()
+            .unwrap();
+
+        // Swap the payload for one claiming another user, keeping the original signature
+        let forged_claims = session_claims(0, 0, u64::MAX);
+        let forged_token = encode_claims(&SessionClaims {
+            sub: 8,
+            ..forged_claims
+        })
+        .unwrap();
+        let mut parts = token.split('.').collect::<Vec<_>>();
+        parts[1] = forged_token.split('.').nth(1).unwrap();
+
+        assert!(SessionClaims::from_token(parts.join(".")).is_err());
+        assert!(SessionClaims::from_token(format!("{}x", token)).is_err());
+    }
+
+    #[test]
+    fn one_time_tokens_store_only_their_hash() {
+        let reset_token = OneTimeToken::password_reset();
+        let verification_token = OneTimeToken::email_verification();
+
+        assert_eq!(reset_token.token_hash, hash_token(&reset_token.token));
+        assert_ne!(reset_token.token_hash, reset_token.token);
+        assert_ne!(reset_token.token, verification_token.token);
+
+        let lifetime = reset_token.expires_at.timestamp() - get_now_time_in_secs() as i64;
+        assert!((0..=*PASSWORD_RESET_TOKEN_TTL as i64).contains(&lifetime));
+    }
+
+    #[test]
+    fn personal_access_tokens_are_prefixed() {
+        let access_token = PersonalAccessToken::generate();
+
+        assert!(access_token.token.starts_with(ACCESS_TOKEN_PREFIX));
+        assert!(access_token.token.starts_with(&access_token.token_prefix));
+        assert_eq!(
+            access_token.token_prefix.len(),
+            ACCESS_TOKEN_PREFIX.len() + 8
+        );
+        assert_eq!(access_token.token_hash, hash_token(&access_token.token));
+    }
 }
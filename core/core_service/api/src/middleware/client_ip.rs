@@ -0,0 +1,242 @@
+use std::{env, net::IpAddr};
+
+use lazy_static::lazy_static;
+use tonic::Request;
+
+lazy_static! {
+    /// Reverse proxies whose forwarding headers are trusted, from the comma separated `TRUSTED_PROXIES`.
+    static ref TRUSTED_PROXIES: Vec<TrustedProxy> = match env::var("TRUSTED_PROXIES") {
+        Ok(value) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .map(|proxy| {
+                TrustedProxy::parse(proxy).unwrap_or_else(|| {
+                    panic!("TRUSTED_PROXIES environment variable is not valid: {}", proxy)
+                })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+}
+
+/// The address or network of a trusted reverse proxy, e.g. `10.0.0.1` or `10.0.0.0/8`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TrustedProxy {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl TrustedProxy {
+    fn parse(value: &str) -> Option<TrustedProxy> {
+        let (address, prefix_len) = match value.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len.parse::<u8>().ok()?)),
+            None => (value, None),
+        };
+
+        let network = address.parse::<IpAddr>().ok()?.to_canonical();
+        let max_prefix_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_prefix_len);
+
+        if prefix_len > max_prefix_len {
+            return None;
+        }
+
+        Some(TrustedProxy {
+            network,
+            prefix_len,
+        })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns the IP address of the client that sent a request.
+///
+/// This is the peer address of the connection. The `x-forwarded-for` and `x-real-ip` headers are
+/// only honoured if the peer is one of the `TRUSTED_PROXIES`, since any client can set them.
+///
+/// # Arguments
+/// * `request` - The incoming gRPC request.
+///
+/// # Returns
+/// * `Option<IpAddr>` - The IP address of the client, or `None` if the peer address is unknown.
+pub fn client_ip<T>(request: &Request<T>) -> Option<IpAddr> {
+    let peer = request.remote_addr()?.ip();
+
+    // A header may be sent more than once, each proxy appending to the last one
+    let forwarded_for = request
+        .metadata()
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect::<Vec<_>>()
+        .join(",");
+    let real_ip = request
+        .metadata()
+        .get("x-real-ip")
+        .and_then(|value| value.to_str().ok());
+
+    Some(resolve_client_ip(
+        peer,
+        Some(forwarded_for.as_str()).filter(|value| !value.is_empty()),
+        real_ip,
+        &TRUSTED_PROXIES,
+    ))
+}
+
+/// Resolves the client address from the peer address and the forwarding headers.
+///
+/// `x-forwarded-for` is walked from the right, since every trusted proxy appends the address it
+/// received the request from; the first hop that is not a trusted proxy is the client.
+fn resolve_client_ip(
+    peer: IpAddr,
+    forwarded_for: Option<&str>,
+    real_ip: Option<&str>,
+    trusted_proxies: &[TrustedProxy],
+) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+
+    let peer = peer.to_canonical();
+
+    if !is_trusted(peer) {
+        return peer;
+    }
+
+    if let Some(forwarded_for) = forwarded_for {
+        let mut client = peer;
+
+        for hop in forwarded_for.rsplit(',') {
+            // Hops left of a malformed entry can't be attributed to a trusted proxy
+            let Ok(hop) = hop.trim().parse::<IpAddr>() else {
+                break;
+            };
+
+            client = hop.to_canonical();
+
+            if !is_trusted(client) {
+                break;
+            }
+        }
+
+        return client;
+    }
+
+    real_ip
+        .and_then(|real_ip| real_ip.trim().parse::<IpAddr>().ok())
+        .map(|real_ip| real_ip.to_canonical())
+        .unwrap_or(peer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn proxies(values: &[&str]) -> Vec<TrustedProxy> {
+        values
+            .iter()
+            .map(|value| TrustedProxy::parse(value).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn ignores_forwarding_headers_of_untrusted_peers() {
+        let trusted = proxies(&["10.0.0.1"]);
+
+        let client = resolve_client_ip(
+            ip("203.0.113.7"),
+            Some("198.51.100.1"),
+            Some("198.51.100.2"),
+            &trusted,
+        );
+
+        assert_eq!(client, ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn ignores_forwarding_headers_without_trusted_proxies() {
+        let client = resolve_client_ip(ip("10.0.0.1"), Some("198.51.100.1"), None, &[]);
+
+        assert_eq!(client, ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn takes_the_right_most_untrusted_hop() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+
+        // The left-most entries were sent by the client and can't be trusted
+        let client = resolve_client_ip(
+            ip("10.0.0.1"),
+            Some("1.2.3.4, 198.51.100.1, 10.0.0.2"),
+            None,
+            &trusted,
+        );
+
+        assert_eq!(client, ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn stops_at_malformed_hops() {
+        let trusted = proxies(&["10.0.0.1", "10.0.0.2"]);
+
+        let client = resolve_client_ip(
+            ip("10.0.0.1"),
+            Some("198.51.100.1, garbage, 10.0.0.2"),
+            None,
+            &trusted,
+        );
+
+        assert_eq!(client, ip("10.0.0.2"));
+    }
+
+    #[test]
+    fn falls_back_to_the_real_ip_header_of_trusted_peers() {
+        let trusted = proxies(&["10.0.0.1"]);
+
+        let client = resolve_client_ip(ip("10.0.0.1"), None, Some("198.51.100.1"), &trusted);
+
+        assert_eq!(client, ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn matches_networks_and_mapped_addresses() {
+        let network = TrustedProxy::parse("192.168.0.0/16").unwrap();
+
+        assert!(network.contains(ip("192.168.10.20")));
+        assert!(network.contains(ip("::ffff:192.168.10.20")));
+        assert!(!network.contains(ip("192.169.0.1")));
+        assert!(TrustedProxy::parse("0.0.0.0/0")
+            .unwrap()
+            .contains(ip("8.8.8.8")));
+        assert!(TrustedProxy::parse("fd00::/8")
+            .unwrap()
+            .contains(ip("fd12::1")));
+    }
+
+    #[test]
+    fn rejects_invalid_proxies() {
+        assert_eq!(TrustedProxy::parse("10.0.0.0/33"), None);
+        assert_eq!(TrustedProxy::parse("proxy.local"), None);
+        assert_eq!(TrustedProxy::parse("10.0.0.0/x"), None);
+    }
+}
@@ -1,21 +1,16 @@
-use tonic::{metadata::MetadataMap, Status};
+use tonic::{Request, Status};
 
-use super::auth_token::SessionClaims;
+use super::auth_layer::AuthContext;
 
-pub fn check_auth_token(metadata: &MetadataMap) -> Result<i64, Status> {
-    match metadata.get("authorization") {
-        Some(t) => {
-            let token_from_header = t.to_str();
-            match token_from_header {
-                Ok(t) => {
-                    let t = t.replace("Bearer ", "");
-                    let token_data = SessionClaims::from_token(t.to_string())?;
-
-                    Ok(token_data.sub)
-                }
-                Err(_) => Err(Status::unauthenticated("token_error")),
-            }
-        }
-        _ => Err(Status::unauthenticated("token_error")),
-    }
+/// Returns the authenticated caller injected by `AuthLayer`.
+///
+/// # Errors
+/// * Returns `Status::unauthenticated` if the request was not authenticated,
+///   e.g. because the method is on the layer's allow-list.
+#[allow(clippy::result_large_err)]
+pub fn auth_context<T>(request: &Request<T>) -> Result<&AuthContext, Status> {
+    request
+        .extensions()
+        .get::<AuthContext>()
+        .ok_or_else(|| Status::unauthenticated("token_error"))
 }
@@ -0,0 +1,125 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tonic::body::{boxed, BoxBody};
+use tower::{Layer, Service};
+
+use super::signing_keys::SIGNING_KEYS;
+
+/// HTTP path the public token verification keys are served at.
+pub const JWKS_PATH: &str = "/.well-known/jwks.json";
+
+/// Tower layer serving the JWKS as plain JSON over HTTP.
+///
+/// Must be applied outside of `GrpcWebLayer`, which rejects plain HTTP/1.1 requests.
+#[derive(Clone, Debug, Default)]
+pub struct JwksLayer;
+
+impl<S> Layer<S> for JwksLayer {
+    type Service = JwksService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        JwksService { inner }
+    }
+}
+
+/// Service created by `JwksLayer`.
+#[derive(Clone, Debug)]
+pub struct JwksService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for JwksService<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        if request.method() != http::Method::GET || request.uri().path() != JWKS_PATH {
+            return Box::pin(self.inner.call(request));
+        }
+
+        let body = serde_json::to_string(SIGNING_KEYS.jwks()).unwrap_or_default();
+        let mut response = http::Response::new(boxed(body));
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/json"),
+        );
+
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, env};
+
+    use http_body_util::BodyExt;
+    use tonic::body::empty_body;
+    use tower::{service_fn, ServiceExt};
+
+    use super::*;
+
+    /// Sends a request through `JwksLayer` to a stand-in gRPC server answering with `418`.
+    async fn call(method: http::Method, path: &str) -> http::Response<BoxBody> {
+        if env::var("JWT_SIGNING_KEY_FILE").is_err() && env::var("JWT_SECRET_KEY").is_err() {
+            env::set_var("JWT_SECRET_KEY", "jwks-layer-test-secret");
+        }
+
+        let grpc_server = service_fn(|_: http::Request<()>| async {
+            let mut response = http::Response::new(empty_body());
+            *response.status_mut() = http::StatusCode::IM_A_TEAPOT;
+
+            Ok::<_, Infallible>(response)
+        });
+        let request = http::Request::builder()
+            .method(method)
+            .uri(path)
+            .body(())
+            .unwrap();
+
+        JwksLayer.layer(grpc_server).oneshot(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn the_jwks_is_served_as_json() {
+        let response = call(http::Method::GET, JWKS_PATH).await;
+
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(
+            response.headers()[http::header::CONTENT_TYPE],
+            "application/json"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let jwks: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(jwks, serde_json::to_value(SIGNING_KEYS.jwks()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn other_requests_reach_the_inner_service() {
+        for (method, path) in [
+            (http::Method::POST, JWKS_PATH),
+            (http::Method::GET, "/.well-known/openid-configuration"),
+            (
+                http::Method::POST,
+                "/helai_api_core_service.UserService/GetUserData",
+            ),
+        ] {
+            let response = call(method, path).await;
+
+            assert_eq!(response.status(), http::StatusCode::IM_A_TEAPOT);
+        }
+    }
+}
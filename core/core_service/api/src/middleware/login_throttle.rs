@@ -0,0 +1,309 @@
+use chrono::Utc;
+use core_database::queries::login_attempts_query::LoginAttemptsQuery;
+use core_debugger::tracing::{event, Level};
+use core_error::core_errors::CoreErrors;
+use sea_orm::DbConn;
+use tonic::Status;
+
+use super::settings::{positive_env_or_default, Setting};
+
+const DEFAULT_LOGIN_LOCKOUT_THRESHOLD: i32 = 5;
+const DEFAULT_IP_LOCKOUT_THRESHOLD: i32 = 50;
+const DEFAULT_LOCKOUT_BASE_SECS: i64 = 30;
+const DEFAULT_LOCKOUT_MAX_SECS: i64 = 3600; // 1 hour in seconds
+const DEFAULT_FAILURE_WINDOW_SECS: i64 = 86400; // 24 hours in seconds
+
+static LOGIN_LOCKOUT_THRESHOLD: Setting<i32> = Setting::new(|| {
+    positive_env_or_default("LOGIN_LOCKOUT_THRESHOLD", DEFAULT_LOGIN_LOCKOUT_THRESHOLD)
+});
+static IP_LOCKOUT_THRESHOLD: Setting<i32> = Setting::new(|| {
+    positive_env_or_default("LOGIN_IP_LOCKOUT_THRESHOLD", DEFAULT_IP_LOCKOUT_THRESHOLD)
+});
+static LOCKOUT_BASE_SECS: Setting<i64> =
+    Setting::new(|| positive_env_or_default("LOGIN_LOCKOUT_BASE_SECS", DEFAULT_LOCKOUT_BASE_SECS));
+static LOCKOUT_MAX_SECS: Setting<i64> =
+    Setting::new(|| positive_env_or_default("LOGIN_LOCKOUT_MAX_SECS", DEFAULT_LOCKOUT_MAX_SECS));
+static FAILURE_WINDOW_SECS: Setting<i64> = Setting::new(|| {
+    positive_env_or_default("LOGIN_FAILURE_WINDOW_SECS", DEFAULT_FAILURE_WINDOW_SECS)
+});
+
+/// Reads the lockout thresholds and durations.
+///
+/// # Errors
+/// * Returns `CoreErrors::DataValidationError` if one of them is invalid.
+pub fn init_settings() -> Result<(), CoreErrors> {
+    LOGIN_LOCKOUT_THRESHOLD.init()?;
+    IP_LOCKOUT_THRESHOLD.init()?;
+    LOCKOUT_BASE_SECS.init()?;
+    LOCKOUT_MAX_SECS.init()?;
+    FAILURE_WINDOW_SECS.init()?;
+
+    Ok(())
+}
+
+/// Rejects a login attempt while the login or the IP address of the client is locked out.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `login` - The login as entered by the client.
+/// * `ip_address` - The IP address of the client, if known.
+///
+/// # Errors
+/// * Returns `Status::resource_exhausted` with a `retry-after` header (in seconds) during a lockout.
+pub async fn check_login_allowed(
+    conn: &DbConn,
+    login: &str,
+    ip_address: Option<&str>,
+) -> Result<(), Status> {
+    let throttle_keys = [Some(login_key(login)), ip_address.map(ip_key)]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    if let Some(locked_until) = LoginAttemptsQuery::get_locked_until(conn, throttle_keys).await? {
+        let retry_after = (locked_until.to_utc() - Utc::now()).num_seconds().max(1);
+
+        let mut status = Status::resource_exhausted("too_many_login_attempts");
+        status
+            .metadata_mut()
+            .insert("retry-after", retry_after.into());
+
+        return Err(status);
+    }
+
+    Ok(())
+}
+
+/// Audits a failed login attempt and counts it against the login and the IP address of the client.
+///
+/// Once a counter reaches its threshold, the login or IP address is locked out for
+/// `LOGIN_LOCKOUT_BASE_SECS`, doubling with every further failure up to `LOGIN_LOCKOUT_MAX_SECS`.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `login` - The login as entered by the client.
+/// * `user_id` - The ID of the user with this login, if one exists.
+/// * `ip_address` - The IP address of the client, if known.
+/// * `device_info` - A description of the client, if known.
+/// * `reason` - Why the attempt failed, e.g. `invalid_password`.
+pub async fn record_login_failure(
+    conn: &DbConn,
+    login: &str,
+    user_id: Option<i32>,
+    ip_address: Option<String>,
+    device_info: Option<String>,
+    reason: &str,
+) -> Result<(), CoreErrors> {
+    LoginAttemptsQuery::record_failed_attempt(
+        conn,
+        login.to_string(),
+        user_id,
+        ip_address.clone(),
+        device_info,
+        reason,
+    )
+    .await?;
+
+    register_failure(conn, login_key(login), *LOGIN_LOCKOUT_THRESHOLD).await?;
+
+    if let Some(ip_address) = ip_address {
+        register_failure(conn, ip_key(&ip_address), *IP_LOCKOUT_THRESHOLD).await?;
+    }
+
+    Ok(())
+}
+
+/// Resets the failed attempts of a login after the user signed in successfully.
+///
+/// Failures of the IP address are kept, so signing in to one account doesn't lift the
+/// lockout of an address that is guessing the passwords of others.
+pub async fn record_login_success(conn: &DbConn, login: &str) -> Result<(), CoreErrors> {
+    LoginAttemptsQuery::clear_failures(conn, login_key(login)).await
+}
+
+/// Counts a failure against a throttle key and locks the key out once it reaches the threshold.
+async fn register_failure(
+    conn: &DbConn,
+    throttle_key: String,
+    threshold: i32,
+) -> Result<(), CoreErrors> {
+    let failed_attempts =
+        LoginAttemptsQuery::register_failure(conn, throttle_key.clone(), *FAILURE_WINDOW_SECS)
+            .await?;
+
+    if failed_attempts >= threshold {
+        let lockout_secs = lockout_secs(failed_attempts - threshold);
+
+        event!(target: "hellai_app_core_events", Level::WARN, "Locking out {} for {}s after {} failed login attempts", throttle_key, lockout_secs, failed_attempts);
+
+        LoginAttemptsQuery::lock(conn, throttle_key, lockout_secs).await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the duration of a lockout, doubling with every failure past the threshold.
+fn lockout_secs(failures_past_threshold: i32) -> i64 {
+    let factor = 1i64 << failures_past_threshold.clamp(0, 62);
+
+    LOCKOUT_BASE_SECS
+        .saturating_mul(factor)
+        .min(*LOCKOUT_MAX_SECS)
+}
+
+fn login_key(login: &str) -> String {
+    format!("login:{}", login.to_lowercase())
+}
+
+fn ip_key(ip_address: &str) -> String {
+    format!("ip:{}", ip_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Duration;
+    use core_database::{
+        entity::{login_attempts, login_throttles},
+        test_support::executed_statements,
+    };
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Value};
+    use tonic::Code;
+
+    use super::*;
+
+    /// The row returned by the upsert counting a failure.
+    fn failed_attempts(count: i32) -> Vec<BTreeMap<String, Value>> {
+        vec![BTreeMap::from([(
+            "failed_attempts".to_string(),
+            Value::from(count),
+        )])]
+    }
+
+    fn locked() -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }
+    }
+
+    fn attempt() -> login_attempts::Model {
+        login_attempts::Model {
+            id: 1,
+            login: "alice".to_string(),
+            user_id: None,
+            ip_address: None,
+            device_info: None,
+            reason: "invalid_password".to_string(),
+            created_at: Default::default(),
+        }
+    }
+
+    #[test]
+    fn lockouts_double_with_every_failure_up_to_the_maximum() {
+        assert_eq!(lockout_secs(0), *LOCKOUT_BASE_SECS);
+        assert_eq!(lockout_secs(1), *LOCKOUT_BASE_SECS * 2);
+        assert_eq!(lockout_secs(2), *LOCKOUT_BASE_SECS * 4);
+        assert_eq!(lockout_secs(1000), *LOCKOUT_MAX_SECS);
+        assert_eq!(lockout_secs(-1), *LOCKOUT_BASE_SECS);
+    }
+
+    #[test]
+    fn logins_are_throttled_regardless_of_case() {
+        assert_eq!(login_key("Alice"), login_key("alice"));
+        assert_ne!(login_key("alice"), ip_key("alice"));
+    }
+
+    #[tokio::test]
+    async fn failures_below_the_threshold_do_not_lock() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([failed_attempts(*LOGIN_LOCKOUT_THRESHOLD - 1)])
+            .into_connection();
+
+        register_failure(&db, login_key("alice"), *LOGIN_LOCKOUT_THRESHOLD)
+            .await
+            .unwrap();
+
+        assert_eq!(executed_statements(db).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failures_from_the_threshold_on_lock_the_key() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([failed_attempts(*LOGIN_LOCKOUT_THRESHOLD + 1)])
+            .append_exec_results([locked()])
+            .into_connection();
+
+        register_failure(&db, login_key("alice"), *LOGIN_LOCKOUT_THRESHOLD)
+            .await
+            .unwrap();
+
+        let statements = executed_statements(db);
+        assert_eq!(statements.len(), 2);
+        let (sql, values) = &statements[1];
+        assert!(sql.contains(r#"UPDATE "login_throttles""#));
+        assert!(values.contains(&Value::from(lockout_secs(1) as f64)));
+        assert!(values.contains(&Value::from(login_key("alice"))));
+    }
+
+    #[tokio::test]
+    async fn failures_count_against_the_login_and_the_ip_address() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![attempt()]])
+            .append_query_results([failed_attempts(1), failed_attempts(1)])
+            .into_connection();
+
+        record_login_failure(
+            &db,
+            "Alice",
+            None,
+            Some("203.0.113.7".to_string()),
+            None,
+            "invalid_password",
+        )
+        .await
+        .unwrap();
+
+        let statements = executed_statements(db);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].0.contains(r#"INSERT INTO "login_attempts""#));
+        assert!(statements[1].1.contains(&Value::from("login:alice")));
+        assert!(statements[2].1.contains(&Value::from("ip:203.0.113.7")));
+    }
+
+    #[tokio::test]
+    async fn locked_out_clients_are_told_when_to_retry() {
+        let throttle = login_throttles::Model {
+            throttle_key: ip_key("203.0.113.7"),
+            failed_attempts: *IP_LOCKOUT_THRESHOLD,
+            last_failed_at: Default::default(),
+            locked_until: Some((Utc::now() + Duration::seconds(90)).fixed_offset()),
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![throttle]])
+            .into_connection();
+
+        let status = check_login_allowed(&db, "alice", Some("203.0.113.7"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        let retry_after: i64 = status
+            .metadata()
+            .get("retry-after")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .unwrap();
+        assert!((1..=90).contains(&retry_after));
+    }
+
+    #[tokio::test]
+    async fn clients_without_a_lockout_may_log_in() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<login_throttles::Model>::new()])
+            .into_connection();
+
+        assert!(check_login_allowed(&db, "alice", None).await.is_ok());
+    }
+}
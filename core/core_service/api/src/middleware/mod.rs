@@ -1,4 +1,14 @@
 pub mod access_check;
+pub mod auth_layer;
 pub mod auth_token;
+pub mod client_ip;
 pub mod interceptors;
+pub mod jwks_layer;
+pub mod login_throttle;
+pub mod oidc;
+pub mod policy;
+pub mod scopes;
+pub mod settings;
+pub mod signing_keys;
+pub mod trash_purge;
 pub mod validators;
@@ -0,0 +1,393 @@
+use std::collections::{HashMap, HashSet};
+
+use core_database::{
+    entity::roles,
+    queries::roles_query::{RolesQuery, ADMINISTRATOR_LEVEL, MANAGER_LEVEL, OWNER_LEVEL},
+};
+use core_debugger::tracing::{event, Level};
+use core_error::core_errors::CoreErrors;
+use sea_orm::DbConn;
+
+/// Named actions that can be performed on companies, projects, tasks and their content.
+///
+/// Every action maps to the least privileged role level that may perform it,
+/// so services never compare role IDs directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    CompanyUpdate,
+    CompanyChangeAlias,
+    CompanyDelete,
+    CompanyTransferOwnership,
+    MemberInvite,
+    MemberRemove,
+    MemberChangeRole,
+    ProjectCreate,
+    ProjectUpdate,
+    ProjectArchive,
+    ProjectDelete,
+    ProjectMemberAdd,
+    ProjectMemberRemove,
+    TaskCreate,
+    TaskUpdate,
+    TaskChangeStatus,
+    TaskDelete,
+    TaskMemberAdd,
+    TaskMemberRemove,
+    SubtaskCreate,
+    SubtaskUpdate,
+    SubtaskAssign,
+    SubtaskUnassign,
+    SubtaskChangeStatus,
+    SubtaskDelete,
+    NoteCreate,
+    ArticleWrite,
+    ArticleDelete,
+    ArticleReadRestricted,
+    TrashRestore,
+}
+
+impl Action {
+    /// Returns the dotted name of the action, used in logs.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::CompanyUpdate => "company.update",
+            Action::CompanyChangeAlias => "company.change_alias",
+            Action::CompanyDelete => "company.delete",
+            Action::CompanyTransferOwnership => "company.transfer_ownership",
+            Action::MemberInvite => "member.invite",
+            Action::MemberRemove => "member.remove",
+            Action::MemberChangeRole => "member.change_role",
+            Action::ProjectCreate => "project.create",
+            Action::ProjectUpdate => "project.update",
+            Action::ProjectArchive => "project.archive",
+            Action::ProjectDelete => "project.delete",
+            Action::ProjectMemberAdd => "project.member.add",
+            Action::ProjectMemberRemove => "project.member.remove",
+            Action::TaskCreate => "task.create",
+            Action::TaskUpdate => "task.update",
+            Action::TaskChangeStatus => "task.change_status",
+            Action::TaskDelete => "task.delete",
+            Action::TaskMemberAdd => "task.member.add",
+            Action::TaskMemberRemove => "task.member.remove",
+            Action::SubtaskCreate => "subtask.create",
+            Action::SubtaskUpdate => "subtask.update",
+            Action::SubtaskAssign => "subtask.assign",
+            Action::SubtaskUnassign => "subtask.unassign",
+            Action::SubtaskChangeStatus => "subtask.change_status",
+            Action::SubtaskDelete => "subtask.delete",
+            Action::NoteCreate => "note.create",
+            Action::ArticleWrite => "article.write",
+            Action::ArticleDelete => "article.delete",
+            Action::ArticleReadRestricted => "article.read_restricted",
+            Action::TrashRestore => "trash.restore",
+        }
+    }
+
+    /// Returns the level of the least privileged role allowed to perform the action.
+    pub fn required_level(self) -> i32 {
+        match self {
+            Action::CompanyChangeAlias
+            | Action::CompanyDelete
+            | Action::CompanyTransferOwnership
+            | Action::ProjectDelete
+            | Action::TaskDelete => OWNER_LEVEL,
+            Action::CompanyUpdate
+            | Action::MemberInvite
+            | Action::MemberRemove
+            | Action::MemberChangeRole
+            | Action::ProjectArchive
+            | Action::ProjectMemberAdd
+            | Action::ProjectMemberRemove
+            | Action::TaskMemberAdd
+            | Action::TaskMemberRemove
+            | Action::SubtaskDelete
+            | Action::NoteCreate
+            | Action::ArticleDelete
+            | Action::ArticleReadRestricted
+            | Action::TrashRestore => ADMINISTRATOR_LEVEL,
+            Action::ProjectCreate
+            | Action::ProjectUpdate
+            | Action::TaskCreate
+            | Action::TaskUpdate
+            | Action::TaskChangeStatus
+            | Action::SubtaskCreate
+            | Action::SubtaskUpdate
+            | Action::SubtaskAssign
+            | Action::SubtaskUnassign
+            | Action::SubtaskChangeStatus
+            | Action::ArticleWrite => MANAGER_LEVEL,
+        }
+    }
+
+    /// Returns whether the owner of the resource (e.g. the assignee of a task)
+    /// may perform the action regardless of their role.
+    pub fn allows_owner(self) -> bool {
+        matches!(
+            self,
+            Action::TaskChangeStatus | Action::SubtaskUnassign | Action::SubtaskChangeStatus
+        )
+    }
+}
+
+/// The user performing an action together with their role in the resource's scope.
+#[derive(Clone, Copy, Debug)]
+pub struct Principal {
+    pub user_id: i32,
+    pub role_id: i32,
+}
+
+impl Principal {
+    pub fn new(user_id: i32, role_id: i32) -> Self {
+        Principal { user_id, role_id }
+    }
+}
+
+/// The resource an action is performed on.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Resource {
+    /// The user owning the resource, e.g. the assignee of a task.
+    pub owner_id: Option<i32>,
+}
+
+impl Resource {
+    pub fn owned_by(owner_id: Option<i32>) -> Self {
+        Resource { owner_id }
+    }
+}
+
+/// Role levels and parent relations loaded from the `roles` table.
+///
+/// A role inherits the privileges of its parent, so its effective level is the lowest
+/// (most privileged) level found while walking up the `parent_role_id` chain.
+#[derive(Clone, Debug, Default)]
+pub struct RoleHierarchy {
+    roles: HashMap<i32, RoleNode>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RoleNode {
+    level: i32,
+    parent_role_id: Option<i32>,
+}
+
+impl RoleHierarchy {
+    /// Builds the hierarchy from role records.
+    pub fn new(roles: Vec<roles::Model>) -> Self {
+        let roles = roles
+            .into_iter()
+            .map(|role| {
+                (
+                    role.id,
+                    RoleNode {
+                        level: role.level,
+                        parent_role_id: role.parent_role_id,
+                    },
+                )
+            })
+            .collect();
+
+        RoleHierarchy { roles }
+    }
+
+    /// Loads the hierarchy from the database.
+    pub async fn load(conn: &DbConn) -> Result<Self, CoreErrors> {
+        Ok(Self::new(RolesQuery::get_all_roles(conn).await?))
+    }
+
+    /// Returns the level stored for a role, ignoring its parents.
+    pub fn level(&self, role_id: i32) -> Option<i32> {
+        self.roles.get(&role_id).map(|role| role.level)
+    }
+
+    /// Returns the effective level of a role, taking inherited privileges into account.
+    ///
+    /// Returns `None` if the role is unknown.
+    pub fn effective_level(&self, role_id: i32) -> Option<i32> {
+        let mut role = self.roles.get(&role_id)?;
+        let mut level = role.level;
+        let mut visited = HashSet::from([role_id]);
+
+        // Walk up the parent chain, guarding against cycles
+        while let Some(parent_id) = role.parent_role_id {
+            if !visited.insert(parent_id) {
+                break;
+            }
+
+            match self.roles.get(&parent_id) {
+                Some(parent) => {
+                    level = level.min(parent.level);
+                    role = parent;
+                }
+                None => break,
+            }
+        }
+
+        Some(level)
+    }
+
+    /// Returns whether a role is strictly more privileged than another role.
+    ///
+    /// Unknown roles never outrank and are always outranked.
+    pub fn outranks(&self, role_id: i32, other_role_id: i32) -> bool {
+        match (
+            self.effective_level(role_id),
+            self.effective_level(other_role_id),
+        ) {
+            (Some(level), Some(other_level)) => level < other_level,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Returns whether a role is at least as privileged as the given level.
+    pub fn has_level(&self, role_id: i32, required_level: i32) -> bool {
+        self.effective_level(role_id)
+            .is_some_and(|level| level <= required_level)
+    }
+}
+
+/// Checks whether a principal may perform an action on a resource.
+///
+/// Access is granted if the principal's effective role level meets the action's required level,
+/// or if the action allows owners and the principal owns the resource.
+///
+/// # Errors
+/// * Returns `CoreErrors::PermissionDenied` if the action is not allowed.
+pub fn authorize(
+    roles: &RoleHierarchy,
+    principal: &Principal,
+    action: Action,
+    resource: &Resource,
+) -> Result<(), CoreErrors> {
+    let is_owner = action.allows_owner() && resource.owner_id == Some(principal.user_id);
+
+    if is_owner || roles.has_level(principal.role_id, action.required_level()) {
+        return Ok(());
+    }
+
+    event!(
+        target: "hellai_app_core_events",
+        Level::DEBUG,
+        "Permission denied: user {} with role {} may not perform {}",
+        principal.user_id,
+        principal.role_id,
+        action.name()
+    );
+
+    Err(CoreErrors::PermissionDenied(
+        "permission_denied".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use core_database::queries::roles_query::{GUEST_LEVEL, USER_LEVEL};
+
+    use super::*;
+
+    const OWNER: i32 = 1;
+    const ADMINISTRATOR: i32 = 2;
+    const MANAGER: i32 = 3;
+    const USER: i32 = 4;
+    const GUEST: i32 = 6;
+    // A custom role with guest level that inherits from the administrator role
+    const DEPUTY: i32 = 7;
+    // Two roles whose parents point at each other
+    const CYCLE_A: i32 = 8;
+    const CYCLE_B: i32 = 9;
+
+    fn role(id: i32, level: i32, parent_role_id: Option<i32>) -> roles::Model {
+        roles::Model {
+            id,
+            name: format!("role-{}", id),
+            description: None,
+            parent_role_id,
+            level,
+        }
+    }
+
+    fn hierarchy() -> RoleHierarchy {
+        RoleHierarchy::new(vec![
+            role(OWNER, OWNER_LEVEL, None),
+            role(ADMINISTRATOR, ADMINISTRATOR_LEVEL, None),
+            role(MANAGER, MANAGER_LEVEL, None),
+            role(USER, USER_LEVEL, None),
+            role(GUEST, GUEST_LEVEL, None),
+            role(DEPUTY, GUEST_LEVEL, Some(ADMINISTRATOR)),
+            role(CYCLE_A, USER_LEVEL, Some(CYCLE_B)),
+            role(CYCLE_B, GUEST_LEVEL, Some(CYCLE_A)),
+        ])
+    }
+
+    #[test]
+    fn effective_level_inherits_the_parent_privileges() {
+        let roles = hierarchy();
+
+        assert_eq!(roles.level(DEPUTY), Some(GUEST_LEVEL));
+        assert_eq!(roles.effective_level(DEPUTY), Some(ADMINISTRATOR_LEVEL));
+        assert_eq!(roles.effective_level(MANAGER), Some(MANAGER_LEVEL));
+        assert_eq!(roles.effective_level(42), None);
+    }
+
+    #[test]
+    fn effective_level_stops_at_cycles() {
+        let roles = hierarchy();
+
+        assert_eq!(roles.effective_level(CYCLE_A), Some(USER_LEVEL));
+        assert_eq!(roles.effective_level(CYCLE_B), Some(USER_LEVEL));
+    }
+
+    #[test]
+    fn outranks_compares_effective_levels() {
+        let roles = hierarchy();
+
+        assert!(roles.outranks(OWNER, ADMINISTRATOR));
+        assert!(roles.outranks(DEPUTY, MANAGER));
+        assert!(!roles.outranks(DEPUTY, ADMINISTRATOR));
+        assert!(roles.outranks(GUEST, 42));
+        assert!(!roles.outranks(42, GUEST));
+    }
+
+    /// Whether a user with the role may perform the action on a resource they don't own.
+    fn allows(roles: &RoleHierarchy, role_id: i32, action: Action) -> bool {
+        authorize(
+            roles,
+            &Principal::new(1, role_id),
+            action,
+            &Resource::default(),
+        )
+        .is_ok()
+    }
+
+    #[test]
+    fn authorize_requires_the_action_level() {
+        let roles = hierarchy();
+
+        assert!(allows(&roles, OWNER, Action::CompanyDelete));
+        assert!(!allows(&roles, ADMINISTRATOR, Action::CompanyDelete));
+        assert!(allows(&roles, MANAGER, Action::TaskUpdate));
+        assert!(!allows(&roles, USER, Action::TaskUpdate));
+        assert!(!allows(&roles, 42, Action::TaskUpdate));
+    }
+
+    #[test]
+    fn authorize_grants_inherited_privileges() {
+        let roles = hierarchy();
+
+        assert!(allows(&roles, DEPUTY, Action::MemberInvite));
+        assert!(!allows(&roles, DEPUTY, Action::CompanyDelete));
+    }
+
+    #[test]
+    fn authorize_lets_owners_perform_owner_actions_only() {
+        let roles = hierarchy();
+        let principal = Principal::new(5, GUEST);
+        let own = Resource::owned_by(Some(5));
+        let foreign = Resource::owned_by(Some(6));
+
+        assert!(authorize(&roles, &principal, Action::SubtaskChangeStatus, &own).is_ok());
+        assert!(authorize(&roles, &principal, Action::SubtaskUnassign, &own).is_ok());
+        assert!(authorize(&roles, &principal, Action::SubtaskChangeStatus, &foreign).is_err());
+        assert!(authorize(&roles, &principal, Action::SubtaskDelete, &own).is_err());
+    }
+}
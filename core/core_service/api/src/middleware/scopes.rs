@@ -0,0 +1,184 @@
+/// Scopes a personal access token can be granted.
+///
+/// `<resource>:write` also grants `<resource>:read`.
+pub const ACCESS_TOKEN_SCOPES: [&str; 11] = [
+    "companies:read",
+    "companies:write",
+    "projects:read",
+    "projects:write",
+    "tasks:read",
+    "tasks:write",
+    "notes:read",
+    "notes:write",
+    "articles:read",
+    "articles:write",
+    "user:read",
+];
+
+/// Returns the scope a personal access token needs to call a gRPC method.
+///
+/// Methods whose name starts with `Get` or `List` need the `read` scope of their service's resource,
+/// all other methods the `write` scope. Methods managing credentials, sessions and the account
+/// itself are off limits to personal access tokens and return `None`.
+///
+/// # Arguments
+/// * `path` - The gRPC method path, e.g. `/helai_api_core_service.TasksService/CreateTask`.
+pub fn required_scope(path: &str) -> Option<String> {
+    let (service, method) = path
+        .strip_prefix("/helai_api_core_service.")?
+        .split_once('/')?;
+
+    let is_read = method.starts_with("Get") || method.starts_with("List");
+
+    let resource = match service {
+        "CompaniesService" => "companies",
+        "ProjectsService" => "projects",
+        "TasksService" | "SubtasksService" => "tasks",
+        "NotesService" => "notes",
+        "KnowledgeBaseService" => "articles",
+        "UserService" if method == "GetUserData" => "user",
+        _ => return None,
+    };
+
+    Some(format!(
+        "{}:{}",
+        resource,
+        if is_read { "read" } else { "write" }
+    ))
+}
+
+/// Checks whether a set of granted scopes includes the required one.
+pub fn is_granted(granted_scopes: &[String], required_scope: &str) -> bool {
+    granted_scopes.iter().any(|scope| {
+        scope == required_scope
+            || required_scope
+                .strip_suffix(":read")
+                .is_some_and(|resource| *scope == format!("{}:write", resource))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(service: &str, method: &str) -> String {
+        format!("/helai_api_core_service.{}/{}", service, method)
+    }
+
+    fn scopes(scopes: &[&str]) -> Vec<String> {
+        scopes.iter().map(|scope| scope.to_string()).collect()
+    }
+
+    #[test]
+    fn get_and_list_methods_need_the_read_scope() {
+        assert_eq!(
+            required_scope(&path("TasksService", "GetTask")).as_deref(),
+            Some("tasks:read")
+        );
+        assert_eq!(
+            required_scope(&path("CompaniesService", "ListCompanyMembers")).as_deref(),
+            Some("companies:read")
+        );
+    }
+
+    #[test]
+    fn other_methods_need_the_write_scope() {
+        assert_eq!(
+            required_scope(&path("TasksService", "CreateTask")).as_deref(),
+            Some("tasks:write")
+        );
+        assert_eq!(
+            required_scope(&path("ProjectsService", "DeleteProject")).as_deref(),
+            Some("projects:write")
+        );
+        // Only the prefix counts, so a method merely containing `Get` writes
+        assert_eq!(
+            required_scope(&path("NotesService", "ForgetNote")).as_deref(),
+            Some("notes:write")
+        );
+    }
+
+    #[test]
+    fn services_map_to_their_resource() {
+        assert_eq!(
+            required_scope(&path("SubtasksService", "UpdateSubtask")).as_deref(),
+            Some("tasks:write")
+        );
+        assert_eq!(
+            required_scope(&path("KnowledgeBaseService", "GetArticle")).as_deref(),
+            Some("articles:read")
+        );
+        assert_eq!(
+            required_scope(&path("UserService", "GetUserData")).as_deref(),
+            Some("user:read")
+        );
+    }
+
+    #[test]
+    fn every_required_scope_can_be_granted() {
+        let paths = [
+            path("CompaniesService", "GetCompany"),
+            path("CompaniesService", "UpdateCompany"),
+            path("ProjectsService", "ListProjects"),
+            path("ProjectsService", "CreateProject"),
+            path("TasksService", "GetTask"),
+            path("SubtasksService", "CreateSubtask"),
+            path("NotesService", "GetNote"),
+            path("NotesService", "CreateNote"),
+            path("KnowledgeBaseService", "ListArticles"),
+            path("KnowledgeBaseService", "CreateArticle"),
+            path("UserService", "GetUserData"),
+        ];
+
+        for path in paths {
+            let scope = required_scope(&path).unwrap();
+            assert!(ACCESS_TOKEN_SCOPES.contains(&scope.as_str()), "{}", scope);
+        }
+    }
+
+    #[test]
+    fn account_and_credential_methods_are_off_limits() {
+        for path in [
+            path("UserService", "ChangePassword"),
+            path("UserService", "ListSessions"),
+            path("UserService", "GetUserProfile"),
+            path("AccessTokensService", "CreateAccessToken"),
+            path("AccessTokensService", "ListAccessTokens"),
+            path("TrashService", "ListTrash"),
+            path("TrashService", "RestoreItem"),
+        ] {
+            assert_eq!(required_scope(&path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn unknown_paths_are_off_limits() {
+        assert_eq!(required_scope(&path("UnknownService", "GetThing")), None);
+        assert_eq!(required_scope("/other_package.TasksService/GetTask"), None);
+        assert_eq!(required_scope("/helai_api_core_service.TasksService"), None);
+        assert_eq!(required_scope(""), None);
+    }
+
+    #[test]
+    fn a_scope_grants_itself() {
+        assert!(is_granted(&scopes(&["tasks:read"]), "tasks:read"));
+        assert!(is_granted(&scopes(&["tasks:write"]), "tasks:write"));
+    }
+
+    #[test]
+    fn write_implies_read() {
+        assert!(is_granted(&scopes(&["tasks:write"]), "tasks:read"));
+    }
+
+    #[test]
+    fn read_does_not_imply_write() {
+        assert!(!is_granted(&scopes(&["tasks:read"]), "tasks:write"));
+    }
+
+    #[test]
+    fn scopes_of_other_resources_are_not_granted() {
+        assert!(!is_granted(&scopes(&["notes:write"]), "tasks:read"));
+        assert!(!is_granted(&scopes(&["tasks:write"]), "tasks_archive:read"));
+        assert!(!is_granted(&scopes(&[]), "tasks:read"));
+    }
+}
@@ -0,0 +1,143 @@
+use std::{env, fmt::Display, ops::Deref, str::FromStr, sync::OnceLock};
+
+use core_error::core_errors::CoreErrors;
+
+/// A setting read from the environment once per process.
+///
+/// `api::start` calls `init` for every setting, so an invalid value stops the startup with an
+/// error. Afterwards the setting is read through `Deref` and can't fail any more.
+pub struct Setting<T> {
+    value: OnceLock<T>,
+    load: fn() -> Result<T, CoreErrors>,
+}
+
+impl<T> Setting<T> {
+    /// Declares a setting that is read with `load`.
+    pub const fn new(load: fn() -> Result<T, CoreErrors>) -> Self {
+        Setting {
+            value: OnceLock::new(),
+            load,
+        }
+    }
+
+    /// Reads the setting unless it was read before.
+    ///
+    /// # Errors
+    /// * Returns the error of `load` if the configured value is invalid.
+    pub fn init(&self) -> Result<&T, CoreErrors> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+
+        let value = (self.load)()?;
+
+        Ok(self.value.get_or_init(|| value))
+    }
+}
+
+impl<T> Deref for Setting<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Only reached without `init`, i.e. in tests; startup has checked every setting
+        self.init().unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+/// Reads a value from the environment, falling back to `default` if the variable is not set.
+///
+/// # Errors
+/// * Returns `CoreErrors::DataValidationError` if the variable is set but can't be parsed.
+pub(crate) fn env_or_default<T: FromStr>(name: &str, default: T) -> Result<T, CoreErrors> {
+    match env::var(name) {
+        Ok(value) => value.parse().map_err(|_| {
+            CoreErrors::DataValidationError(format!("{} environment variable is not valid", name))
+        }),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Reads a value that must be greater than zero, falling back to `default` if it is not set.
+///
+/// # Errors
+/// * Returns `CoreErrors::DataValidationError` if the variable can't be parsed or is not positive.
+pub(crate) fn positive_env_or_default<T>(name: &str, default: T) -> Result<T, CoreErrors>
+where
+    T: FromStr + PartialOrd + Default + Display,
+{
+    let value = env_or_default(name, default)?;
+
+    if value <= T::default() {
+        return Err(CoreErrors::DataValidationError(format!(
+            "{} environment variable must be greater than 0, got {}",
+            name, value
+        )));
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every test uses its own variables, since tests run in parallel in one process
+
+    #[test]
+    fn unset_variables_fall_back_to_the_default() {
+        assert_eq!(env_or_default("SETTINGS_TEST_UNSET", 42u64).unwrap(), 42);
+        assert_eq!(
+            positive_env_or_default("SETTINGS_TEST_UNSET", 42u64).unwrap(),
+            42
+        );
+    }
+
+    #[test]
+    fn set_variables_are_parsed() {
+        env::set_var("SETTINGS_TEST_PARSED", "7");
+
+        assert_eq!(env_or_default("SETTINGS_TEST_PARSED", 42i32).unwrap(), 7);
+        assert_eq!(
+            positive_env_or_default("SETTINGS_TEST_PARSED", 42i32).unwrap(),
+            7
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_errors() {
+        env::set_var("SETTINGS_TEST_INVALID", "an hour");
+
+        assert!(env_or_default("SETTINGS_TEST_INVALID", 42u64).is_err());
+    }
+
+    #[test]
+    fn values_up_to_zero_are_not_positive() {
+        env::set_var("SETTINGS_TEST_ZERO", "0");
+        env::set_var("SETTINGS_TEST_NEGATIVE", "-3");
+
+        assert!(positive_env_or_default("SETTINGS_TEST_ZERO", 42u64).is_err());
+        assert!(positive_env_or_default("SETTINGS_TEST_NEGATIVE", 42i32).is_err());
+        assert_eq!(env_or_default("SETTINGS_TEST_NEGATIVE", 42i32).unwrap(), -3);
+    }
+
+    #[test]
+    fn a_setting_is_read_once() {
+        static SETTING: Setting<u64> = Setting::new(|| env_or_default("SETTINGS_TEST_ONCE", 1));
+
+        env::set_var("SETTINGS_TEST_ONCE", "2");
+        assert_eq!(*SETTING.init().unwrap(), 2);
+
+        env::set_var("SETTINGS_TEST_ONCE", "3");
+        assert_eq!(*SETTING, 2);
+    }
+
+    #[test]
+    fn an_invalid_setting_fails_to_init() {
+        static SETTING: Setting<u64> =
+            Setting::new(|| env_or_default("SETTINGS_TEST_INVALID_SETTING", 1));
+
+        env::set_var("SETTINGS_TEST_INVALID_SETTING", "-1");
+
+        assert!(SETTING.init().is_err());
+    }
+}
@@ -0,0 +1,419 @@
+use std::{collections::HashMap, env, fs};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use core_error::core_errors::CoreErrors;
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header,
+};
+use spki::{ObjectIdentifier, SubjectPublicKeyInfoRef};
+
+use super::settings::Setting;
+
+const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const RSA_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+
+/// The keys used to sign and verify tokens, loaded once from the environment.
+pub static SIGNING_KEYS: Setting<SigningKeys> = Setting::new(SigningKeys::from_env);
+
+/// A public key tokens can be verified with.
+struct VerificationKey {
+    algorithm: Algorithm,
+    decoding_key: DecodingKey,
+}
+
+/// The key new tokens are signed with together with all keys accepted for verification.
+///
+/// With `JWT_SIGNING_KEY_FILE` set, tokens are signed with an EdDSA or RS256 private key and carry
+/// the key's `kid` header. Tokens are verified with the public key matching their `kid`, so old keys
+/// can stay in `JWT_VERIFICATION_KEYS` until the tokens signed with them expire.
+/// Without it, tokens are signed and verified with the HS256 secret from `JWT_SECRET_KEY`.
+pub struct SigningKeys {
+    algorithm: Algorithm,
+    key_id: Option<String>,
+    encoding_key: EncodingKey,
+    verification_keys: HashMap<String, VerificationKey>,
+    secret_key: Option<DecodingKey>,
+    jwks: JwkSet,
+}
+
+impl SigningKeys {
+    /// Loads the keys from the environment.
+    ///
+    /// * `JWT_SIGNING_KEY_FILE` - Path to the PKCS#8 PEM private key used for signing.
+    /// * `JWT_SIGNING_KEY_ID` - The `kid` of the signing key.
+    /// * `JWT_SIGNING_ALGORITHM` - `EdDSA` (default) or `RS256`.
+    /// * `JWT_VERIFICATION_KEYS` - Comma separated `kid=path` pairs of PEM public keys,
+    ///   including the public key of the signing key.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::JWTGenerationError` if a key can't be read or the configuration is inconsistent.
+    pub fn from_env() -> Result<SigningKeys, CoreErrors> {
+        let signing_key_file = match env::var("JWT_SIGNING_KEY_FILE") {
+            Ok(path) => path,
+            Err(_) => return Self::from_secret(),
+        };
+
+        let key_id = env::var("JWT_SIGNING_KEY_ID").map_err(|_| {
+            config_error("JWT_SIGNING_KEY_ID must be set together with JWT_SIGNING_KEY_FILE")
+        })?;
+
+        let algorithm = match env::var("JWT_SIGNING_ALGORITHM").as_deref() {
+            Ok("EdDSA") | Err(_) => Algorithm::EdDSA,
+            Ok("RS256") => Algorithm::RS256,
+            Ok(other) => {
+                return Err(config_error(&format!(
+                    "unsupported JWT_SIGNING_ALGORITHM {}",
+                    other
+                )))
+            }
+        };
+
+        let verification_list = env::var("JWT_VERIFICATION_KEYS").unwrap_or_default();
+
+        Self::from_key_files(algorithm, key_id, &signing_key_file, &verification_list)
+    }
+
+    /// Loads the signing key and the verification keys from PEM files.
+    ///
+    /// # Arguments
+    /// * `algorithm` - `EdDSA` or `RS256`.
+    /// * `key_id` - The `kid` of the signing key.
+    /// * `signing_key_file` - Path to the PKCS#8 PEM private key used for signing.
+    /// * `verification_list` - Comma separated `kid=path` pairs of PEM public keys.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::JWTGenerationError` if a key can't be read or the configuration is inconsistent.
+    fn from_key_files(
+        algorithm: Algorithm,
+        key_id: String,
+        signing_key_file: &str,
+        verification_list: &str,
+    ) -> Result<SigningKeys, CoreErrors> {
+        let private_pem = read_key_file(signing_key_file)?;
+        let encoding_key = match algorithm {
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(&private_pem)?,
+            _ => EncodingKey::from_ed_pem(&private_pem)?,
+        };
+
+        // Load every public key accepted for verification
+        let mut verification_keys = HashMap::new();
+        let mut jwks = JwkSet { keys: Vec::new() };
+
+        for entry in verification_list
+            .split(',')
+            .filter(|entry| !entry.is_empty())
+        {
+            let (kid, path) = entry
+                .split_once('=')
+                .ok_or_else(|| config_error("JWT_VERIFICATION_KEYS entries must be kid=path"))?;
+
+            let jwk = public_key_to_jwk(kid.trim(), &read_key_file(path.trim())?)?;
+            let verification_key = VerificationKey {
+                algorithm: match jwk.algorithm {
+                    AlgorithmParameters::RSA(_) => Algorithm::RS256,
+                    _ => Algorithm::EdDSA,
+                },
+                decoding_key: DecodingKey::from_jwk(&jwk)?,
+            };
+
+            verification_keys.insert(kid.trim().to_string(), verification_key);
+            jwks.keys.push(jwk);
+        }
+
+        // Tokens we sign must be verifiable by ourselves and by everyone reading the JWKS
+        match verification_keys.get(&key_id) {
+            Some(key) if key.algorithm == algorithm => {}
+            _ => {
+                return Err(config_error(
+                    "JWT_VERIFICATION_KEYS must contain the public key of the signing key",
+                ))
+            }
+        }
+
+        Ok(SigningKeys {
+            algorithm,
+            key_id: Some(key_id),
+            encoding_key,
+            verification_keys,
+            secret_key: None,
+            jwks,
+        })
+    }
+
+    /// Falls back to the shared HS256 secret from `JWT_SECRET_KEY`.
+    fn from_secret() -> Result<SigningKeys, CoreErrors> {
+        let secret = env::var("JWT_SECRET_KEY")
+            .map_err(|_| config_error("JWT_SECRET_KEY environment variable is not set"))?;
+
+        Ok(SigningKeys {
+            algorithm: Algorithm::HS256,
+            key_id: None,
+            encoding_key: EncodingKey::from_secret(secret.as_ref()),
+            verification_keys: HashMap::new(),
+            secret_key: Some(DecodingKey::from_secret(secret.as_ref())),
+            jwks: JwkSet { keys: Vec::new() },
+        })
+    }
+
+    /// Returns the header for newly signed tokens.
+    pub fn header(&self) -> Header {
+        let mut header = Header::new(self.algorithm);
+        header.kid = self.key_id.clone();
+
+        header
+    }
+
+    /// Returns the key new tokens are signed with.
+    pub fn encoding_key(&self) -> &EncodingKey {
+        &self.encoding_key
+    }
+
+    /// Returns the key and algorithm a token with the given header must be verified with.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::JWTGenerationError` if no accepted key matches the header.
+    pub fn decoding_key(&self, header: &Header) -> Result<(Algorithm, &DecodingKey), CoreErrors> {
+        match (&header.kid, &self.secret_key) {
+            (Some(kid), _) => self
+                .verification_keys
+                .get(kid)
+                .map(|key| (key.algorithm, &key.decoding_key))
+                .ok_or_else(|| CoreErrors::JWTGenerationError("invalid_token".to_string())),
+            (None, Some(secret_key)) => Ok((Algorithm::HS256, secret_key)),
+            (None, None) => Err(CoreErrors::JWTGenerationError("invalid_token".to_string())),
+        }
+    }
+
+    /// Returns the public verification keys as a JWK set.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+/// Builds the JWK of an Ed25519 or RSA public key in PEM (SPKI) format.
+fn public_key_to_jwk(kid: &str, pem: &[u8]) -> Result<Jwk, CoreErrors> {
+    let pem = pem::parse(pem).map_err(|_| config_error("invalid public key PEM"))?;
+    let spki = SubjectPublicKeyInfoRef::try_from(pem.contents())
+        .map_err(|_| config_error("invalid public key"))?;
+    let public_key = spki.subject_public_key.raw_bytes();
+
+    let (key_algorithm, algorithm) = if spki.algorithm.oid == ED25519_OID {
+        (
+            KeyAlgorithm::EdDSA,
+            AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: URL_SAFE_NO_PAD.encode(public_key),
+            }),
+        )
+    } else if spki.algorithm.oid == RSA_OID {
+        let rsa_key = pkcs1::RsaPublicKey::try_from(public_key)
+            .map_err(|_| config_error("invalid RSA public key"))?;
+
+        (
+            KeyAlgorithm::RS256,
+            AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: URL_SAFE_NO_PAD.encode(rsa_key.modulus.as_bytes()),
+                e: URL_SAFE_NO_PAD.encode(rsa_key.public_exponent.as_bytes()),
+            }),
+        )
+    } else {
+        return Err(config_error(
+            "only Ed25519 and RSA public keys are supported",
+        ));
+    };
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm,
+    })
+}
+
+fn read_key_file(path: &str) -> Result<Vec<u8>, CoreErrors> {
+    fs::read(path).map_err(|err| config_error(&format!("failed to read key {}: {}", path, err)))
+}
+
+fn config_error(message: &str) -> CoreErrors {
+    CoreErrors::JWTGenerationError(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{decode, decode_header, encode, Validation};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Claims {
+        sub: i64,
+        exp: u64,
+    }
+
+    /// Returns the path of a checked-in test key.
+    fn key_file(name: &str) -> String {
+        format!("{}/testdata/keys/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn verification_list(keys: &[(&str, &str)]) -> String {
+        keys.iter()
+            .map(|(kid, name)| format!("{}={}", kid, key_file(name)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Keys signing with `current` and still accepting tokens of `previous`.
+    fn rotated_keys() -> SigningKeys {
+        SigningKeys::from_key_files(
+            Algorithm::EdDSA,
+            "current".to_string(),
+            &key_file("current.pem"),
+            &verification_list(&[
+                ("current", "current.pub.pem"),
+                ("previous", "previous.pub.pem"),
+            ]),
+        )
+        .unwrap()
+    }
+
+    /// Keys the service used before the rotation, signing with `previous`.
+    fn previous_keys() -> SigningKeys {
+        SigningKeys::from_key_files(
+            Algorithm::EdDSA,
+            "previous".to_string(),
+            &key_file("previous.pem"),
+            &verification_list(&[("previous", "previous.pub.pem")]),
+        )
+        .unwrap()
+    }
+
+    fn sign(keys: &SigningKeys) -> String {
+        let claims = Claims {
+            sub: 7,
+            exp: u64::MAX,
+        };
+
+        encode(&keys.header(), &claims, keys.encoding_key()).unwrap()
+    }
+
+    fn verify(keys: &SigningKeys, token: &str) -> Result<Claims, CoreErrors> {
+        let (algorithm, decoding_key) = keys.decoding_key(&decode_header(token)?)?;
+
+        Ok(decode::<Claims>(token, decoding_key, &Validation::new(algorithm))?.claims)
+    }
+
+    #[test]
+    fn signed_tokens_carry_the_signing_key_id() {
+        let keys = rotated_keys();
+        let header = decode_header(&sign(&keys)).unwrap();
+
+        assert_eq!(header.alg, Algorithm::EdDSA);
+        assert_eq!(header.kid.as_deref(), Some("current"));
+        assert_eq!(verify(&keys, &sign(&keys)).unwrap().sub, 7);
+    }
+
+    #[test]
+    fn the_verification_key_is_selected_by_kid() {
+        let keys = rotated_keys();
+
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some("previous".to_string());
+        assert!(keys.decoding_key(&header).is_ok());
+
+        header.kid = Some("unknown".to_string());
+        assert!(keys.decoding_key(&header).is_err());
+
+        // Without a secret, tokens must name their key
+        header.kid = None;
+        assert!(keys.decoding_key(&header).is_err());
+    }
+
+    #[test]
+    fn tokens_of_a_rotated_key_are_still_accepted() {
+        let token = sign(&previous_keys());
+
+        assert_eq!(verify(&rotated_keys(), &token).unwrap().sub, 7);
+    }
+
+    #[test]
+    fn tokens_naming_another_key_are_rejected() {
+        let token = sign(&previous_keys());
+
+        // Signed with `previous` but claiming to be signed with `current`
+        let (_, rest) = token.split_once('.').unwrap();
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some("current".to_string());
+        let header = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header).unwrap());
+
+        assert!(verify(&rotated_keys(), &format!("{}.{}", header, rest)).is_err());
+    }
+
+    #[test]
+    fn the_signing_key_must_be_a_verification_key() {
+        let keys = SigningKeys::from_key_files(
+            Algorithm::EdDSA,
+            "current".to_string(),
+            &key_file("current.pem"),
+            &verification_list(&[("previous", "previous.pub.pem")]),
+        );
+
+        assert!(keys.is_err());
+    }
+
+    #[test]
+    fn invalid_key_files_are_errors() {
+        let missing_file = SigningKeys::from_key_files(
+            Algorithm::EdDSA,
+            "current".to_string(),
+            &key_file("missing.pem"),
+            &verification_list(&[("current", "current.pub.pem")]),
+        );
+        let private_key_as_public_key = SigningKeys::from_key_files(
+            Algorithm::EdDSA,
+            "current".to_string(),
+            &key_file("current.pem"),
+            &verification_list(&[("current", "current.pem")]),
+        );
+        let entry_without_kid = SigningKeys::from_key_files(
+            Algorithm::EdDSA,
+            "current".to_string(),
+            &key_file("current.pem"),
+            &key_file("current.pub.pem"),
+        );
+
+        assert!(missing_file.is_err());
+        assert!(private_key_as_public_key.is_err());
+        assert!(entry_without_kid.is_err());
+    }
+
+    #[test]
+    fn the_jwks_lists_every_verification_key() {
+        let jwks = serde_json::to_value(rotated_keys().jwks()).unwrap();
+        let keys = jwks["keys"].as_array().unwrap();
+
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0]["kid"], "current");
+        assert_eq!(keys[1]["kid"], "previous");
+
+        for key in keys {
+            assert_eq!(key["kty"], "OKP");
+            assert_eq!(key["crv"], "Ed25519");
+            assert_eq!(key["alg"], "EdDSA");
+            assert_eq!(key["use"], "sig");
+            assert!(key.get("d").is_none());
+        }
+
+        // The raw public key from `current.pub.pem`
+        assert_eq!(keys[0]["x"], "Kjf3M4po6IBkLzY_HSnni0LgqUlOJAxRVZHn-usxhNI");
+    }
+}
@@ -0,0 +1,71 @@
+use std::{sync::Arc, time::Duration};
+
+use core_database::queries::trash_query::TrashQuery;
+use core_debugger::tracing::{event, Level};
+use core_error::core_errors::CoreErrors;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+
+use super::settings::{positive_env_or_default, Setting};
+
+const DEFAULT_TRASH_RETENTION_DAYS: i32 = 30;
+const DEFAULT_TRASH_PURGE_INTERVAL_SECS: u64 = 3600; // 1 hour in seconds
+
+/// Days deleted items stay in the trash before they are purged.
+pub static TRASH_RETENTION_DAYS: Setting<i32> =
+    Setting::new(|| positive_env_or_default("TRASH_RETENTION_DAYS", DEFAULT_TRASH_RETENTION_DAYS));
+static TRASH_PURGE_INTERVAL_SECS: Setting<u64> = Setting::new(|| {
+    positive_env_or_default(
+        "TRASH_PURGE_INTERVAL_SECS",
+        DEFAULT_TRASH_PURGE_INTERVAL_SECS,
+    )
+});
+
+/// Reads the retention period and the purge interval.
+///
+/// Both must be positive: a zero interval would stop the purge task and a retention period up to
+/// zero would purge deleted items straight away.
+///
+/// # Errors
+/// * Returns `CoreErrors::DataValidationError` if one of them is invalid.
+pub fn init_settings() -> Result<(), CoreErrors> {
+    TRASH_RETENTION_DAYS.init()?;
+    TRASH_PURGE_INTERVAL_SECS.init()?;
+
+    Ok(())
+}
+
+/// Starts a background task purging the items that have been in the trash for longer than
+/// `TRASH_RETENTION_DAYS`, every `TRASH_PURGE_INTERVAL_SECS`.
+///
+/// A failed run is logged and retried on the next tick.
+///
+/// # Arguments
+/// * `conn` - The shared database connection.
+pub fn spawn_trash_purge(conn: Arc<DatabaseConnection>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(*TRASH_PURGE_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            match purge_expired(&conn).await {
+                Ok(0) => {}
+                Ok(purged) => {
+                    event!(target: "hellai_app_core_events", Level::INFO, "Purged {} items from the trash", purged);
+                }
+                Err(err) => {
+                    event!(target: "hellai_app_core_events", Level::ERROR, "Failed to purge the trash: {}", err);
+                }
+            }
+        }
+    });
+}
+
+/// Purges the expired items in one transaction, so a failed run purges nothing.
+async fn purge_expired(conn: &DatabaseConnection) -> Result<u64, CoreErrors> {
+    let txn = conn.begin().await?;
+    let purged = TrashQuery::purge_expired(&txn, *TRASH_RETENTION_DAYS).await?;
+    txn.commit().await?;
+
+    Ok(purged)
+}
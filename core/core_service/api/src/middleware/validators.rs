@@ -1,3 +1,4 @@
+use chrono::{DateTime, FixedOffset};
 use core_error::core_errors::CoreErrors;
 use regex::Regex;
 
@@ -201,6 +202,31 @@ pub fn no_special_symbols_validator(value: String) -> Result<String, CoreErrors>
     Ok(value)
 }
 
+/// Validates a company alias and normalizes it to lowercase.
+///
+/// Checks:
+/// - Between 3 and 32 characters.
+/// - Contains only ASCII letters and digits.
+///
+/// # Arguments
+///
+/// * `value` - A `String` representing the alias.
+///
+/// # Returns
+///
+/// * `Ok(String)` with the lowercase alias.
+/// * `Err(CoreErrors)` if the value is not a valid alias.
+pub fn company_alias_validator(value: String) -> Result<String, CoreErrors> {
+    let alias = value.trim().to_lowercase();
+
+    if alias.len() < 3 || alias.len() > 32 || !alias.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(CoreErrors::DataValidationError(
+            "validator_invalid_company_alias".to_string(),
+        ));
+    }
+    Ok(alias)
+}
+
 // Validator that checks if the value is a valid hexadecimal color using a regular expression.
 ///
 /// A valid hexadecimal color starts with a '#' followed by exactly 3 or 6 valid hexadecimal characters.
@@ -226,3 +252,41 @@ pub fn hex_color_validator(value: String) -> Result<String, CoreErrors> {
         ))
     }
 }
+
+/// Parses a date string in RFC 3339 format (e.g. `2024-12-31T18:00:00+00:00`).
+///
+/// # Arguments
+///
+/// * `value` - A `String` representing the date.
+///
+/// # Returns
+///
+/// * `Ok(DateTime<FixedOffset>)` if the value is a valid RFC 3339 date.
+/// * `Err(CoreErrors)` if the value cannot be parsed.
+pub fn date_format_validation(value: String) -> Result<DateTime<FixedOffset>, CoreErrors> {
+    DateTime::parse_from_rfc3339(&value)
+        .map_err(|_| CoreErrors::DataValidationError("validator_invalid_date".to_string()))
+}
+
+/// Validates that a value is one of the supported task priorities (`Low`, `Medium`, `High`).
+///
+/// The comparison is case-insensitive and the returned value is normalized to the capitalized form.
+///
+/// # Arguments
+///
+/// * `value` - A `String` representing the priority.
+///
+/// # Returns
+///
+/// * `Ok(String)` with the normalized priority.
+/// * `Err(CoreErrors)` if the value is not a supported priority.
+pub fn task_priority_validator(value: String) -> Result<String, CoreErrors> {
+    match value.to_lowercase().as_str() {
+        "low" => Ok("Low".to_string()),
+        "medium" => Ok("Medium".to_string()),
+        "high" => Ok("High".to_string()),
+        _ => Err(CoreErrors::DataValidationError(
+            "validator_invalid_task_priority".to_string(),
+        )),
+    }
+}
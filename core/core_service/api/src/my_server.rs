@@ -1,8 +1,14 @@
 use std::sync::Arc;
 
 use sea_orm::DatabaseConnection;
+use service::mailer::Mailer;
+
+use crate::middleware::{oidc::OidcProvider, policy::RoleHierarchy};
 
 #[derive(Clone)]
 pub struct MyServer {
     pub connection: Arc<DatabaseConnection>,
+    pub roles: Arc<RoleHierarchy>,
+    pub mailer: Arc<dyn Mailer>,
+    pub oidc: Option<Arc<OidcProvider>>,
 }
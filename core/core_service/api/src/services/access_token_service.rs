@@ -0,0 +1,198 @@
+use core_database::{
+    entity::personal_access_tokens, queries::access_tokens_query::AccessTokensQuery,
+};
+use core_debugger::tracing::{event, Level};
+use tonic::{Request, Response, Status};
+
+use crate::{
+    helai_api_core_service::{
+        access_tokens_service_server::AccessTokensService, AccessTokenResponse,
+        CreateAccessTokenRequest, CreateAccessTokenResponse, ListAccessTokensRequest,
+        ListAccessTokensResponse, RevokeAccessTokenRequest, StatusResponse,
+    },
+    middleware::{
+        auth_token::{get_now_time_in_secs, timestamp_to_date_time, PersonalAccessToken},
+        interceptors,
+        scopes::ACCESS_TOKEN_SCOPES,
+        validators::{empty_validation, max_symbols_validator_250, CompositValidator},
+    },
+    my_server::MyServer,
+};
+
+#[tonic::async_trait]
+impl AccessTokensService for MyServer {
+    /// Creates a personal access token for the authenticated user.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `CreateAccessTokenRequest`, which includes the name,
+    ///   the scopes and the optional lifetime of the token.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<CreateAccessTokenResponse>, Status>` - The token, which is shown only once,
+    ///   or an error if the name, a scope or the lifetime is invalid.
+    async fn create_access_token(
+        &self,
+        request: Request<CreateAccessTokenRequest>,
+    ) -> Result<Response<CreateAccessTokenResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received create access token request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Step 2: Extract the inner request payload
+        let request = request.into_inner();
+
+        // Step 3: Validate the name, scopes and lifetime of the token
+        let name_validator =
+            CompositValidator::new(vec![empty_validation, max_symbols_validator_250]);
+        let name = name_validator.validate(request.name)?;
+
+        let mut scopes: Vec<String> = Vec::new();
+        for scope in request.scopes {
+            let scope = scope.trim().to_string();
+
+            if !ACCESS_TOKEN_SCOPES.contains(&scope.as_str()) {
+                return Err(Status::invalid_argument("invalid_scope"));
+            }
+
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+
+        if scopes.is_empty() {
+            return Err(Status::invalid_argument("scopes_required"));
+        }
+
+        let expires_at = match request.expires_in_days {
+            Some(days) if days <= 0 || days > MAX_ACCESS_TOKEN_LIFETIME_DAYS => {
+                return Err(Status::invalid_argument("invalid_expiry"))
+            }
+            Some(days) => Some(timestamp_to_date_time(
+                get_now_time_in_secs() + days as u64 * 86400,
+            )),
+            None => None,
+        };
+
+        // Step 4: Generate the token and store its hash
+        let access_token = PersonalAccessToken::generate();
+
+        let stored_token = AccessTokensQuery::create_access_token(
+            &self.connection,
+            user_id_from_token as i32,
+            name,
+            access_token.token_hash,
+            access_token.token_prefix,
+            scopes.join(" "),
+            expires_at,
+        )
+        .await?;
+
+        // The response carries the token, so it is not logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Access token {} created for user {}", stored_token.id, user_id_from_token);
+
+        Ok(Response::new(CreateAccessTokenResponse {
+            token: access_token.token,
+            access_token: Some(access_token_response(stored_token)),
+        }))
+    }
+
+    /// Lists the personal access tokens of the authenticated user that were not revoked.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `ListAccessTokensRequest`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<ListAccessTokensResponse>, Status>` - The tokens, newest first, including expired ones.
+    async fn list_access_tokens(
+        &self,
+        request: Request<ListAccessTokensRequest>,
+    ) -> Result<Response<ListAccessTokensResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received list access tokens request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Step 2: Fetch the tokens of the user
+        let access_tokens =
+            AccessTokensQuery::get_user_access_tokens(&self.connection, user_id_from_token as i32)
+                .await?;
+
+        // Step 3: Prepare the response
+        let response = Response::new(ListAccessTokensResponse {
+            access_tokens: access_tokens
+                .into_iter()
+                .map(access_token_response)
+                .collect(),
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Revokes a personal access token of the authenticated user.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `RevokeAccessTokenRequest`, which includes the ID of the token.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<StatusResponse>, Status>` - A response indicating success if the token was revoked,
+    ///   or a not found error if the user has no active token with this ID.
+    async fn revoke_access_token(
+        &self,
+        request: Request<RevokeAccessTokenRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received revoke access token request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Step 2: Extract the inner request payload
+        let request = request.into_inner();
+
+        // Step 3: Revoke the token; other users' tokens are reported as missing
+        if !AccessTokensQuery::revoke_access_token(
+            &self.connection,
+            user_id_from_token as i32,
+            request.access_token_id,
+        )
+        .await?
+        {
+            return Err(Status::not_found("access_token_not_found"));
+        }
+
+        // Step 4: Prepare a success response
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+}
+
+/// Upper bound for `expires_in_days` of a personal access token.
+const MAX_ACCESS_TOKEN_LIFETIME_DAYS: i32 = 365;
+
+/// Converts a stored personal access token into its gRPC representation.
+fn access_token_response(token: personal_access_tokens::Model) -> AccessTokenResponse {
+    AccessTokenResponse {
+        id: token.id,
+        name: token.name,
+        scopes: token
+            .scopes
+            .split_whitespace()
+            .map(|scope| scope.to_string())
+            .collect(),
+        token_prefix: token.token_prefix,
+        expires_at: token.expires_at.map(|expires_at| expires_at.to_string()),
+        last_used_at: token
+            .last_used_at
+            .map(|last_used_at| last_used_at.to_string()),
+        created_at: token.created_at.to_string(),
+    }
+}
@@ -1,18 +1,40 @@
-use core_database::queries::companies_query::CompaniesQuery;
+use std::env;
+
+use core_database::{
+    entity::{companies, company_invitations, sea_orm_active_enums::AccessLevelType},
+    queries::{
+        companies_query::{AliasChange, CompaniesQuery},
+        company_invitations_query::CompanyInvitationsQuery,
+        roles_query::OWNER_LEVEL,
+        user_query::UserQuery,
+    },
+};
 use core_debugger::tracing::{event, Level};
+use core_error::core_errors::CoreErrors;
+use sea_orm::{DbConn, TransactionTrait};
+use service::mailer::MailMessage;
 use tonic::{Request, Response, Status};
 
 use crate::{
     helai_api_core_service::{
-        companies_service_server::CompaniesService, CompanyInfoResponse, CompanyUserInfoResponse,
-        CreateCompanyRequest, CreateCompanyResponse, DeleteCompanyRequest, GetAllCompanyRequest,
-        GetAllCompanyRespnonse, StatusResponse, UserCompanyModificationRequest,
+        companies_service_server::CompaniesService, invitation_request::Invitation,
+        ChangeCompanyAliasRequest, ChangeMemberRoleRequest, CompanyAccessLevel,
+        CompanyInfoResponse, CompanyInvitationResponse, CompanyMemberResponse,
+        CompanyUserInfoResponse, CreateCompanyRequest, CreateCompanyResponse, DeleteCompanyRequest,
+        GetAllCompanyRequest, GetAllCompanyRespnonse, GetCompanyByAliasRequest,
+        GetCompanyByAliasResponse, InvitationRequest, InviteUserToCompanyRequest,
+        LeaveCompanyRequest, ListCompanyMembersRequest, ListCompanyMembersResponse,
+        ListPendingInvitationsRequest, ListPendingInvitationsResponse, ProjectRole,
+        RevokeInvitationRequest, StatusResponse, TransferCompanyOwnershipRequest,
+        UpdateCompanyRequest, UserCompanyModificationRequest,
     },
     middleware::{
         access_check::check_company_permission,
+        auth_token::{hash_token, OneTimeToken},
         interceptors,
+        policy::{authorize, Action, Principal, Resource, RoleHierarchy},
         validators::{
-            empty_validation, max_symbols_validator_20, min_symbols_validator_3,
+            self, empty_validation, max_symbols_validator_20, min_symbols_validator_3,
             no_special_symbols_validator, CompositValidator,
         },
     },
@@ -42,8 +64,8 @@ impl CompaniesService for MyServer {
     ) -> Result<Response<CreateCompanyResponse>, Status> {
         event!(target: "hellai_app_core_events", Level::DEBUG, "Received create company request: {:?}", request);
 
-        // Step 1: Extract user ID from auth token in request metadata for authentication
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the request to access its inner data
         let request = request.into_inner();
@@ -106,8 +128,8 @@ impl CompaniesService for MyServer {
     ) -> Result<Response<CompanyUserInfoResponse>, Status> {
         event!(target: "hellai_app_core_events", Level::DEBUG, "Received request: {:?}", request);
 
-        // Step 1: Authenticate the user by extracting their ID from the auth token in request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the request to access the inner data
         let request = request.into_inner();
@@ -119,26 +141,26 @@ impl CompaniesService for MyServer {
         let user_company_access =
             check_company_permission(conn, user_id_from_token as i32, request.company_id).await?;
 
-        // Permission level check - allow access if the user's role is sufficiently privileged (role_id <= 2)
-        if user_company_access.role_id <= 2 {
-            // Step 4: Add the specified user to the company with the necessary permissions
-            let user_company =
-                CompaniesQuery::add_user_to_company(conn, request.user_id, request.company_id)
-                    .await?;
+        // Permission check - only members allowed to invite may add users
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+            Action::MemberInvite,
+            &Resource::default(),
+        )?;
 
-            // Step 5: Prepare the response with the new user's role information
-            let response = Response::new(CompanyUserInfoResponse {
-                user_id: user_company.user_id,
-                user_role: user_company.role_id - 1, // Adjust to match the gRPC enum by subtracting 1
-            });
+        // Step 4: Add the specified user to the company with the necessary permissions
+        let user_company =
+            CompaniesQuery::add_user_to_company(conn, request.user_id, request.company_id).await?;
 
-            event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
-            return Ok(response);
-        }
+        // Step 5: Prepare the response with the new user's role information
+        let response = Response::new(CompanyUserInfoResponse {
+            user_id: user_company.user_id,
+            user_role: user_company.role_id - 1, // Adjust to match the gRPC enum by subtracting 1
+        });
 
-        // Log and return a permission denied error if the user lacks authorization
-        event!(target: "hellai_app_core_events", Level::DEBUG, "permission_denied");
-        Err(Status::permission_denied("permission_denied"))
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
     }
 
     /// Removes a specified user from a company if the authenticated user has the required permissions.
@@ -162,8 +184,8 @@ impl CompaniesService for MyServer {
     ) -> Result<Response<StatusResponse>, Status> {
         event!(target: "hellai_app_core_events", Level::DEBUG, "Received remove user request: {:?}", request);
 
-        // Step 1: Authenticate the user by extracting their ID from the auth token in request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the request to access the inner data
         let request = request.into_inner();
@@ -173,35 +195,30 @@ impl CompaniesService for MyServer {
 
         // Step 2: Check if the authenticated user is trying to remove themselves
         if user_id_from_token as i32 == request.user_id {
-            // User is removing themselves, proceed with the removal
-            CompaniesQuery::remove_user_from_company(
-                conn,
-                request.user_id,
-                request.company_id,
-                None,
-            )
-            .await?;
+            // User is removing themselves, proceed with the removal unless they are the last owner
+            leave_company(conn, request.user_id, request.company_id).await?;
         } else {
             // Step 3: Verify that the authenticated user has the required permissions to remove other users
             let user_company_access =
                 check_company_permission(conn, user_id_from_token as i32, request.company_id)
                     .await?;
 
-            // Permission level check - allow access if the user's role is sufficiently privileged (role_id <= 2)
-            if user_company_access.role_id <= 2 {
-                // Authorized to remove the specified user, proceed with removal
-                CompaniesQuery::remove_user_from_company(
-                    conn,
-                    request.user_id,
-                    request.company_id,
-                    Some(user_company_access.role_id),
-                )
-                .await?;
-            } else {
-                // Log and return a permission denied error if the user lacks authorization
-                event!(target: "hellai_app_core_events", Level::DEBUG, "Permission denied for removing user");
-                return Err(Status::permission_denied("permission_denied"));
-            }
+            // Permission check - only members allowed to remove others may proceed
+            authorize(
+                &self.roles,
+                &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+                Action::MemberRemove,
+                &Resource::default(),
+            )?;
+
+            // Authorized to remove the specified user, proceed with removal
+            CompaniesQuery::remove_user_from_company(
+                conn,
+                request.user_id,
+                request.company_id,
+                Some(user_company_access.role_id),
+            )
+            .await?;
         }
 
         // Step 4: Prepare a success response indicating the user was removed
@@ -211,11 +228,11 @@ impl CompaniesService for MyServer {
         Ok(response)
     }
 
-    /// Deletes a specified company and all associated users if the authenticated user has the "Owner" role.
+    /// Moves a specified company to the trash if the authenticated user has the "Owner" role.
     ///
     /// This function checks if the authenticated user has the "Owner" role for the specified company.
-    /// If authorized, it deletes the company and removes all associated users. If unauthorized, it returns
-    /// a permission denied error.
+    /// If authorized, it moves the company with its projects, tasks and notes to the trash; members keep
+    /// their roles so the company can be restored. If unauthorized, it returns a permission denied error.
     ///
     /// # Arguments
     ///
@@ -223,7 +240,7 @@ impl CompaniesService for MyServer {
     ///
     /// # Returns
     ///
-    /// * `Result<Response<StatusResponse>, Status>` - A success response if the company and associated users are deleted,
+    /// * `Result<Response<StatusResponse>, Status>` - A success response if the company is moved to the trash,
     ///   or a permission denied error if the user lacks the required authorization.
     async fn delete_company(
         &self,
@@ -231,8 +248,8 @@ impl CompaniesService for MyServer {
     ) -> Result<Response<StatusResponse>, Status> {
         event!(target: "hellai_app_core_events", Level::DEBUG, "Received delete company request: {:?}", request);
 
-        // Step 1: Authenticate the user and extract their ID from the auth token in the request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the request to access the inner data
         let request = request.into_inner();
@@ -245,23 +262,22 @@ impl CompaniesService for MyServer {
         let user_company_access =
             check_company_permission(conn, user_id_from_token as i32, request.company_id).await?;
 
-        // Step 4: Proceed with deletion only if the user has the "Owner" role (role_id == 1)
-        if user_company_access.role_id == 1 {
-            // Delete the specified company
-            CompaniesQuery::delete_company(conn, request.company_id).await?;
-            // Delete all user associations with the specified company
-            CompaniesQuery::delete_all_users_from_company(conn, request.company_id).await?;
+        // Step 4: Proceed with deletion only if the user has the "Owner" role
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+            Action::CompanyDelete,
+            &Resource::default(),
+        )?;
 
-            // Step 5: Construct a success response indicating successful deletion
-            let response = Response::new(StatusResponse { success: true });
+        // Move the specified company to the trash
+        CompaniesQuery::delete_company(conn.as_ref(), request.company_id).await?;
 
-            event!(target: "hellai_app_core_events", Level::DEBUG, "Company and associated users deleted successfully. Response: {:?}", response);
-            Ok(response)
-        } else {
-            // Log and return a permission denied error if the user lacks "Owner" role authorization
-            event!(target: "hellai_app_core_events", Level::DEBUG, "Permission denied: User lacks 'Owner' role to delete company");
-            Err(Status::permission_denied("permission_denied"))
-        }
+        // Step 5: Construct a success response indicating successful deletion
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Company moved to the trash successfully. Response: {:?}", response);
+        Ok(response)
     }
 
     /// Retrieves all companies associated with a user.
@@ -290,8 +306,8 @@ impl CompaniesService for MyServer {
             request
         );
 
-        // Step 2: Authenticate the user and extract their ID from the auth token
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 2: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Step 3: Establish a database connection
         let conn = &self.connection;
@@ -327,4 +343,1136 @@ impl CompaniesService for MyServer {
 
         Ok(response)
     }
+
+    /// Invites a user to a company by email with the chosen role.
+    ///
+    /// The invitation link is emailed to the address and stays valid for `COMPANY_INVITATION_TOKEN_TTL_SECS`.
+    /// Inviting the same email again revokes the earlier invitation. Nobody can invite a user with a role
+    /// higher than their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `InviteUserToCompanyRequest`, which includes
+    ///   the company ID, the email of the invited user and their role.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<CompanyInvitationResponse>, Status>` - A response with the pending invitation,
+    ///   or a permission error if the authenticated user may not invite members with this role.
+    async fn invite_user_to_company(
+        &self,
+        request: Request<InviteUserToCompanyRequest>,
+    ) -> Result<Response<CompanyInvitationResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received invite user request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Validate the email and the invited role
+        let email = validators::email_format_validation(request.email)?;
+        let role_id = role_id_from_proto(request.user_role)?;
+
+        // Step 3: Verify that the authenticated user may invite members with this role
+        let user_company_access =
+            check_company_permission(conn, user_id_from_token as i32, request.company_id).await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+            Action::MemberInvite,
+            &Resource::default(),
+        )?;
+
+        authorize_invited_role(&self.roles, user_company_access.role_id, role_id)?;
+
+        // Step 4: Members of the company can't be invited again
+        if let Some(user) = UserQuery::get_user_by_email(conn, &email).await? {
+            if CompaniesQuery::get_user_company(conn, user.id, request.company_id)
+                .await?
+                .is_some()
+            {
+                return Err(Status::failed_precondition("already_company_member"));
+            }
+        }
+
+        let company = CompaniesQuery::get_company_by_id(conn, request.company_id)
+            .await?
+            .ok_or_else(|| Status::not_found("Company not found: Invalid company ID"))?;
+
+        // Step 5: Store the invitation; earlier invitations of the email to the company stop working
+        let invitation_token = OneTimeToken::company_invitation();
+        let invitation = CompanyInvitationsQuery::create_invitation(
+            conn,
+            company.id,
+            email,
+            role_id,
+            user_id_from_token as i32,
+            invitation_token.token_hash,
+            invitation_token.expires_at,
+        )
+        .await?;
+
+        // A delivery failure is only logged, the invitation can be sent again
+        if let Err(err) = self
+            .mailer
+            .send(company_invitation_message(
+                invitation.email.clone(),
+                &company.name,
+                &invitation_token.token,
+            ))
+            .await
+        {
+            event!(target: "hellai_app_core_events", Level::ERROR, "Failed to send invitation {} email: {}", invitation.id, err);
+        }
+
+        // Step 6: Prepare the response with the pending invitation
+        let response = Response::new(CompanyInvitationResponse {
+            invitation_id: invitation.id,
+            company_id: company.id,
+            company_name: company.name,
+            email: invitation.email,
+            user_role: invitation.role_id - 1, // Adjust to match the gRPC enum by subtracting 1
+            invited_by: invitation.invited_by,
+            expires_at: invitation.expires_at.to_string(),
+            created_at: invitation.created_at.to_string(),
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Accepts an invitation and adds the authenticated user to the company with the invited role.
+    ///
+    /// The invitation is identified by the token from the invitation link or, for users with a verified email,
+    /// by its ID. It must have been sent to the email of the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `InvitationRequest`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<CompanyUserInfoResponse>, Status>` - A response with the user's role in the company,
+    ///   or `invalid_invitation` if the invitation is not pending or was sent to another email.
+    async fn accept_invitation(
+        &self,
+        request: Request<InvitationRequest>,
+    ) -> Result<Response<CompanyUserInfoResponse>, Status> {
+        // The request may carry a token, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received accept invitation request");
+
+        // Step 1: Get the user authenticated by the auth layer
+        let auth_context = interceptors::auth_context(&request)?.clone();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        let user = match UserQuery::get_user_by_id(conn, auth_context.user_id as i32).await? {
+            Some(user) => user,
+            None => return Err(Status::not_found("User not found: Invalid user ID")),
+        };
+
+        // Step 2: Find the pending invitation sent to the user
+        let invitation = find_user_invitation(
+            conn,
+            &user.email,
+            auth_context.email_verified,
+            request.into_inner().invitation,
+        )
+        .await?;
+
+        // Step 3: Accept it and join the company
+        let user_company =
+            CompanyInvitationsQuery::accept_invitation(conn, invitation.id, user.id, &user.email)
+                .await?
+                .ok_or_else(|| Status::invalid_argument("invalid_invitation"))?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "User {} accepted invitation {}", user.id, invitation.id);
+
+        // Step 4: Prepare the response with the user's role in the company
+        let response = Response::new(CompanyUserInfoResponse {
+            user_id: user_company.user_id,
+            user_role: user_company.role_id - 1, // Adjust to match the gRPC enum by subtracting 1
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Declines an invitation sent to the authenticated user.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `InvitationRequest`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<StatusResponse>, Status>` - A response indicating success if the invitation was declined,
+    ///   or `invalid_invitation` if the invitation is not pending or was sent to another email.
+    async fn decline_invitation(
+        &self,
+        request: Request<InvitationRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // The request may carry a token, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received decline invitation request");
+
+        // Step 1: Get the user authenticated by the auth layer
+        let auth_context = interceptors::auth_context(&request)?.clone();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        let user = match UserQuery::get_user_by_id(conn, auth_context.user_id as i32).await? {
+            Some(user) => user,
+            None => return Err(Status::not_found("User not found: Invalid user ID")),
+        };
+
+        // Step 2: Find the pending invitation sent to the user
+        let invitation = find_user_invitation(
+            conn,
+            &user.email,
+            auth_context.email_verified,
+            request.into_inner().invitation,
+        )
+        .await?;
+
+        // Step 3: Decline it
+        if !CompanyInvitationsQuery::decline_invitation(conn, invitation.id, &user.email).await? {
+            return Err(Status::invalid_argument("invalid_invitation"));
+        }
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "User {} declined invitation {}", user.id, invitation.id);
+
+        // Step 4: Prepare a success response
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Lists pending invitations, newest first.
+    ///
+    /// With a company ID the invitations sent by the company are listed, which requires the `member.invite`
+    /// privilege. Without it the invitations sent to the email of the authenticated user are listed,
+    /// which requires a verified email.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `ListPendingInvitationsRequest`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<ListPendingInvitationsResponse>, Status>` - A response with the pending invitations.
+    async fn list_pending_invitations(
+        &self,
+        request: Request<ListPendingInvitationsRequest>,
+    ) -> Result<Response<ListPendingInvitationsResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received list pending invitations request: {:?}", request);
+
+        // Step 1: Get the user authenticated by the auth layer
+        let auth_context = interceptors::auth_context(&request)?.clone();
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Load the invitations of the company or of the user
+        let invitations = match request.company_id {
+            Some(company_id) => {
+                let user_company_access =
+                    check_company_permission(conn, auth_context.user_id as i32, company_id).await?;
+
+                authorize(
+                    &self.roles,
+                    &Principal::new(auth_context.user_id as i32, user_company_access.role_id),
+                    Action::MemberInvite,
+                    &Resource::default(),
+                )?;
+
+                CompanyInvitationsQuery::get_pending_company_invitations(conn, company_id).await?
+            }
+            None => {
+                // Invitations are only shown to the owner of the email
+                if !auth_context.email_verified {
+                    return Err(Status::permission_denied("email_not_verified"));
+                }
+
+                let user =
+                    match UserQuery::get_user_by_id(conn, auth_context.user_id as i32).await? {
+                        Some(user) => user,
+                        None => return Err(Status::not_found("User not found: Invalid user ID")),
+                    };
+
+                CompanyInvitationsQuery::get_pending_user_invitations(conn, &user.email).await?
+            }
+        };
+
+        // Step 3: Transform the database results into the gRPC response format
+        let response = Response::new(ListPendingInvitationsResponse {
+            invitations: invitations
+                .into_iter()
+                .map(|invitation| CompanyInvitationResponse {
+                    invitation_id: invitation.id,
+                    company_id: invitation.company_id,
+                    company_name: invitation.company_name,
+                    email: invitation.email,
+                    user_role: invitation.role_id - 1, // Adjust to match the gRPC enum by subtracting 1
+                    invited_by: invitation.invited_by,
+                    expires_at: invitation.expires_at.to_string(),
+                    created_at: invitation.created_at.to_string(),
+                })
+                .collect(),
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Revokes a pending invitation of a company; its link stops working immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `RevokeInvitationRequest`, which includes the invitation ID.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<StatusResponse>, Status>` - A response indicating success if the invitation was revoked,
+    ///   or a permission error if the authenticated user may not manage the company's invitations.
+    async fn revoke_invitation(
+        &self,
+        request: Request<RevokeInvitationRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received revoke invitation request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Find the pending invitation
+        let invitation =
+            CompanyInvitationsQuery::get_pending_invitation(conn, request.invitation_id)
+                .await?
+                .ok_or_else(|| Status::not_found("invitation_not_found"))?;
+
+        // Step 3: Verify that the authenticated user may manage the company's invitations
+        let user_company_access =
+            check_company_permission(conn, user_id_from_token as i32, invitation.company_id)
+                .await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+            Action::MemberInvite,
+            &Resource::default(),
+        )?;
+
+        // Step 4: Revoke it
+        if !CompanyInvitationsQuery::revoke_invitation(conn, invitation.id).await? {
+            return Err(Status::not_found("invitation_not_found"));
+        }
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Invitation {} revoked by {}", invitation.id, user_id_from_token);
+
+        // Step 5: Prepare a success response
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Lists the members of a company with their roles and access levels, most privileged roles first.
+    ///
+    /// Every member of the company may list its members.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `ListCompanyMembersRequest`, which includes
+    ///   the company ID and the requested page.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<ListCompanyMembersResponse>, Status>` - A response with a page of members and whether
+    ///   more pages exist, or a permission error if the authenticated user is not a member of the company.
+    async fn list_company_members(
+        &self,
+        request: Request<ListCompanyMembersRequest>,
+    ) -> Result<Response<ListCompanyMembersResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received list company members request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Only members of the company may see its members
+        check_company_permission(conn, user_id_from_token as i32, request.company_id).await?;
+
+        let page_size = match request.page_size {
+            0 => DEFAULT_MEMBERS_PAGE_SIZE,
+            page_size => page_size.min(MAX_MEMBERS_PAGE_SIZE),
+        } as u64;
+        let offset = request.page as u64 * page_size;
+
+        // Step 3: Fetch one extra member to find out whether another page exists
+        let mut members =
+            CompaniesQuery::get_company_members(conn, request.company_id, page_size + 1, offset)
+                .await?;
+
+        let has_more = members.len() as u64 > page_size;
+        members.truncate(page_size as usize);
+
+        // Step 4: Transform the database results into the gRPC response format
+        let response = Response::new(ListCompanyMembersResponse {
+            members: members
+                .into_iter()
+                .map(|member| CompanyMemberResponse {
+                    user_id: member.user_id,
+                    login: member.login,
+                    user_name: member.user_name,
+                    email: member.email,
+                    is_active: member.is_active,
+                    user_role: member.role_id - 1, // Adjust to match the gRPC enum by subtracting 1
+                    access_level: access_level_to_proto(member.access_level),
+                })
+                .collect(),
+            has_more,
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Promotes or demotes a member of a company.
+    ///
+    /// The authenticated user must hold a higher role than both the member's current and new role;
+    /// owners may change any role. The last owner of a company can't be demoted.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `ChangeMemberRoleRequest`, which includes
+    ///   the company ID, the ID of the member and their new role.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<CompanyUserInfoResponse>, Status>` - A response with the member's new role,
+    ///   or a permission error if the authenticated user may not change it.
+    async fn change_member_role(
+        &self,
+        request: Request<ChangeMemberRoleRequest>,
+    ) -> Result<Response<CompanyUserInfoResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received change member role request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        let role_id = role_id_from_proto(request.user_role)?;
+
+        // Step 2: Verify that the authenticated user may change roles in the company
+        let user_company_access =
+            check_company_permission(conn, user_id_from_token as i32, request.company_id).await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+            Action::MemberChangeRole,
+            &Resource::default(),
+        )?;
+
+        // Step 3: Verify that the authenticated user ranks above the member's current and new role
+        let member_company_access =
+            CompaniesQuery::get_user_company(conn, request.user_id, request.company_id)
+                .await?
+                .ok_or_else(|| Status::not_found("member_not_found"))?;
+
+        authorize_role_change(
+            &self.roles,
+            user_company_access.role_id,
+            member_company_access.role_id,
+            role_id,
+        )?;
+
+        // Step 4: Change the role, keeping at least one owner
+        let txn = conn.begin().await.map_err(CoreErrors::from)?;
+        let user_company =
+            CompaniesQuery::change_member_role(&txn, request.user_id, request.company_id, role_id)
+                .await?
+                .ok_or_else(|| Status::failed_precondition("last_company_owner"))?;
+        txn.commit().await.map_err(CoreErrors::from)?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Role of user {} in company {} changed to {} by {}", request.user_id, request.company_id, role_id, user_id_from_token);
+
+        // Step 5: Prepare the response with the member's new role
+        let response = Response::new(CompanyUserInfoResponse {
+            user_id: user_company.user_id,
+            user_role: user_company.role_id - 1, // Adjust to match the gRPC enum by subtracting 1
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Hands the owner role of a company over to another member.
+    ///
+    /// The roles of the owner and the recipient are swapped in one step, so the former owner takes over
+    /// the recipient's role. The recipient must already be a member of the company.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `TransferCompanyOwnershipRequest`, which includes
+    ///   the company ID and the ID of the new owner.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<StatusResponse>, Status>` - A response indicating success if the ownership was transferred,
+    ///   or a permission error if the authenticated user is not the owner of the company.
+    async fn transfer_company_ownership(
+        &self,
+        request: Request<TransferCompanyOwnershipRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received transfer company ownership request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        if user_id_from_token as i32 == request.user_id {
+            return Err(Status::invalid_argument("cannot_transfer_to_self"));
+        }
+
+        // Step 2: Verify that the authenticated user owns the company
+        let user_company_access =
+            check_company_permission(conn, user_id_from_token as i32, request.company_id).await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+            Action::CompanyTransferOwnership,
+            &Resource::default(),
+        )?;
+
+        // Step 3: The recipient must already be a member without the owner role
+        match CompaniesQuery::get_user_company(conn, request.user_id, request.company_id).await? {
+            Some(member) if self.roles.level(member.role_id) == Some(OWNER_LEVEL) => {
+                return Err(Status::failed_precondition("already_company_owner"))
+            }
+            Some(_) => {}
+            None => return Err(Status::failed_precondition("not_company_member")),
+        }
+
+        // Step 4: Swap the roles of the owner and the recipient
+        if !CompaniesQuery::transfer_company_ownership(
+            conn,
+            user_id_from_token as i32,
+            request.user_id,
+            request.company_id,
+        )
+        .await?
+        {
+            return Err(Status::failed_precondition("ownership_transfer_failed"));
+        }
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Ownership of company {} transferred from {} to {}", request.company_id, user_id_from_token, request.user_id);
+
+        // Step 5: Prepare a success response
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Removes the authenticated user from a company.
+    ///
+    /// The last owner can't leave; they have to transfer the ownership or delete the company first.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `LeaveCompanyRequest`, which includes the company ID.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<StatusResponse>, Status>` - A response indicating success if the user left the company,
+    ///   or `last_company_owner` if the user is its last owner.
+    async fn leave_company(
+        &self,
+        request: Request<LeaveCompanyRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received leave company request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Leave the company, keeping at least one owner
+        leave_company(conn, user_id_from_token as i32, request.company_id).await?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "User {} left company {}", user_id_from_token, request.company_id);
+
+        // Step 3: Prepare a success response
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+    /// Updates the name, description and contact information of a company.
+    ///
+    /// Omitted fields are left unchanged and an empty description or contact information clears it.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `UpdateCompanyRequest`, which includes the company ID
+    ///   and the fields to update.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<CompanyInfoResponse>, Status>` - A response with the updated company,
+    ///   or a permission error if the authenticated user may not edit the company.
+    async fn update_company(
+        &self,
+        request: Request<UpdateCompanyRequest>,
+    ) -> Result<Response<CompanyInfoResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received update company request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Verify that the authenticated user may edit the company
+        let user_company_access =
+            check_company_permission(conn, user_id_from_token as i32, request.company_id).await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+            Action::CompanyUpdate,
+            &Resource::default(),
+        )?;
+
+        // Step 3: Validate the new name with the same checks as on creation
+        let name = match request.name {
+            Some(name) => {
+                let composite_validator = CompositValidator::new(vec![
+                    empty_validation,
+                    min_symbols_validator_3,
+                    max_symbols_validator_20,
+                    no_special_symbols_validator,
+                ]);
+
+                Some(composite_validator.validate(name)?)
+            }
+            None => None,
+        };
+
+        // An empty value clears the optional fields
+        let description = request
+            .description
+            .map(|description| Some(description).filter(|value| !value.is_empty()));
+        let contact_info = request
+            .contact_info
+            .map(|contact_info| Some(contact_info).filter(|value| !value.is_empty()));
+
+        // Step 4: Update the company
+        let company = CompaniesQuery::update_company(
+            conn,
+            request.company_id,
+            name,
+            description,
+            contact_info,
+        )
+        .await?
+        .ok_or_else(|| Status::not_found("company_not_found"))?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Company {} updated by user {}", company.id, user_id_from_token);
+
+        // Step 5: Prepare a response with the updated company
+        let response = Response::new(company_info_response(company));
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Changes the alias of a company.
+    ///
+    /// The alias must not be reserved or used by another company, including as a former alias.
+    /// The former alias of the company keeps resolving to it in `get_company_by_alias`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `ChangeCompanyAliasRequest`, which includes the company ID
+    ///   and the new alias.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<CompanyInfoResponse>, Status>` - A response with the updated company,
+    ///   or an error if the alias is invalid, reserved or taken.
+    async fn change_company_alias(
+        &self,
+        request: Request<ChangeCompanyAliasRequest>,
+    ) -> Result<Response<CompanyInfoResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received change company alias request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Verify that the authenticated user may change the alias
+        let user_company_access =
+            check_company_permission(conn, user_id_from_token as i32, request.company_id).await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+            Action::CompanyChangeAlias,
+            &Resource::default(),
+        )?;
+
+        // Step 3: Validate the alias
+        let name_alias = validators::company_alias_validator(request.name_alias)?;
+
+        // Step 4: Change the alias if it is free, keeping the former one as a redirect
+        let txn = conn.begin().await.map_err(CoreErrors::from)?;
+        let alias_change =
+            CompaniesQuery::change_company_alias(&txn, request.company_id, name_alias).await?;
+        let company = match alias_change {
+            AliasChange::Changed(company) => company,
+            AliasChange::Unavailable => return Err(Status::already_exists("company_alias_taken")),
+            AliasChange::CompanyNotFound => return Err(Status::not_found("company_not_found")),
+        };
+        txn.commit().await.map_err(CoreErrors::from)?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Alias of company {} changed to {} by user {}", company.id, company.name_alias, user_id_from_token);
+
+        // Step 5: Prepare a response with the updated company
+        let response = Response::new(company_info_response(company));
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Retrieves a company by its current or a former alias.
+    ///
+    /// Only members of the company can look it up. A former alias is reported as `redirected`,
+    /// so clients can switch to the current alias.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `GetCompanyByAliasRequest`, which includes the alias.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<GetCompanyByAliasResponse>, Status>` - A response with the company,
+    ///   or `company_not_found` if no company uses the alias.
+    async fn get_company_by_alias(
+        &self,
+        request: Request<GetCompanyByAliasRequest>,
+    ) -> Result<Response<GetCompanyByAliasResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received get company by alias request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Resolve the alias, following former aliases
+        let name_alias = request.name_alias.trim().to_lowercase();
+
+        let (company, redirected) = CompaniesQuery::get_company_by_alias(conn, &name_alias)
+            .await?
+            .ok_or_else(|| Status::not_found("company_not_found"))?;
+
+        // Step 3: Verify that the authenticated user is a member of the company
+        check_company_permission(conn, user_id_from_token as i32, company.id).await?;
+
+        // Step 4: Prepare a response with the company
+        let response = Response::new(GetCompanyByAliasResponse {
+            company: Some(company_info_response(company)),
+            redirected,
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+}
+
+/// Number of members returned per page when the request does not specify a page size.
+const DEFAULT_MEMBERS_PAGE_SIZE: u32 = 20;
+
+/// Upper bound for the page size of `list_company_members`.
+const MAX_MEMBERS_PAGE_SIZE: u32 = 100;
+
+/// Converts a company into its gRPC representation.
+fn company_info_response(company: companies::Model) -> CompanyInfoResponse {
+    CompanyInfoResponse {
+        id: company.id,
+        name: company.name,
+        name_alias: company.name_alias,
+        description: company.description,
+        contact_info: company.contact_info,
+    }
+}
+
+/// Removes a user from a company unless they are its last owner.
+///
+/// # Errors
+/// * Returns `Status::permission_denied` if the user is not a member of the company.
+/// * Returns `Status::failed_precondition` with `last_company_owner` if the user is its last owner.
+async fn leave_company(conn: &DbConn, user_id: i32, company_id: i32) -> Result<(), Status> {
+    check_company_permission(conn, user_id, company_id).await?;
+
+    let txn = conn.begin().await.map_err(CoreErrors::from)?;
+    if !CompaniesQuery::leave_company(&txn, user_id, company_id).await? {
+        return Err(Status::failed_precondition("last_company_owner"));
+    }
+    txn.commit().await.map_err(CoreErrors::from)?;
+
+    Ok(())
+}
+
+/// Checks that a user may give a company member another role.
+///
+/// Owners may change any role, including those of other owners. Everybody else must rank above
+/// both the member's current role and the new role, so nobody can promote a member to their own
+/// level or touch the role of an equal.
+///
+/// # Errors
+/// * Returns `Status::permission_denied` if the role change is not allowed.
+#[allow(clippy::result_large_err)]
+fn authorize_role_change(
+    roles: &RoleHierarchy,
+    role_id: i32,
+    member_role_id: i32,
+    new_role_id: i32,
+) -> Result<(), Status> {
+    let is_owner = roles.has_level(role_id, OWNER_LEVEL);
+
+    if is_owner || (roles.outranks(role_id, member_role_id) && roles.outranks(role_id, new_role_id))
+    {
+        return Ok(());
+    }
+
+    Err(Status::permission_denied("permission_denied"))
+}
+
+/// Checks that a user may invite members with a role.
+///
+/// Nobody can invite members with a role that outranks their own.
+///
+/// # Errors
+/// * Returns `Status::permission_denied` if the invited role outranks the role of the user.
+#[allow(clippy::result_large_err)]
+fn authorize_invited_role(
+    roles: &RoleHierarchy,
+    role_id: i32,
+    invited_role_id: i32,
+) -> Result<(), Status> {
+    if roles.outranks(invited_role_id, role_id) {
+        return Err(Status::permission_denied("permission_denied"));
+    }
+
+    Ok(())
+}
+
+/// Finds a pending invitation sent to the email of a user.
+///
+/// An invitation can be identified by the token from its link, which proves access to the email, or by its ID,
+/// which is only accepted from users with a verified email.
+///
+/// # Errors
+/// * Returns `Status::invalid_argument` with `invalid_invitation` if the invitation is unknown, no longer pending
+///   or was sent to another email.
+/// * Returns `Status::permission_denied` with `email_not_verified` if an unverified user passes an ID.
+async fn find_user_invitation(
+    conn: &DbConn,
+    email: &str,
+    email_verified: bool,
+    invitation: Option<Invitation>,
+) -> Result<company_invitations::Model, Status> {
+    let invitation = match invitation {
+        Some(Invitation::Token(token)) => {
+            CompanyInvitationsQuery::get_pending_invitation_by_token(conn, &hash_token(&token))
+                .await?
+        }
+        Some(Invitation::InvitationId(invitation_id)) => {
+            if !email_verified {
+                return Err(Status::permission_denied("email_not_verified"));
+            }
+
+            CompanyInvitationsQuery::get_pending_invitation(conn, invitation_id).await?
+        }
+        None => None,
+    };
+
+    invitation
+        .filter(|invitation| invitation.email.to_lowercase() == email.to_lowercase())
+        .ok_or_else(|| Status::invalid_argument("invalid_invitation"))
+}
+
+/// Converts a gRPC `ProjectRole` enum value into the role ID stored in the database.
+fn role_id_from_proto(role: i32) -> Result<i32, CoreErrors> {
+    match ProjectRole::try_from(role) {
+        Ok(role) => Ok(role as i32 + 1), // Adjust the gRPC enum to the role ID by adding 1
+        Err(_) => Err(CoreErrors::DataValidationError(
+            "validator_invalid_role".to_string(),
+        )),
+    }
+}
+
+/// Converts an access level stored in the database into the gRPC `CompanyAccessLevel` enum value.
+fn access_level_to_proto(access_level: AccessLevelType) -> i32 {
+    match access_level {
+        AccessLevelType::Full => CompanyAccessLevel::Full as i32,
+        AccessLevelType::Limited => CompanyAccessLevel::Limited as i32,
+        AccessLevelType::Restricted => CompanyAccessLevel::Restricted as i32,
+    }
+}
+
+/// Builds the email containing the company invitation link.
+///
+/// The link points to `COMPANY_INVITATION_URL` with the token appended as the `token` query parameter.
+fn company_invitation_message(email: String, company_name: &str, token: &str) -> MailMessage {
+    let invitation_url = env::var("COMPANY_INVITATION_URL")
+        .unwrap_or("http://localhost:3000/accept-invitation".to_string());
+
+    MailMessage {
+        to: email,
+        subject: format!("You're invited to join {} on Hellai", company_name),
+        body: format!(
+            "You have been invited to join {} on Hellai.\n\n\
+             Open the link below to accept the invitation:\n{}?token={}\n\n\
+             If you don't have an account yet, you can create one with this email address.\n",
+            company_name, invitation_url, token
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core_database::{
+        entity::roles,
+        queries::roles_query::{ADMINISTRATOR_LEVEL, GUEST_LEVEL, MANAGER_LEVEL, USER_LEVEL},
+    };
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase};
+    use tonic::Code;
+
+    use super::*;
+
+    const OWNER: i32 = 1;
+    const ADMINISTRATOR: i32 = 2;
+    const MANAGER: i32 = 3;
+    const USER: i32 = 4;
+    const GUEST: i32 = 6;
+    // A custom role with guest level that inherits from the administrator role
+    const DEPUTY: i32 = 7;
+
+    fn role(id: i32, level: i32, parent_role_id: Option<i32>) -> roles::Model {
+        roles::Model {
+            id,
+            name: format!("role-{}", id),
+            description: None,
+            parent_role_id,
+            level,
+        }
+    }
+
+    fn hierarchy() -> RoleHierarchy {
+        RoleHierarchy::new(vec![
+            role(OWNER, OWNER_LEVEL, None),
+            role(ADMINISTRATOR, ADMINISTRATOR_LEVEL, None),
+            role(MANAGER, MANAGER_LEVEL, None),
+            role(USER, USER_LEVEL, None),
+            role(GUEST, GUEST_LEVEL, None),
+            role(DEPUTY, GUEST_LEVEL, Some(ADMINISTRATOR)),
+        ])
+    }
+
+    fn invitation(email: &str) -> company_invitations::Model {
+        company_invitations::Model {
+            id: 1,
+            company_id: 1,
+            email: email.to_string(),
+            role_id: USER,
+            invited_by: Some(1),
+            token_hash: hash_token("invitation-token"),
+            expires_at: Default::default(),
+            accepted_at: None,
+            declined_at: None,
+            revoked_at: None,
+            created_at: Default::default(),
+        }
+    }
+
+    /// A database holding the given pending invitation, or none.
+    fn invitation_database(invitation: Option<company_invitations::Model>) -> DatabaseConnection {
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([invitation.into_iter().collect::<Vec<_>>()])
+            .into_connection()
+    }
+
+    #[test]
+    fn invitations_can_not_outrank_the_inviter() {
+        let roles = hierarchy();
+
+        assert!(authorize_invited_role(&roles, ADMINISTRATOR, ADMINISTRATOR).is_ok());
+        assert!(authorize_invited_role(&roles, ADMINISTRATOR, GUEST).is_ok());
+        assert!(authorize_invited_role(&roles, DEPUTY, ADMINISTRATOR).is_ok());
+        assert!(authorize_invited_role(&roles, OWNER, OWNER).is_ok());
+
+        let status = authorize_invited_role(&roles, ADMINISTRATOR, OWNER).unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert!(authorize_invited_role(&roles, MANAGER, ADMINISTRATOR).is_err());
+        assert!(authorize_invited_role(&roles, GUEST, USER).is_err());
+    }
+
+    #[test]
+    fn owners_may_change_any_role() {
+        let roles = hierarchy();
+
+        assert!(authorize_role_change(&roles, OWNER, OWNER, ADMINISTRATOR).is_ok());
+        assert!(authorize_role_change(&roles, OWNER, GUEST, OWNER).is_ok());
+    }
+
+    #[test]
+    fn role_changes_need_to_outrank_the_current_and_new_role() {
+        let roles = hierarchy();
+
+        assert!(authorize_role_change(&roles, ADMINISTRATOR, USER, MANAGER).is_ok());
+        assert!(authorize_role_change(&roles, ADMINISTRATOR, MANAGER, GUEST).is_ok());
+        // Inherited privileges count like the parent role's
+        assert!(authorize_role_change(&roles, DEPUTY, USER, MANAGER).is_ok());
+
+        let status = authorize_role_change(&roles, ADMINISTRATOR, USER, ADMINISTRATOR).unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert!(authorize_role_change(&roles, ADMINISTRATOR, ADMINISTRATOR, USER).is_err());
+        assert!(authorize_role_change(&roles, ADMINISTRATOR, OWNER, USER).is_err());
+        assert!(authorize_role_change(&roles, MANAGER, USER, MANAGER).is_err());
+    }
+
+    #[tokio::test]
+    async fn invitation_links_are_accepted_for_the_invited_email() {
+        let db = invitation_database(Some(invitation("New.User@example.com")));
+
+        let found = find_user_invitation(
+            &db,
+            "new.user@example.com",
+            false,
+            Some(Invitation::Token("invitation-token".to_string())),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(found.id, 1);
+    }
+
+    #[tokio::test]
+    async fn invitations_of_other_emails_are_rejected() {
+        let db = invitation_database(Some(invitation("someone@example.com")));
+
+        let status = find_user_invitation(
+            &db,
+            "new.user@example.com",
+            true,
+            Some(Invitation::Token("invitation-token".to_string())),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "invalid_invitation");
+    }
+
+    #[tokio::test]
+    async fn invitation_ids_require_a_verified_email() {
+        let db = invitation_database(Some(invitation("new.user@example.com")));
+
+        let status = find_user_invitation(
+            &db,
+            "new.user@example.com",
+            false,
+            Some(Invitation::InvitationId(1)),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(status.message(), "email_not_verified");
+        // The invitation is not even looked up
+        assert!(db.into_transaction_log().is_empty());
+
+        let db = invitation_database(Some(invitation("new.user@example.com")));
+        let found = find_user_invitation(
+            &db,
+            "new.user@example.com",
+            true,
+            Some(Invitation::InvitationId(1)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(found.id, 1);
+    }
+
+    #[tokio::test]
+    async fn unknown_and_missing_invitations_are_rejected() {
+        let db = invitation_database(None);
+
+        let status = find_user_invitation(
+            &db,
+            "new.user@example.com",
+            true,
+            Some(Invitation::Token("expired-token".to_string())),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.message(), "invalid_invitation");
+
+        let status = find_user_invitation(&db, "new.user@example.com", true, None)
+            .await
+            .unwrap_err();
+        assert_eq!(status.message(), "invalid_invitation");
+    }
 }
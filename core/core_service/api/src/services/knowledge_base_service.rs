@@ -0,0 +1,523 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use core_database::{
+    entity::{knowledge_base, sea_orm_active_enums::AccessLevelType},
+    queries::knowledge_base_query::KnowledgeBaseQuery,
+};
+use core_debugger::tracing::{event, Level};
+use core_error::core_errors::CoreErrors;
+use tonic::{Request, Response, Status};
+
+use crate::{
+    helai_api_core_service::{
+        knowledge_base_service_server::KnowledgeBaseService, ArticleAccessLevel, ArticleResponse,
+        CreateArticleRequest, DeleteArticleRequest, GetArticleRequest, ListArticlesRequest,
+        ListArticlesResponse, ProjectRole, StatusResponse, UpdateArticleRequest,
+    },
+    middleware::{
+        access_check::{
+            can_read_article, check_knowledge_base_permission, get_knowledge_base_reader,
+        },
+        interceptors,
+        policy::{authorize, Action, Principal, Resource},
+        validators::{
+            empty_validation, max_symbols_validator_250, min_symbols_validator_3, CompositValidator,
+        },
+    },
+    my_server::MyServer,
+};
+
+// Implementing the KnowledgeBaseService trait for MyServer
+#[tonic::async_trait]
+impl KnowledgeBaseService for MyServer {
+    /// Creates a new knowledge base article in a company or one of its projects.
+    ///
+    /// The author must be at least a "Manager" in the scope and must be able to read the article
+    /// they create, so nobody can publish documents above their own access.
+    ///
+    /// # Arguments
+    /// * `request` - A `Request` object containing the scope and the article details.
+    ///
+    /// # Returns
+    /// * `Result<Response<ArticleResponse>, Status>` - A gRPC response containing the created article,
+    ///   or a gRPC `Status` error if validation or permission checks fail.
+    ///
+    /// # Errors
+    /// * Returns `Status::permission_denied` if the user's role is lower than "Manager"
+    ///   or the article would not be readable by the author.
+    async fn create_article(
+        &self,
+        request: Request<CreateArticleRequest>,
+    ) -> Result<Response<ArticleResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received create article request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Validate the article details
+        let composite_validator_title = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_250,
+        ]);
+
+        let validated_title = composite_validator_title.validate(request.title)?;
+        let access_level = access_level_from_proto(request.access_level)?;
+        let role_id = request.min_role.map(role_id_from_proto).transpose()?;
+
+        // Step 3: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 4: Resolve the user's role in the company or project
+        let reader = check_knowledge_base_permission(
+            conn,
+            user_id_from_token as i32,
+            request.company_id,
+            request.project_id,
+        )
+        .await?;
+
+        // Step 5: Create the article in the database
+        let article = knowledge_base::Model {
+            id: 0,
+            company_id: request.company_id,
+            project_id: request.project_id,
+            title: validated_title,
+            content: Some(request.content),
+            access_level,
+            role_id,
+        };
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, reader.role_id),
+            Action::ArticleWrite,
+            &Resource::default(),
+        )?;
+
+        // The author must be able to read the article they create
+        if !can_read_article(&self.roles, &article, &reader) {
+            event!(
+                target: "hellai_app_core_events",
+                Level::DEBUG,
+                "Permission denied: User lacks sufficient privileges to create article"
+            );
+
+            return Err(Status::permission_denied("permission_denied"));
+        }
+
+        let article = KnowledgeBaseQuery::create_article(
+            conn,
+            article.company_id,
+            article.project_id,
+            article.title,
+            article.content.unwrap_or_default(),
+            article.access_level,
+            article.role_id,
+        )
+        .await?;
+
+        // Step 6: Construct and return the response
+        let response = Response::new(article_response(article));
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Article created successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Updates the title, content, access level or minimum role of an article.
+    ///
+    /// The editor must be at least a "Manager", must be able to read the article,
+    /// and must still be able to read it after the change.
+    ///
+    /// # Arguments
+    /// * `request` - A `Request` object containing the article ID and the fields to change.
+    ///
+    /// # Returns
+    /// * `Result<Response<ArticleResponse>, Status>` - A gRPC response containing the updated article,
+    ///   or a gRPC `Status` error if validation or permission checks fail.
+    async fn update_article(
+        &self,
+        request: Request<UpdateArticleRequest>,
+    ) -> Result<Response<ArticleResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received update article request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Validate only the fields that are being changed
+        let composite_validator_title = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_250,
+        ]);
+
+        let title = request
+            .title
+            .map(|title| composite_validator_title.validate(title))
+            .transpose()?;
+        let access_level = request
+            .access_level
+            .map(access_level_from_proto)
+            .transpose()?;
+        let role_id = if request.clear_min_role {
+            Some(None)
+        } else {
+            request
+                .min_role
+                .map(role_id_from_proto)
+                .transpose()?
+                .map(Some)
+        };
+
+        // Step 3: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 4: Load the article and check that the user can read and edit it
+        let article = match KnowledgeBaseQuery::get_article_by_id(conn, request.article_id).await? {
+            Some(article) => article,
+            None => return Err(Status::permission_denied("permission_denied")),
+        };
+
+        let reader = check_knowledge_base_permission(
+            conn,
+            user_id_from_token as i32,
+            article.company_id,
+            article.project_id,
+        )
+        .await?;
+
+        // The article must stay readable for the editor once the change is applied
+        let mut changed_article = article.clone();
+        if let Some(access_level) = &access_level {
+            changed_article.access_level = access_level.clone();
+        }
+        if let Some(role_id) = role_id {
+            changed_article.role_id = role_id;
+        }
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, reader.role_id),
+            Action::ArticleWrite,
+            &Resource::default(),
+        )?;
+
+        // The author must be able to read the article before and after the change
+        if !can_read_article(&self.roles, &article, &reader)
+            || !can_read_article(&self.roles, &changed_article, &reader)
+        {
+            event!(
+                target: "hellai_app_core_events",
+                Level::DEBUG,
+                "Permission denied: User lacks sufficient privileges to update article"
+            );
+
+            return Err(Status::permission_denied("permission_denied"));
+        }
+
+        // Step 5: Apply the changes
+        let article = KnowledgeBaseQuery::update_article(
+            conn,
+            article,
+            title,
+            request.content,
+            access_level,
+            role_id,
+        )
+        .await?;
+
+        // Step 6: Construct and return the response
+        let response = Response::new(article_response(article));
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Article updated successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Deletes an article.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the article ID to be deleted.
+    ///
+    /// # Returns
+    /// * `Result<Response<StatusResponse>, Status>` - Returns a gRPC response indicating success,
+    ///   or a gRPC `Status` error if the user lacks sufficient permissions or if any database operation fails.
+    ///
+    /// # Errors
+    /// * Returns `Status::permission_denied` if the user's role is lower than "Administrator"
+    ///   or the user cannot read the article.
+    async fn delete_article(
+        &self,
+        request: Request<DeleteArticleRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received delete article request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 3: Load the article and verify the user's permissions
+        let article = match KnowledgeBaseQuery::get_article_by_id(conn, request.article_id).await? {
+            Some(article) => article,
+            None => return Err(Status::permission_denied("permission_denied")),
+        };
+
+        let reader = check_knowledge_base_permission(
+            conn,
+            user_id_from_token as i32,
+            article.company_id,
+            article.project_id,
+        )
+        .await?;
+
+        // Allow deletion only if the user's role is sufficiently privileged
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, reader.role_id),
+            Action::ArticleDelete,
+            &Resource::default(),
+        )?;
+
+        if !can_read_article(&self.roles, &article, &reader) {
+            event!(
+                target: "hellai_app_core_events",
+                Level::DEBUG,
+                "Permission denied: User lacks sufficient privileges to delete article"
+            );
+
+            return Err(Status::permission_denied("permission_denied"));
+        }
+
+        // Step 4: Delete the article
+        KnowledgeBaseQuery::delete_article(conn, article.id).await?;
+
+        // Step 5: Construct a success response
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Article deleted successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Retrieves a single article if the user may read it.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the article ID.
+    ///
+    /// # Returns
+    /// * `Result<Response<ArticleResponse>, Status>` - A response containing the article,
+    ///   or `Status::permission_denied` if the article does not exist or the user may not read it.
+    async fn get_article(
+        &self,
+        request: Request<GetArticleRequest>,
+    ) -> Result<Response<ArticleResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received get article request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 3: Load the article and check the read permissions
+        let article = match KnowledgeBaseQuery::get_article_by_id(conn, request.article_id).await? {
+            Some(article) => article,
+            None => return Err(Status::permission_denied("permission_denied")),
+        };
+
+        let reader = check_knowledge_base_permission(
+            conn,
+            user_id_from_token as i32,
+            article.company_id,
+            article.project_id,
+        )
+        .await?;
+
+        if !can_read_article(&self.roles, &article, &reader) {
+            return Err(Status::permission_denied("permission_denied"));
+        }
+
+        // Step 4: Construct and return the response
+        let response = Response::new(article_response(article));
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Retrieved article successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Lists the articles of a company, or of a single project, that the user may read.
+    ///
+    /// Articles the user may not read are silently left out of the list.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the company ID and an optional project ID.
+    ///
+    /// # Returns
+    /// * `Result<Response<ListArticlesResponse>, Status>` - A response containing the visible articles,
+    ///   or `Status::permission_denied` if the user has no access to the requested scope.
+    async fn list_articles(
+        &self,
+        request: Request<ListArticlesRequest>,
+    ) -> Result<Response<ListArticlesResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received list articles request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 3: The user must have access to the requested scope
+        check_knowledge_base_permission(
+            conn,
+            user_id_from_token as i32,
+            request.company_id,
+            request.project_id,
+        )
+        .await?;
+
+        // Step 4: Fetch the articles and keep only the readable ones.
+        // The reader role depends on the article's project, so it is resolved once per project.
+        let articles =
+            KnowledgeBaseQuery::get_articles(conn, request.company_id, request.project_id).await?;
+
+        let mut readers = HashMap::new();
+        let mut visible_articles = Vec::new();
+
+        for article in articles {
+            if let Entry::Vacant(entry) = readers.entry(article.project_id) {
+                entry.insert(
+                    get_knowledge_base_reader(
+                        conn,
+                        user_id_from_token as i32,
+                        article.company_id,
+                        article.project_id,
+                    )
+                    .await?,
+                );
+            }
+
+            if let Some(Some(reader)) = readers.get(&article.project_id) {
+                if can_read_article(&self.roles, &article, reader) {
+                    visible_articles.push(article_response(article));
+                }
+            }
+        }
+
+        // Step 5: Construct and return the response
+        let response = Response::new(ListArticlesResponse {
+            articles: visible_articles,
+        });
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Retrieved articles successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+}
+
+/// Converts an article into the gRPC response format.
+fn article_response(article: knowledge_base::Model) -> ArticleResponse {
+    ArticleResponse {
+        article_id: article.id,
+        company_id: article.company_id,
+        project_id: article.project_id,
+        title: article.title,
+        content: article.content.unwrap_or_default(),
+        access_level: access_level_to_proto(article.access_level),
+        min_role: article.role_id.map(|role_id| role_id - 1), // Adjust to match the gRPC enum by subtracting 1
+    }
+}
+
+/// Converts an access level stored in the database into the gRPC `ArticleAccessLevel` enum value.
+fn access_level_to_proto(access_level: AccessLevelType) -> i32 {
+    match access_level {
+        AccessLevelType::Full => ArticleAccessLevel::Full as i32,
+        AccessLevelType::Limited => ArticleAccessLevel::Limited as i32,
+        AccessLevelType::Restricted => ArticleAccessLevel::Restricted as i32,
+    }
+}
+
+/// Converts a gRPC `ArticleAccessLevel` enum value into the access level stored in the database.
+fn access_level_from_proto(access_level: i32) -> Result<AccessLevelType, CoreErrors> {
+    match ArticleAccessLevel::try_from(access_level) {
+        Ok(ArticleAccessLevel::Full) => Ok(AccessLevelType::Full),
+        Ok(ArticleAccessLevel::Limited) => Ok(AccessLevelType::Limited),
+        Ok(ArticleAccessLevel::Restricted) => Ok(AccessLevelType::Restricted),
+        Err(_) => Err(CoreErrors::DataValidationError(
+            "validator_invalid_access_level".to_string(),
+        )),
+    }
+}
+
+/// Converts a gRPC `ProjectRole` enum value into the role ID stored in the database.
+fn role_id_from_proto(role: i32) -> Result<i32, CoreErrors> {
+    match ProjectRole::try_from(role) {
+        Ok(role) => Ok(role as i32 + 1), // Adjust the gRPC enum to the role ID by adding 1
+        Err(_) => Err(CoreErrors::DataValidationError(
+            "validator_invalid_role".to_string(),
+        )),
+    }
+}
@@ -1,5 +1,9 @@
+pub mod access_token_service;
 pub mod company_service;
+pub mod knowledge_base_service;
 pub mod notes_service;
 pub mod project_service;
+pub mod subtask_service;
 pub mod task_service;
+pub mod trash_service;
 pub mod user_service;
@@ -1,11 +1,15 @@
-use core_database::queries::notes_query::NotesQuery;
+use core_database::{
+    entity::notes,
+    queries::notes_query::{NotesFilter, NotesQuery},
+};
 use core_debugger::tracing::{event, Level};
 use tonic::{Request, Response, Status};
 
 use crate::{
     helai_api_core_service::{
         notes_service_server::NotesService, CreateNoteRequest, CreateNoteResponse,
-        DeleteNoteRequest, StatusResponse,
+        DeleteNoteRequest, GetNotesRequest, GetNotesResponse, NoteResponse, StatusResponse,
+        UpdateNoteRequest,
     },
     middleware::{
         access_check::{
@@ -13,10 +17,11 @@ use crate::{
             check_tasks_permission,
         },
         interceptors,
+        policy::{authorize, Action, Principal, Resource},
         validators::{
-            empty_validation, hex_color_validator, max_symbols_validator_20,
-            max_symbols_validator_250, min_symbols_validator_3, no_special_symbols_validator,
-            CompositValidator,
+            date_format_validation, empty_validation, hex_color_validator,
+            max_symbols_validator_20, max_symbols_validator_250, min_symbols_validator_3,
+            no_special_symbols_validator, CompositValidator,
         },
     },
     my_server::MyServer,
@@ -49,8 +54,8 @@ impl NotesService for MyServer {
             request
         );
 
-        // Step 1: Authenticate the user by extracting their ID from the auth token in request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Step 2: Extract the inner request payload
         let request: CreateNoteRequest = request.into_inner();
@@ -108,11 +113,14 @@ impl NotesService for MyServer {
             None // No associated entity; personal note
         };
 
-        // Step 6: Permission check - allow access if the user's role is sufficiently privileged (role_id <= 2)
-        if let Some(level) = access_lvl {
-            if level > 2 {
-                return Err(Status::permission_denied("permission_denied"));
-            }
+        // Step 6: Permission check - allow access if the user's role is sufficiently privileged
+        if let Some(role_id) = access_lvl {
+            authorize(
+                &self.roles,
+                &Principal::new(user_id_from_token as i32, role_id),
+                Action::NoteCreate,
+                &Resource::default(),
+            )?;
         }
 
         // Step 7: Create the note in the database
@@ -170,8 +178,8 @@ impl NotesService for MyServer {
             request
         );
 
-        // Step 2: Authenticate the user using the auth token from the request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 2: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Step 3: Extract the inner payload from the gRPC request
         let request = request.into_inner();
@@ -198,4 +206,195 @@ impl NotesService for MyServer {
 
         Ok(response)
     }
+
+    /// Lists the notes visible to the authenticated user.
+    ///
+    /// Visibility follows the same rules as `check_note_permission`: the user's own notes and
+    /// notes in scopes where the user is at least an "Administrator". Results are ordered from
+    /// newest to oldest and paginated.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the filters and the requested page.
+    ///
+    /// # Returns
+    /// * `Result<Response<GetNotesResponse>, Status>` - Returns a page of notes and whether more pages exist,
+    ///   or a gRPC `Status` error if a filter fails validation.
+    async fn get_notes(
+        &self,
+        request: Request<GetNotesRequest>,
+    ) -> Result<Response<GetNotesResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received get notes request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Validate the filters and the pagination parameters
+        let filter = NotesFilter {
+            company_id: request.company_id,
+            project_id: request.project_id,
+            task_id: request.task_id,
+            subtask_id: request.subtask_id,
+            tag: request.tag,
+            author_id: request.author_id,
+            created_from: request
+                .created_from
+                .map(date_format_validation)
+                .transpose()?,
+            created_to: request.created_to.map(date_format_validation).transpose()?,
+        };
+
+        let page_size = match request.page_size {
+            0 => DEFAULT_NOTES_PAGE_SIZE,
+            page_size => page_size.min(MAX_NOTES_PAGE_SIZE),
+        } as u64;
+        let offset = request.page as u64 * page_size;
+
+        // Step 3: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 4: Fetch one extra note to find out whether another page exists
+        let mut notes = NotesQuery::get_notes(
+            conn,
+            user_id_from_token as i32,
+            filter,
+            page_size + 1,
+            offset,
+        )
+        .await?;
+
+        let has_more = notes.len() as u64 > page_size;
+        notes.truncate(page_size as usize);
+
+        // Step 5: Construct and return the response
+        let response = Response::new(GetNotesResponse {
+            notes: notes.into_iter().map(note_response).collect(),
+            has_more,
+        });
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Retrieved notes successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Updates the content, tags or decoration color of a note.
+    ///
+    /// Only the fields present in the request are changed. The same users that may delete a note may edit it.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the note ID and the fields to change.
+    ///
+    /// # Returns
+    /// * `Result<Response<NoteResponse>, Status>` - Returns the updated note,
+    ///   or a gRPC `Status` error if validation or permission checks fail.
+    ///
+    /// # Errors
+    /// * Returns `Status::permission_denied` if the user lacks sufficient permissions.
+    async fn update_note(
+        &self,
+        request: Request<UpdateNoteRequest>,
+    ) -> Result<Response<NoteResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received update note request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Validate only the fields that are being changed
+        let composite_validator_tags = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_20,
+            no_special_symbols_validator,
+        ]);
+
+        let composite_validator_content = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_250,
+        ]);
+
+        let composite_validator_decoration_color =
+            CompositValidator::new(vec![empty_validation, hex_color_validator]);
+
+        let validated_content = request
+            .content
+            .map(|content| composite_validator_content.validate(content))
+            .transpose()?;
+        let validated_tags = request
+            .tags
+            .map(|tags| composite_validator_tags.validate(tags))
+            .transpose()?;
+        let validated_decoration_color = request
+            .decoration_color
+            .map(|color| composite_validator_decoration_color.validate(color))
+            .transpose()?;
+
+        // Step 3: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 4: Verify that the user has permission to edit the note
+        let note = check_note_permission(conn, user_id_from_token as i32, request.note_id).await?;
+
+        // Step 5: Apply the changes
+        let note = NotesQuery::update_note(
+            conn,
+            note,
+            validated_content,
+            validated_tags,
+            validated_decoration_color,
+        )
+        .await?;
+
+        // Step 6: Construct and return the response
+        let response = Response::new(note_response(note));
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Note updated successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+}
+
+/// Number of notes returned per page when the request does not specify a page size.
+const DEFAULT_NOTES_PAGE_SIZE: u32 = 20;
+
+/// Upper bound for the page size of `get_notes`.
+const MAX_NOTES_PAGE_SIZE: u32 = 100;
+
+/// Converts a note into the gRPC response format.
+fn note_response(note: notes::Model) -> NoteResponse {
+    NoteResponse {
+        note_id: note.id,
+        user_id: note.user_id,
+        company_id: note.company_id,
+        project_id: note.project_id,
+        task_id: note.task_id,
+        subtask_id: note.subtask_id,
+        content: note.content,
+        tags: note.tags.unwrap_or_default(),
+        decoration_color: note.decoration_color.unwrap_or_default(),
+        created_at: note.created_at.to_string(),
+    }
 }
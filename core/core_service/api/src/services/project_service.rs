@@ -1,16 +1,20 @@
-use core_database::queries::projects_query::ProjectQuery;
+use core_database::{entity::projects, queries::projects_query::ProjectQuery};
 use core_debugger::tracing::{event, Level};
+use sea_orm::DbConn;
 use tonic::{Request, Response, Status};
 
 use crate::{
     helai_api_core_service::{
         projects_service_server::ProjectsService, CreateProjectRequest, CreateProjectResponse,
         DeleteProjectRequest, GetAllCompanyProjectsRequest, GetAllCompanyProjectsRespnonse,
-        ProjectUserInfoResponse, ProjectsResponse, StatusResponse, UserProjectModificationRequest,
+        ListProjectMembersRequest, ListProjectMembersResponse, ProjectArchiveRequest,
+        ProjectMemberResponse, ProjectUserInfoResponse, ProjectsResponse, StatusResponse,
+        UpdateProjectRequest, UserProjectModificationRequest,
     },
     middleware::{
         access_check::{check_company_permission, check_project_permission},
         interceptors,
+        policy::{authorize, Action, Principal, Resource, RoleHierarchy},
         validators::{
             empty_validation, hex_color_validator, max_symbols_validator_20,
             max_symbols_validator_250, min_symbols_validator_3, no_special_symbols_validator,
@@ -42,8 +46,8 @@ impl ProjectsService for MyServer {
     ) -> Result<Response<CreateProjectResponse>, Status> {
         event!(target: "hellai_app_core_events", Level::DEBUG, "Received create project request: {:?}", request);
 
-        // Step 1: Authenticate the user by extracting their ID from the auth token in request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the request to access the inner data
         let request = request.into_inner();
@@ -79,36 +83,37 @@ impl ProjectsService for MyServer {
         let user_company_access =
             check_company_permission(conn, user_id_from_token as i32, request.company_id).await?;
 
-        // Permission level check - allow access if the user's role is sufficiently privileged (role_id <= 3)
-        if user_company_access.role_id <= 3 {
-            // Step 5: Create a new project in the database
-            let new_project = ProjectQuery::create_project(
-                conn,
-                request.company_id,
-                validated_project_title,
-                validated_project_description,
-                validated_project_decoration_color,
-                user_id_from_token as i32,
-                user_company_access.role_id,
-            )
-            .await?;
+        // Permission check - only sufficiently privileged company members may create projects
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+            Action::ProjectCreate,
+            &Resource::default(),
+        )?;
 
-            // Step 6: Construct a success response with the new project details
-            let response = Response::new(CreateProjectResponse {
-                project_id: new_project.id,
-                company_id: new_project.company_id,
-                title: new_project.title,
-                description: new_project.description.unwrap_or_default(),
-                decoration_color: new_project.decoration_color.unwrap_or_default(),
-            });
-
-            event!(target: "hellai_app_core_events", Level::DEBUG, "Project created successfully. Response: {:?}", response);
-            Ok(response)
-        } else {
-            // Log and return a permission denied error if the user lacks sufficient privileges
-            event!(target: "hellai_app_core_events", Level::DEBUG, "Permission denied: User lacks sufficient privileges to create project");
-            Err(Status::permission_denied("permission_denied"))
-        }
+        // Step 5: Create a new project in the database
+        let new_project = ProjectQuery::create_project(
+            conn,
+            request.company_id,
+            validated_project_title,
+            validated_project_description,
+            validated_project_decoration_color,
+            user_id_from_token as i32,
+            user_company_access.role_id,
+        )
+        .await?;
+
+        // Step 6: Construct a success response with the new project details
+        let response = Response::new(CreateProjectResponse {
+            project_id: new_project.id,
+            company_id: new_project.company_id,
+            title: new_project.title,
+            description: new_project.description.unwrap_or_default(),
+            decoration_color: new_project.decoration_color.unwrap_or_default(),
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Project created successfully. Response: {:?}", response);
+        Ok(response)
     }
 
     /// Adds a user to a project if the authenticated user has sufficient permissions.
@@ -131,8 +136,8 @@ impl ProjectsService for MyServer {
     ) -> Result<Response<ProjectUserInfoResponse>, Status> {
         event!(target: "hellai_app_core_events", Level::DEBUG, "Received add user to project request: {:?}", request);
 
-        // Step 1: Authenticate the user by extracting their ID from the auth token in request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the request to access the inner data
         let request = request.into_inner();
@@ -144,26 +149,26 @@ impl ProjectsService for MyServer {
         let user_company_access =
             check_project_permission(conn, user_id_from_token as i32, request.project_id).await?;
 
-        // Permission level check - allow access if the user's role is sufficiently privileged (role_id <= 2)
-        if user_company_access.user_role.id <= 2 {
-            // Step 4: Add the specified user to the project
-            let user_access =
-                ProjectQuery::add_user_to_project(conn, request.user_id, request.project_id)
-                    .await?;
-
-            // Step 5: Construct a success response with the added user's information
-            let response = Response::new(ProjectUserInfoResponse {
-                user_id: user_access.user_id,
-                user_role: user_access.role_id.unwrap_or(0) - 1, // Adjust to match the gRPC enum by subtracting 1
-            });
+        // Permission check - only sufficiently privileged project members may add users
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.user_role.id),
+            Action::ProjectMemberAdd,
+            &Resource::default(),
+        )?;
+
+        // Step 4: Add the specified user to the project
+        let user_access =
+            ProjectQuery::add_user_to_project(conn, request.user_id, request.project_id).await?;
+
+        // Step 5: Construct a success response with the added user's information
+        let response = Response::new(ProjectUserInfoResponse {
+            user_id: user_access.user_id,
+            user_role: user_access.role_id.unwrap_or(0) - 1, // Adjust to match the gRPC enum by subtracting 1
+        });
 
-            event!(target: "hellai_app_core_events", Level::DEBUG, "User added to project successfully. Response: {:?}", response);
-            Ok(response)
-        } else {
-            // Log and return a permission denied error if the user's role is not sufficiently privileged
-            event!(target: "hellai_app_core_events", Level::DEBUG, "Permission denied: User lacks sufficient role level to add users to project");
-            Err(Status::permission_denied("permission_denied"))
-        }
+        event!(target: "hellai_app_core_events", Level::DEBUG, "User added to project successfully. Response: {:?}", response);
+        Ok(response)
     }
 
     /// Removes a user from a project if the authenticated user has sufficient permissions.
@@ -186,8 +191,8 @@ impl ProjectsService for MyServer {
     ) -> Result<Response<StatusResponse>, Status> {
         event!(target: "hellai_app_core_events", Level::DEBUG, "Received remove user from project request: {:?}", request);
 
-        // Step 1: Authenticate the user by extracting their ID from the auth token in request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the request to access the inner data
         let request = request.into_inner();
@@ -206,21 +211,22 @@ impl ProjectsService for MyServer {
                 check_project_permission(conn, user_id_from_token as i32, request.project_id)
                     .await?;
 
-            // Permission level check - allow access if the user's role is sufficiently privileged (role_id <= 2)
-            if user_company_access.user_role.id <= 2 {
-                // Authorized to remove the specified user; proceed with the removal
-                ProjectQuery::remove_user_from_project(
-                    conn,
-                    request.user_id,
-                    request.project_id,
-                    Some(user_company_access.user_role.id),
-                )
-                .await?;
-            } else {
-                // Log and return a permission denied error if the user lacks authorization
-                event!(target: "hellai_app_core_events", Level::DEBUG, "Permission denied: Insufficient role level for removing user");
-                return Err(Status::permission_denied("permission_denied"));
-            }
+            // Permission check - only sufficiently privileged project members may remove others
+            authorize(
+                &self.roles,
+                &Principal::new(user_id_from_token as i32, user_company_access.user_role.id),
+                Action::ProjectMemberRemove,
+                &Resource::default(),
+            )?;
+
+            // Authorized to remove the specified user; proceed with the removal
+            ProjectQuery::remove_user_from_project(
+                conn,
+                request.user_id,
+                request.project_id,
+                Some(user_company_access.user_role.id),
+            )
+            .await?;
         }
 
         // Step 5: Construct a success response indicating the user was removed
@@ -230,11 +236,11 @@ impl ProjectsService for MyServer {
         Ok(response)
     }
 
-    /// Deletes a specified project and all associated user roles if the authenticated user has the "Owner" role.
+    /// Moves a specified project to the trash if the authenticated user has the "Owner" role.
     ///
     /// This function verifies if the authenticated user has the "Owner" role for the project.
-    /// If authorized, it deletes the project and all user associations with the project.
-    /// If unauthorized, it returns a permission denied error.
+    /// If authorized, it moves the project with its tasks and notes to the trash; user associations
+    /// are kept so the project can be restored. If unauthorized, it returns a permission denied error.
     ///
     /// # Arguments
     ///
@@ -242,7 +248,7 @@ impl ProjectsService for MyServer {
     ///
     /// # Returns
     ///
-    /// * `Result<Response<StatusResponse>, Status>` - A success response if the project and associated users are deleted,
+    /// * `Result<Response<StatusResponse>, Status>` - A success response if the project is moved to the trash,
     ///   or a permission denied error if the user lacks sufficient privileges.
     async fn delete_project(
         &self,
@@ -250,8 +256,8 @@ impl ProjectsService for MyServer {
     ) -> Result<Response<StatusResponse>, Status> {
         event!(target: "hellai_app_core_events", Level::DEBUG, "Received delete project request: {:?}", request);
 
-        // Step 1: Authenticate the user by extracting their ID from the auth token in request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Step 2: Unwrap the request to access its inner data
         let request = request.into_inner();
@@ -263,24 +269,22 @@ impl ProjectsService for MyServer {
         let user_company_access =
             check_project_permission(conn, user_id_from_token as i32, request.project_id).await?;
 
-        // Permission level check - allow deletion only if the user's role is "Owner" (role_id == 1)
-        if user_company_access.user_role.id == 1 {
-            // Step 5: Delete the specified project
-            ProjectQuery::delete_project(conn, request.project_id).await?;
+        // Permission check - allow deletion only if the user's role is "Owner"
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.user_role.id),
+            Action::ProjectDelete,
+            &Resource::default(),
+        )?;
 
-            // Step 6: Delete all user associations with the specified project
-            ProjectQuery::delete_all_users_from_project(conn, request.project_id).await?;
+        // Step 5: Move the specified project to the trash
+        ProjectQuery::delete_project(conn.as_ref(), request.project_id).await?;
 
-            // Step 7: Construct and return a success response
-            let response = Response::new(StatusResponse { success: true });
+        // Step 6: Construct and return a success response
+        let response = Response::new(StatusResponse { success: true });
 
-            event!(target: "hellai_app_core_events", Level::DEBUG, "Project and associated users deleted successfully. Response: {:?}", response);
-            Ok(response)
-        } else {
-            // Log and return a permission denied error if the user lacks the required "Owner" role
-            event!(target: "hellai_app_core_events", Level::DEBUG, "Permission denied: User lacks 'Owner' role to delete project");
-            Err(Status::permission_denied("permission_denied"))
-        }
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Project moved to the trash successfully. Response: {:?}", response);
+        Ok(response)
     }
 
     /// Retrieves all company projects filtered by user access.
@@ -309,8 +313,8 @@ impl ProjectsService for MyServer {
             request
         );
 
-        // Step 2: Authenticate the user by extracting their ID from the auth token in the request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 2: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Step 3: Extract the inner payload from the gRPC request
         let request = request.into_inner();
@@ -323,20 +327,13 @@ impl ProjectsService for MyServer {
             conn,
             request.company_id,
             user_id_from_token as i32,
+            request.include_archived,
         )
         .await?;
 
         // Step 6: Transform the database results into the gRPC response format
-        let projects_response: Vec<ProjectsResponse> = projects_db
-            .into_iter()
-            .map(|project| ProjectsResponse {
-                id: project.id,
-                company_id: project.company_id,
-                title: project.title,
-                description: project.description,
-                decoration_color: project.decoration_color,
-            })
-            .collect();
+        let projects_response: Vec<ProjectsResponse> =
+            projects_db.into_iter().map(project_response).collect();
 
         // Step 7: Construct the response object
         let response = Response::new(GetAllCompanyProjectsRespnonse {
@@ -353,4 +350,284 @@ impl ProjectsService for MyServer {
 
         Ok(response)
     }
+    /// Updates the title, description and decoration color of a project.
+    ///
+    /// Omitted fields are left unchanged; provided fields are validated like on creation.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `UpdateProjectRequest`, which includes the project ID
+    ///   and the fields to update.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<ProjectsResponse>, Status>` - A response with the updated project,
+    ///   or a permission denied error if the user lacks authorization.
+    async fn update_project(
+        &self,
+        request: Request<UpdateProjectRequest>,
+    ) -> Result<Response<ProjectsResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received update project request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Step 2: Validate the provided project details using composite validators
+        let composite_validator_title = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_20,
+            no_special_symbols_validator,
+        ]);
+
+        let composite_validator_description = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_250,
+        ]);
+
+        let composite_validator_hex_color =
+            CompositValidator::new(vec![empty_validation, hex_color_validator]);
+
+        let validated_project_title = request
+            .title
+            .map(|title| composite_validator_title.validate(title))
+            .transpose()?;
+        let validated_project_description = request
+            .description
+            .map(|description| composite_validator_description.validate(description))
+            .transpose()?;
+        let validated_project_decoration_color = request
+            .decoration_color
+            .map(|color| composite_validator_hex_color.validate(color))
+            .transpose()?;
+
+        // Step 3: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 4: Check if the authenticated user has sufficient permissions for the specified project
+        let user_project_access =
+            check_project_permission(conn, user_id_from_token as i32, request.project_id).await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_project_access.user_role.id),
+            Action::ProjectUpdate,
+            &Resource::default(),
+        )?;
+
+        // Step 5: Update the project
+        let project = ProjectQuery::update_project(
+            conn,
+            request.project_id,
+            validated_project_title,
+            validated_project_description,
+            validated_project_decoration_color,
+        )
+        .await?
+        .ok_or_else(|| Status::not_found("project_not_found"))?;
+
+        // Step 6: Construct a success response with the updated project
+        let response = Response::new(project_response(project));
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Project updated successfully. Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Archives a project, hiding it from project listings by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `ProjectArchiveRequest`, which includes the project ID.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<ProjectsResponse>, Status>` - A response with the archived project,
+    ///   or a permission denied error if the user lacks authorization.
+    async fn archive_project(
+        &self,
+        request: Request<ProjectArchiveRequest>,
+    ) -> Result<Response<ProjectsResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received archive project request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Step 2: Archive the project if the user is allowed to
+        let project = set_project_archived(
+            &self.connection,
+            &self.roles,
+            user_id_from_token as i32,
+            request.project_id,
+            true,
+        )
+        .await?;
+
+        // Step 3: Construct a success response with the archived project
+        let response = Response::new(project_response(project));
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Project archived successfully. Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Restores an archived project.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `ProjectArchiveRequest`, which includes the project ID.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<ProjectsResponse>, Status>` - A response with the restored project,
+    ///   or a permission denied error if the user lacks authorization.
+    async fn unarchive_project(
+        &self,
+        request: Request<ProjectArchiveRequest>,
+    ) -> Result<Response<ProjectsResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received unarchive project request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Step 2: Restore the project if the user is allowed to
+        let project = set_project_archived(
+            &self.connection,
+            &self.roles,
+            user_id_from_token as i32,
+            request.project_id,
+            false,
+        )
+        .await?;
+
+        // Step 3: Construct a success response with the restored project
+        let response = Response::new(project_response(project));
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Project unarchived successfully. Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Lists the users with access to a project, most privileged roles first.
+    ///
+    /// Company members whose role grants access to all projects are included and marked as inherited.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - A gRPC request containing `ListProjectMembersRequest`, which includes the project ID
+    ///   and the requested page.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Response<ListProjectMembersResponse>, Status>` - A response with the members of the page,
+    ///   or a permission denied error if the user has no access to the project.
+    async fn list_project_members(
+        &self,
+        request: Request<ListProjectMembersRequest>,
+    ) -> Result<Response<ListProjectMembersResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received list project members request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Only users with access to the project may see its members
+        check_project_permission(conn, user_id_from_token as i32, request.project_id).await?;
+
+        let page_size = match request.page_size {
+            0 => DEFAULT_MEMBERS_PAGE_SIZE,
+            page_size => page_size.min(MAX_MEMBERS_PAGE_SIZE),
+        } as u64;
+        let offset = request.page as u64 * page_size;
+
+        // Step 3: Fetch one extra member to find out whether another page exists
+        let mut members =
+            ProjectQuery::get_project_members(conn, request.project_id, page_size + 1, offset)
+                .await?;
+
+        let has_more = members.len() as u64 > page_size;
+        members.truncate(page_size as usize);
+
+        // Step 4: Transform the database results into the gRPC response format
+        let response = Response::new(ListProjectMembersResponse {
+            members: members
+                .into_iter()
+                .map(|member| ProjectMemberResponse {
+                    user_id: member.user_id,
+                    login: member.login,
+                    user_name: member.user_name,
+                    email: member.email,
+                    is_active: member.is_active,
+                    user_role: member.role_id - 1, // Adjust to match the gRPC enum by subtracting 1
+                    inherited: member.inherited,
+                })
+                .collect(),
+            has_more,
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+}
+
+/// Number of members returned per page when the request does not specify a page size.
+const DEFAULT_MEMBERS_PAGE_SIZE: u32 = 20;
+
+/// Largest page of members a request may ask for.
+const MAX_MEMBERS_PAGE_SIZE: u32 = 100;
+
+/// Archives or restores a project if the user may do so.
+///
+/// # Errors
+/// * Returns `Status::permission_denied` if the user has no access to the project or their role is too low.
+/// * Returns `Status::not_found` if the project does not exist.
+async fn set_project_archived(
+    conn: &DbConn,
+    roles: &RoleHierarchy,
+    user_id: i32,
+    project_id: i32,
+    archived: bool,
+) -> Result<projects::Model, Status> {
+    let user_project_access = check_project_permission(conn, user_id, project_id).await?;
+
+    authorize(
+        roles,
+        &Principal::new(user_id, user_project_access.user_role.id),
+        Action::ProjectArchive,
+        &Resource::default(),
+    )?;
+
+    let project = ProjectQuery::set_project_archived(conn, project_id, archived)
+        .await?
+        .ok_or_else(|| Status::not_found("project_not_found"))?;
+
+    event!(target: "hellai_app_core_events", Level::DEBUG, "Project {} archived: {} by user {}", project_id, archived, user_id);
+
+    Ok(project)
+}
+
+/// Converts a project into its gRPC representation.
+fn project_response(project: projects::Model) -> ProjectsResponse {
+    ProjectsResponse {
+        id: project.id,
+        company_id: project.company_id,
+        title: project.title,
+        description: project.description,
+        decoration_color: project.decoration_color,
+        archived_at: project
+            .archived_at
+            .map(|archived_at| archived_at.to_string()),
+    }
 }
@@ -0,0 +1,525 @@
+use core_database::queries::{
+    subtasks_query::{SubtaskModelFromQueryResult, SubtasksQuery},
+    tasks_query::TasksQuery,
+};
+use core_debugger::tracing::{event, Level};
+use sea_orm::DbConn;
+use tonic::{Request, Response, Status};
+
+use crate::{
+    helai_api_core_service::{
+        subtasks_service_server::SubtasksService, AssignSubtaskRequest, AssignedUserInfo,
+        ChangeSubtaskStatusRequest, CreateSubtaskRequest, DeleteSubtaskRequest,
+        GetAllTaskSubtasksRequest, GetAllTaskSubtasksResponse, StatusResponse, SubtaskResponse,
+        UnassignSubtaskRequest, UpdateSubtaskRequest,
+    },
+    middleware::{
+        access_check::{check_subtask_permission, check_tasks_permission},
+        interceptors,
+        policy::{authorize, Action, Principal, Resource},
+        validators::{
+            date_format_validation, empty_validation, max_symbols_validator_20,
+            max_symbols_validator_250, min_symbols_validator_3, no_special_symbols_validator,
+            CompositValidator,
+        },
+    },
+    my_server::MyServer,
+    services::task_service::{task_status_from_proto, task_status_to_proto},
+};
+
+// Implementing the SubtasksService trait for MyServer
+#[tonic::async_trait]
+impl SubtasksService for MyServer {
+    /// Creates a new subtask under a task after validating inputs and checking permissions.
+    ///
+    /// # Arguments
+    /// * `request` - A `Request` object containing the parent task ID and the subtask details.
+    ///
+    /// # Returns
+    /// * `Result<Response<SubtaskResponse>, Status>` - A gRPC response containing the created subtask,
+    ///   or a gRPC `Status` error if validation, permission checks, or database interactions fail.
+    ///
+    /// # Errors
+    /// * Returns `Status::permission_denied` if the user's role on the parent task is lower than "Manager".
+    async fn create_subtask(
+        &self,
+        request: Request<CreateSubtaskRequest>,
+    ) -> Result<Response<SubtaskResponse>, Status> {
+        // Log the incoming request at the DEBUG level
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received create subtask request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Extract the inner request payload
+        let request = request.into_inner();
+
+        // Step 2: Validate the subtask details
+        let composite_validator_title = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_20,
+            no_special_symbols_validator,
+        ]);
+
+        let composite_validator_description = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_250,
+        ]);
+
+        let validated_title = composite_validator_title.validate(request.title)?;
+        let validated_description =
+            composite_validator_description.validate(request.description)?;
+        let validated_due_date = request.due_date.map(date_format_validation).transpose()?;
+
+        // Step 3: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 4: Subtasks inherit permissions from their parent task
+        let user_task_access =
+            check_tasks_permission(conn, user_id_from_token as i32, request.task_id).await?;
+
+        // Verify if the user has sufficient privileges to create subtasks
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_task_access.1),
+            Action::SubtaskCreate,
+            &Resource::default(),
+        )?;
+
+        // Step 5: Create the subtask in the database
+        let new_subtask = SubtasksQuery::create_subtask(
+            conn,
+            request.task_id,
+            validated_title,
+            validated_description,
+            validated_due_date,
+        )
+        .await?;
+
+        // Step 6: Construct and return the response
+        let response = Response::new(load_subtask_response(conn, new_subtask.id).await?);
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Subtask created successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Updates the title, description or due date of a subtask.
+    ///
+    /// Only the fields present in the request are changed.
+    ///
+    /// # Arguments
+    /// * `request` - A `Request` object containing the subtask ID and the fields to change.
+    ///
+    /// # Returns
+    /// * `Result<Response<SubtaskResponse>, Status>` - A gRPC response containing the updated subtask,
+    ///   or a gRPC `Status` error if validation or permission checks fail.
+    ///
+    /// # Errors
+    /// * Returns `Status::permission_denied` if the user's role is lower than "Manager".
+    async fn update_subtask(
+        &self,
+        request: Request<UpdateSubtaskRequest>,
+    ) -> Result<Response<SubtaskResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received update subtask request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Validate only the fields that are being changed
+        let composite_validator_title = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_20,
+            no_special_symbols_validator,
+        ]);
+
+        let composite_validator_description = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_250,
+        ]);
+
+        let validated_title = request
+            .title
+            .map(|title| composite_validator_title.validate(title))
+            .transpose()?;
+        let validated_description = request
+            .description
+            .map(|description| composite_validator_description.validate(description))
+            .transpose()?;
+        let validated_due_date = request.due_date.map(date_format_validation).transpose()?;
+
+        // Step 3: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 4: Check the user's permissions for the subtask
+        let (subtask, role_id) =
+            check_subtask_permission(conn, user_id_from_token as i32, request.subtask_id).await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, role_id),
+            Action::SubtaskUpdate,
+            &Resource::default(),
+        )?;
+
+        // Step 5: Apply the changes
+        SubtasksQuery::update_subtask(
+            conn,
+            subtask,
+            validated_title,
+            validated_description,
+            validated_due_date,
+        )
+        .await?;
+
+        // Step 6: Construct and return the response
+        let response = Response::new(load_subtask_response(conn, request.subtask_id).await?);
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Subtask updated successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Deletes a subtask and all associated user-subtask relationships from the database.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the subtask ID to be deleted.
+    ///
+    /// # Returns
+    /// * `Result<Response<StatusResponse>, Status>` - Returns a gRPC response indicating success,
+    ///   or a gRPC `Status` error if the user lacks sufficient permissions or if any database operation fails.
+    ///
+    /// # Errors
+    /// * Returns `Status::permission_denied` if the user's role is lower than "Administrator".
+    async fn delete_subtask(
+        &self,
+        request: Request<DeleteSubtaskRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received delete subtask request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 3: Verify the authenticated user's permissions for the subtask
+        let (_, role_id) =
+            check_subtask_permission(conn, user_id_from_token as i32, request.subtask_id).await?;
+
+        // Allow deletion only if the user's role is sufficiently privileged
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, role_id),
+            Action::SubtaskDelete,
+            &Resource::default(),
+        )?;
+
+        // Step 4: Delete the subtask, its user associations are removed by the foreign key cascade
+        SubtasksQuery::delete_subtask(conn.as_ref(), request.subtask_id).await?;
+
+        // Step 5: Construct a success response
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Subtask deleted successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Retrieves all subtasks of a task the authenticated user has access to.
+    ///
+    /// # Arguments
+    /// * `request` - The incoming gRPC request containing the task ID.
+    ///
+    /// # Returns
+    /// * `Result<Response<GetAllTaskSubtasksResponse>, Status>` - A response containing the list of subtasks,
+    ///   or a gRPC `Status` error if the user has no access to the task.
+    async fn get_all_task_subtasks(
+        &self,
+        request: Request<GetAllTaskSubtasksRequest>,
+    ) -> Result<Response<GetAllTaskSubtasksResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received get all task subtasks request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 3: Any access to the parent task allows viewing its subtasks
+        check_tasks_permission(conn, user_id_from_token as i32, request.task_id).await?;
+
+        // Step 4: Fetch and transform the subtasks
+        let subtasks = SubtasksQuery::get_all_task_subtasks(conn, request.task_id)
+            .await?
+            .into_iter()
+            .map(subtask_response)
+            .collect();
+
+        // Step 5: Construct and return the response
+        let response = Response::new(GetAllTaskSubtasksResponse { subtasks });
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Retrieved task subtasks successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Assigns a subtask to a user who has access to the parent task.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the subtask ID and the new assignee's user ID.
+    ///
+    /// # Returns
+    /// * `Result<Response<SubtaskResponse>, Status>` - A response containing the updated subtask,
+    ///   or a gRPC `Status` error if permission checks fail.
+    ///
+    /// # Errors
+    /// * Returns `Status::permission_denied` if the user's role is lower than "Manager".
+    /// * Returns `Status::failed_precondition` if the assignee has no access to the parent task.
+    async fn assign_subtask(
+        &self,
+        request: Request<AssignSubtaskRequest>,
+    ) -> Result<Response<SubtaskResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received assign subtask request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 3: Verify the authenticated user's permissions for the subtask
+        let (subtask, role_id) =
+            check_subtask_permission(conn, user_id_from_token as i32, request.subtask_id).await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, role_id),
+            Action::SubtaskAssign,
+            &Resource::default(),
+        )?;
+
+        // Step 4: The assignee must be able to see the parent task
+        if TasksQuery::get_user_task_with_access_lvl(conn, request.user_id, subtask.task_id)
+            .await?
+            .is_none()
+        {
+            return Err(Status::failed_precondition("assignee_has_no_task_access"));
+        }
+
+        // Step 5: Assign the subtask
+        SubtasksQuery::set_subtask_assignee(conn, subtask, Some(request.user_id)).await?;
+
+        // Step 6: Construct and return the response
+        let response = Response::new(load_subtask_response(conn, request.subtask_id).await?);
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Subtask assigned successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Clears the assignee of a subtask.
+    ///
+    /// The current assignee may unassign themselves; anyone else needs at least the "Manager" role.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the subtask ID.
+    ///
+    /// # Returns
+    /// * `Result<Response<SubtaskResponse>, Status>` - A response containing the updated subtask,
+    ///   or a gRPC `Status` error if permission checks fail.
+    async fn unassign_subtask(
+        &self,
+        request: Request<UnassignSubtaskRequest>,
+    ) -> Result<Response<SubtaskResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received unassign subtask request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 3: Verify the authenticated user's permissions for the subtask
+        let (subtask, role_id) =
+            check_subtask_permission(conn, user_id_from_token as i32, request.subtask_id).await?;
+
+        // The current assignee may always unassign themselves
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, role_id),
+            Action::SubtaskUnassign,
+            &Resource::owned_by(subtask.assigned_to),
+        )?;
+
+        // Step 4: Clear the assignee
+        SubtasksQuery::set_subtask_assignee(conn, subtask, None).await?;
+
+        // Step 5: Construct and return the response
+        let response = Response::new(load_subtask_response(conn, request.subtask_id).await?);
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Subtask unassigned successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Changes the status of a subtask.
+    ///
+    /// The assignee of the subtask may change its status; anyone else needs at least the "Manager" role.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the subtask ID and the new status.
+    ///
+    /// # Returns
+    /// * `Result<Response<SubtaskResponse>, Status>` - A response containing the updated subtask,
+    ///   or a gRPC `Status` error if validation or permission checks fail.
+    async fn change_subtask_status(
+        &self,
+        request: Request<ChangeSubtaskStatusRequest>,
+    ) -> Result<Response<SubtaskResponse>, Status> {
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received change subtask status request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        let request = request.into_inner();
+
+        // Step 2: Validate the requested status
+        let status = task_status_from_proto(request.status)?;
+
+        // Step 3: Establish a database connection
+        let conn = &self.connection;
+
+        // Step 4: Verify the authenticated user's permissions for the subtask
+        let (subtask, role_id) =
+            check_subtask_permission(conn, user_id_from_token as i32, request.subtask_id).await?;
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, role_id),
+            Action::SubtaskChangeStatus,
+            &Resource::owned_by(subtask.assigned_to),
+        )?;
+
+        // Step 5: Store the new status
+        SubtasksQuery::change_subtask_status(conn, subtask, status).await?;
+
+        // Step 6: Construct and return the response
+        let response = Response::new(load_subtask_response(conn, request.subtask_id).await?);
+
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Subtask status changed successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+}
+
+/// Loads a subtask with its assignee and converts it into the gRPC response format.
+async fn load_subtask_response(conn: &DbConn, subtask_id: i32) -> Result<SubtaskResponse, Status> {
+    match SubtasksQuery::get_subtask_with_assignee(conn, subtask_id).await? {
+        Some(subtask) => Ok(subtask_response(subtask)),
+        None => Err(Status::not_found("subtask_not_found")),
+    }
+}
+
+/// Converts a subtask query result into the gRPC response format.
+fn subtask_response(subtask: SubtaskModelFromQueryResult) -> SubtaskResponse {
+    SubtaskResponse {
+        subtask_id: subtask.id,
+        task_id: subtask.task_id,
+        assigned_to: subtask.assigned_to_id.map(|id| AssignedUserInfo {
+            id,
+            name: subtask.assigned_to_name.unwrap_or_default(),
+        }),
+        status: task_status_to_proto(subtask.status),
+        title: subtask.title,
+        description: subtask.description.unwrap_or_default(),
+        created_at: subtask.created_at.to_string(),
+        due_date: subtask
+            .due_date
+            .map_or_else(String::new, |date| date.to_string()),
+    }
+}
@@ -1,22 +1,27 @@
 use core_database::{
-    entity::sea_orm_active_enums::TaskStatusType, queries::tasks_query::TasksQuery,
+    entity::sea_orm_active_enums::TaskStatusType,
+    queries::tasks_query::{TaskModelFromQueryResult, TasksQuery},
 };
 use core_debugger::tracing::{event, Level};
+use core_error::core_errors::CoreErrors;
 use tonic::{Request, Response, Status};
 
 use crate::{
     helai_api_core_service::{
         tasks_service_server::TasksService, AssignedUserInfo, CreateTaskRequest,
         CreateTaskResponse, DeleteTaskRequest, GetAllProjectTasksRequest,
-        GetAllProjectTasksResponse, StatusResponse, TaskResponse, TaskUserInfoResponse,
+        GetAllProjectTasksResponse, StatusResponse, TaskResponse,
+        TaskStatusType as ProtoTaskStatusType, TaskUserInfoResponse, UpdateTaskRequest,
         UserTaskModificationRequest,
     },
     middleware::{
         access_check::{check_project_permission, check_tasks_permission},
         interceptors,
+        policy::{authorize, Action, Principal, Resource},
         validators::{
-            empty_validation, max_symbols_validator_20, max_symbols_validator_250,
-            min_symbols_validator_3, no_special_symbols_validator, CompositValidator,
+            date_format_validation, empty_validation, max_symbols_validator_20,
+            max_symbols_validator_250, min_symbols_validator_3, no_special_symbols_validator,
+            task_priority_validator, CompositValidator,
         },
     },
     my_server::MyServer,
@@ -45,8 +50,8 @@ impl TasksService for MyServer {
             request
         );
 
-        // Step 1: Authenticate the user using the auth token from the request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Extract the inner request payload
         let request = request.into_inner();
@@ -77,47 +82,41 @@ impl TasksService for MyServer {
         let user_company_access =
             check_project_permission(conn, user_id_from_token as i32, request.project_id).await?;
 
-        // Verify if the user has sufficient privileges (role_id <= 3 indicates privileged roles)
-        if user_company_access.user_role.id <= 3 {
-            // Step 5: Create a new task in the database
-            let new_task = TasksQuery::create_task(
-                conn,
-                request.project_id,
-                validated_task_title,
-                validated_task_description,
-                user_id_from_token as i32,
-            )
-            .await?;
+        // Verify if the user has sufficient privileges to create tasks in the project
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_company_access.user_role.id),
+            Action::TaskCreate,
+            &Resource::default(),
+        )?;
 
-            // Step 6: Construct and return a success response with the new task details
-            let response = Response::new(CreateTaskResponse {
-                task_id: new_task.id,
-                project_id: new_task.project_id,
-                title: new_task.title,
-                description: new_task.description.unwrap_or_default(),
-            });
-
-            // Log the success event
-            event!(
-                target: "hellai_app_core_events",
-                Level::DEBUG,
-                "Task created successfully. Response: {:?}",
-                response
-            );
+        // Step 5: Create a new task in the database
+        let new_task = TasksQuery::create_task(
+            conn,
+            request.project_id,
+            validated_task_title,
+            validated_task_description,
+            user_id_from_token as i32,
+        )
+        .await?;
+
+        // Step 6: Construct and return a success response with the new task details
+        let response = Response::new(CreateTaskResponse {
+            task_id: new_task.id,
+            project_id: new_task.project_id,
+            title: new_task.title,
+            description: new_task.description.unwrap_or_default(),
+        });
 
-            Ok(response)
-        } else {
-            // Log and return a permission denied error if the user lacks sufficient privileges
-            event!(
-                target: "hellai_app_core_events",
-                Level::DEBUG,
-                "Permission denied: User lacks sufficient privileges to create task"
-            );
+        // Log the success event
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Task created successfully. Response: {:?}",
+            response
+        );
 
-            Err(Status::permission_denied(
-                "Permission denied: insufficient privileges",
-            ))
-        }
+        Ok(response)
     }
 
     /// Adds a user to a task after verifying the permissions of the authenticated user.
@@ -147,8 +146,8 @@ impl TasksService for MyServer {
             request
         );
 
-        // Step 1: Authenticate the user by checking the auth token in the request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the gRPC request to access the inner payload
         let request = request.into_inner();
@@ -160,39 +159,33 @@ impl TasksService for MyServer {
         let user_task_access =
             check_tasks_permission(conn, user_id_from_token as i32, request.task_id).await?;
 
-        // Allow access only if the user's role ID is sufficiently privileged (role_id <= 2)
-        if user_task_access.1 <= 2 {
-            // Step 4: Add the specified user to the task
-            let user_access =
-                TasksQuery::add_user_to_task(conn, request.user_id, request.task_id).await?;
-
-            // Step 5: Construct a success response with the added user's details
-            let response = Response::new(TaskUserInfoResponse {
-                user_id: user_access.user_id,
-                user_role: user_access.role_id.unwrap_or(0) - 1, // Adjust role ID to match the gRPC enum by subtracting 1
-            });
-
-            // Log the success event
-            event!(
-                target: "hellai_app_core_events",
-                Level::DEBUG,
-                "User added to task successfully. Response: {:?}",
-                response
-            );
+        // Allow access only if the user's role is sufficiently privileged
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_task_access.1),
+            Action::TaskMemberAdd,
+            &Resource::default(),
+        )?;
+
+        // Step 4: Add the specified user to the task
+        let user_access =
+            TasksQuery::add_user_to_task(conn, request.user_id, request.task_id).await?;
+
+        // Step 5: Construct a success response with the added user's details
+        let response = Response::new(TaskUserInfoResponse {
+            user_id: user_access.user_id,
+            user_role: user_access.role_id.unwrap_or(0) - 1, // Adjust role ID to match the gRPC enum by subtracting 1
+        });
 
-            Ok(response)
-        } else {
-            // Step 6: Log and return a permission denied error if the user lacks sufficient privileges
-            event!(
-                target: "hellai_app_core_events",
-                Level::DEBUG,
-                "Permission denied: User lacks sufficient privileges to add users to task"
-            );
+        // Log the success event
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "User added to task successfully. Response: {:?}",
+            response
+        );
 
-            Err(Status::permission_denied(
-                "Permission denied: insufficient privileges",
-            ))
-        }
+        Ok(response)
     }
 
     /// Removes a user from a task after validating the permissions of the authenticated user.
@@ -222,8 +215,8 @@ impl TasksService for MyServer {
             request
         );
 
-        // Step 2: Authenticate the user by checking the auth token in the request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 2: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the gRPC request to access the inner payload
         let request = request.into_inner();
@@ -242,26 +235,21 @@ impl TasksService for MyServer {
             let user_task_access =
                 check_tasks_permission(conn, user_id_from_token as i32, request.task_id).await?;
 
-            if user_task_access.1 <= 2 {
-                // User has sufficient privileges; proceed with the removal
-                TasksQuery::remove_user_from_task(
-                    conn,
-                    request.user_id,
-                    request.task_id,
-                    Some(user_task_access.1),
-                )
-                .await?;
-            } else {
-                // Log and return a permission denied error
-                event!(
-                    target: "hellai_app_core_events",
-                    Level::DEBUG,
-                    "Permission denied: Insufficient role level for removing user"
-                );
-                return Err(Status::permission_denied(
-                    "Permission denied: insufficient privileges",
-                ));
-            }
+            authorize(
+                &self.roles,
+                &Principal::new(user_id_from_token as i32, user_task_access.1),
+                Action::TaskMemberRemove,
+                &Resource::default(),
+            )?;
+
+            // User has sufficient privileges; proceed with the removal
+            TasksQuery::remove_user_from_task(
+                conn,
+                request.user_id,
+                request.task_id,
+                Some(user_task_access.1),
+            )
+            .await?;
         }
 
         // Step 5: Construct a success response indicating the user was removed
@@ -278,11 +266,164 @@ impl TasksService for MyServer {
         Ok(response)
     }
 
-    /// Deletes a task and all associated user-task relationships from the database.
+    /// Partially updates a task according to the field mask in the request.
+    ///
+    /// Supported mask paths are `title`, `description`, `status`, `priority` and `due_date`.
+    /// Managers and above may change any field. The user the task is assigned to may only
+    /// change its status. Status changes must follow the allowed transitions
+    /// (see `validate_task_status_transition`).
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the task ID, the new values and the update mask.
+    ///
+    /// # Returns
+    /// * `Result<Response<TaskResponse>, Status>` - Returns the updated task,
+    ///   or a gRPC `Status` error if validation or permission checks fail.
+    ///
+    /// # Errors
+    /// * Returns `Status::invalid_argument` if the mask is empty, contains unknown paths,
+    ///   or a value fails validation.
+    /// * Returns `Status::permission_denied` if the user's role does not allow the requested changes.
+    async fn update_task(
+        &self,
+        request: Request<UpdateTaskRequest>,
+    ) -> Result<Response<TaskResponse>, Status> {
+        // Log the incoming request
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Received update task request: {:?}",
+            request
+        );
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the gRPC request to access the inner payload
+        let request = request.into_inner();
+
+        // Step 2: Validate the fields listed in the update mask
+        let update_mask = request.update_mask.unwrap_or_default();
+
+        if update_mask.paths.is_empty() {
+            return Err(
+                CoreErrors::DataValidationError("validator_empty_update_mask".to_string()).into(),
+            );
+        }
+
+        let composite_validator_title = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_20,
+            no_special_symbols_validator,
+        ]);
+
+        let composite_validator_description = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_250,
+        ]);
+
+        let composite_validator_priority =
+            CompositValidator::new(vec![empty_validation, task_priority_validator]);
+
+        let mut title = None;
+        let mut description = None;
+        let mut status = None;
+        let mut priority = None;
+        let mut due_date = None;
+
+        for path in update_mask.paths {
+            match path.as_str() {
+                "title" => {
+                    title = Some(composite_validator_title.validate(request.title.clone())?);
+                }
+                "description" => {
+                    description = Some(
+                        composite_validator_description.validate(request.description.clone())?,
+                    );
+                }
+                "status" => status = Some(task_status_from_proto(request.status)?),
+                "priority" => {
+                    priority =
+                        Some(composite_validator_priority.validate(request.priority.clone())?);
+                }
+                "due_date" => {
+                    // An empty due date clears the stored value
+                    due_date = Some(if request.due_date.is_empty() {
+                        None
+                    } else {
+                        Some(date_format_validation(request.due_date.clone())?)
+                    });
+                }
+                _ => {
+                    return Err(CoreErrors::DataValidationError(
+                        "validator_invalid_update_mask".to_string(),
+                    )
+                    .into());
+                }
+            }
+        }
+
+        // Step 3: Establish a connection to the database
+        let conn = &self.connection;
+
+        // Step 4: Verify the authenticated user's permissions for the specified task
+        let (_, role_id) =
+            check_tasks_permission(conn, user_id_from_token as i32, request.task_id).await?;
+
+        // Load the stored task to know its current status and assignee
+        let task = match TasksQuery::get_task_by_id(conn, request.task_id).await? {
+            Some(task) => task,
+            None => return Err(Status::not_found("task_not_found")),
+        };
+
+        // Managers and above may edit anything; the assignee may only move the task between statuses
+        let changes_details =
+            title.is_some() || description.is_some() || priority.is_some() || due_date.is_some();
+        let action = if changes_details {
+            Action::TaskUpdate
+        } else {
+            Action::TaskChangeStatus
+        };
+
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, role_id),
+            action,
+            &Resource::owned_by(task.assigned_to),
+        )?;
+
+        // Step 5: Check that the requested status change is allowed
+        if let Some(new_status) = &status {
+            validate_task_status_transition(&task.status, new_status)?;
+        }
+
+        // Step 6: Apply the changes
+        TasksQuery::update_task(conn, task, title, description, status, priority, due_date).await?;
+
+        // Step 7: Construct and return the updated task
+        let response = match TasksQuery::get_task_with_assignee(conn, request.task_id).await? {
+            Some(task) => Response::new(task_response(task)),
+            None => return Err(Status::not_found("task_not_found")),
+        };
+
+        // Log the success event
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Task updated successfully. Response: {:?}",
+            response
+        );
+
+        Ok(response)
+    }
+
+    /// Moves a task to the trash.
     ///
     /// This function validates the authenticated user's permissions, ensuring only users with the "Owner" role
-    /// (role_id == 1) can delete a task. Upon successful validation, the task and all associated user-task
-    /// relationships are removed from the database.
+    /// can delete a task. Upon successful validation, the task and its notes are moved to the trash;
+    /// user-task relationships are kept so the task can be restored.
     ///
     /// # Arguments
     /// * `request` - A gRPC `Request` object containing the task ID to be deleted.
@@ -306,8 +447,8 @@ impl TasksService for MyServer {
             request
         );
 
-        // Step 1: Authenticate the user by checking the auth token in the request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Step 2: Unwrap the gRPC request to access the inner payload
         let request = request.into_inner();
@@ -319,38 +460,29 @@ impl TasksService for MyServer {
         let user_task_access =
             check_tasks_permission(conn, user_id_from_token as i32, request.task_id).await?;
 
-        // Allow deletion only if the user's role is "Owner" (role_id == 1)
-        if user_task_access.1 == 1 {
-            // Step 5: Delete the task from the database
-            TasksQuery::delete_task(conn, request.task_id).await?;
-
-            // Step 6: Delete all user associations with the specified task
-            TasksQuery::delete_all_users_from_task(conn, request.task_id).await?;
+        // Allow deletion only if the user's role is "Owner"
+        authorize(
+            &self.roles,
+            &Principal::new(user_id_from_token as i32, user_task_access.1),
+            Action::TaskDelete,
+            &Resource::default(),
+        )?;
 
-            // Step 7: Construct a success response
-            let response = Response::new(StatusResponse { success: true });
+        // Step 5: Move the task to the trash
+        TasksQuery::delete_task(conn.as_ref(), request.task_id).await?;
 
-            // Log the success event
-            event!(
-                target: "hellai_app_core_events",
-                Level::DEBUG,
-                "Task and associated users deleted successfully. Response: {:?}",
-                response
-            );
+        // Step 6: Construct a success response
+        let response = Response::new(StatusResponse { success: true });
 
-            Ok(response)
-        } else {
-            // Log and return a permission denied error if the user lacks the required "Owner" role
-            event!(
-                target: "hellai_app_core_events",
-                Level::DEBUG,
-                "Permission denied: User lacks 'Owner' role to delete task"
-            );
+        // Log the success event
+        event!(
+            target: "hellai_app_core_events",
+            Level::DEBUG,
+            "Task moved to the trash successfully. Response: {:?}",
+            response
+        );
 
-            Err(Status::permission_denied(
-                "Permission denied: insufficient privileges",
-            ))
-        }
+        Ok(response)
     }
 
     /// Retrieves all tasks for a specified project that the authenticated user has access to.
@@ -381,17 +513,8 @@ impl TasksService for MyServer {
             request
         );
 
-        // Step 1: Authenticate the user by verifying the auth token in the request metadata.
-        let user_id_from_token =
-            interceptors::check_auth_token(request.metadata()).map_err(|e| {
-                event!(
-                    target: "hellai_app_core_events",
-                    Level::ERROR,
-                    "Authentication failed: {:?}",
-                    e
-                );
-                Status::unauthenticated("Invalid authentication token")
-            })?;
+        // Step 1: Get the ID of the user authenticated by the auth layer.
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Extract the inner request data.
         let request = request.into_inner();
@@ -417,29 +540,7 @@ impl TasksService for MyServer {
         })?;
 
         // Step 4: Transform the database task results into the gRPC response format.
-        let tasks_response: Vec<TaskResponse> = tasks_db
-            .into_iter()
-            .map(|task| TaskResponse {
-                task_id: task.id,
-                project_id: task.project_id,
-                assigned_to: Some(AssignedUserInfo {
-                    id: task.assigned_to_id,
-                    name: task.assigned_to_name,
-                }),
-                status: match task.status {
-                    TaskStatusType::Completed => 0,
-                    TaskStatusType::InProgress => 1,
-                    TaskStatusType::Pending => 2,
-                },
-                title: task.title,
-                description: task.description.unwrap_or_else(String::new),
-                priority: task.priority.unwrap_or_else(|| "Medium".to_string()),
-                created_at: task.created_at.to_string(),
-                due_date: task
-                    .due_date
-                    .map_or_else(String::new, |date| date.to_string()),
-            })
-            .collect();
+        let tasks_response: Vec<TaskResponse> = tasks_db.into_iter().map(task_response).collect();
 
         // Step 5: Construct and return the response.
         let response = Response::new(GetAllProjectTasksResponse {
@@ -457,3 +558,111 @@ impl TasksService for MyServer {
         Ok(response)
     }
 }
+
+/// Converts a task query result into the gRPC response format.
+fn task_response(task: TaskModelFromQueryResult) -> TaskResponse {
+    TaskResponse {
+        task_id: task.id,
+        project_id: task.project_id,
+        assigned_to: Some(AssignedUserInfo {
+            id: task.assigned_to_id,
+            name: task.assigned_to_name,
+        }),
+        status: task_status_to_proto(task.status),
+        title: task.title,
+        description: task.description.unwrap_or_default(),
+        priority: task.priority.unwrap_or_else(|| "Medium".to_string()),
+        created_at: task.created_at.to_string(),
+        due_date: task
+            .due_date
+            .map_or_else(String::new, |date| date.to_string()),
+    }
+}
+
+/// Checks that a task may move from its current status to the requested one.
+///
+/// Allowed transitions are `pending -> in_progress`, `in_progress -> pending`,
+/// `in_progress -> completed` and `completed -> in_progress` (reopening).
+/// Keeping the current status is always allowed.
+fn validate_task_status_transition(
+    current: &TaskStatusType,
+    next: &TaskStatusType,
+) -> Result<(), CoreErrors> {
+    let allowed = matches!(
+        (current, next),
+        (TaskStatusType::Pending, TaskStatusType::Pending)
+            | (TaskStatusType::Pending, TaskStatusType::InProgress)
+            | (TaskStatusType::InProgress, _)
+            | (TaskStatusType::Completed, TaskStatusType::Completed)
+            | (TaskStatusType::Completed, TaskStatusType::InProgress)
+    );
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(CoreErrors::DataValidationError(
+            "validator_invalid_status_transition".to_string(),
+        ))
+    }
+}
+
+/// Converts a task status stored in the database into the gRPC `TaskStatusType` enum value.
+pub(crate) fn task_status_to_proto(status: TaskStatusType) -> i32 {
+    match status {
+        TaskStatusType::Completed => ProtoTaskStatusType::Completed as i32,
+        TaskStatusType::InProgress => ProtoTaskStatusType::InProgress as i32,
+        TaskStatusType::Pending => ProtoTaskStatusType::Pending as i32,
+    }
+}
+
+/// Converts a gRPC `TaskStatusType` enum value into the task status stored in the database.
+pub(crate) fn task_status_from_proto(status: i32) -> Result<TaskStatusType, CoreErrors> {
+    match ProtoTaskStatusType::try_from(status) {
+        Ok(ProtoTaskStatusType::Completed) => Ok(TaskStatusType::Completed),
+        Ok(ProtoTaskStatusType::InProgress) => Ok(TaskStatusType::InProgress),
+        Ok(ProtoTaskStatusType::Pending) => Ok(TaskStatusType::Pending),
+        Err(_) => Err(CoreErrors::DataValidationError(
+            "validator_invalid_task_status".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::Iterable;
+
+    use super::*;
+
+    #[test]
+    fn status_transitions() {
+        use TaskStatusType::*;
+
+        let table = [
+            (Pending, Pending, true),
+            (Pending, InProgress, true),
+            (Pending, Completed, false),
+            (InProgress, Pending, true),
+            (InProgress, InProgress, true),
+            (InProgress, Completed, true),
+            (Completed, Pending, false),
+            (Completed, InProgress, true),
+            (Completed, Completed, true),
+        ];
+
+        // Every pair of statuses is covered by the table
+        assert_eq!(table.len(), TaskStatusType::iter().count().pow(2));
+
+        for (current, next, allowed) in table {
+            let result = validate_task_status_transition(&current, &next);
+
+            match result {
+                Ok(()) => assert!(allowed, "{:?} -> {:?} was allowed", current, next),
+                Err(CoreErrors::DataValidationError(message)) => {
+                    assert!(!allowed, "{:?} -> {:?} was refused", current, next);
+                    assert_eq!(message, "validator_invalid_status_transition");
+                }
+                Err(err) => panic!("unexpected error {:?}", err),
+            }
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use chrono::Duration;
+use core_database::queries::trash_query::{TrashItem, TrashItemType, TrashQuery};
+use core_debugger::tracing::{event, Level};
+use core_error::core_errors::CoreErrors;
+use sea_orm::DbConn;
+use tonic::{Request, Response, Status};
+
+use crate::{
+    helai_api_core_service::{
+        trash_service_server::TrashService, ListTrashRequest, ListTrashResponse, RestoreRequest,
+        StatusResponse, TrashItemResponse, TrashItemType as TrashItemTypeProto,
+    },
+    middleware::{
+        access_check::check_company_permission,
+        interceptors,
+        policy::{authorize, Action, Principal, Resource, RoleHierarchy},
+        trash_purge::TRASH_RETENTION_DAYS,
+    },
+    my_server::MyServer,
+};
+
+// Implementing the TrashService trait for MyServer
+#[tonic::async_trait]
+impl TrashService for MyServer {
+    /// Lists a page of the trash, most recently deleted items first.
+    ///
+    /// With a company ID, the deleted projects, tasks and notes of the company are listed, which
+    /// requires at least the "Administrator" role. Without one, the deleted companies the user owns
+    /// and the user's deleted notes outside of any company are listed. Items deleted along with
+    /// their parent are left out, since they are restored with it.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the optional company ID and the requested page.
+    ///
+    /// # Returns
+    /// * `Result<Response<ListTrashResponse>, Status>` - Returns a page of the trash and whether more pages exist.
+    ///
+    /// # Errors
+    /// * Returns `Status::permission_denied` if the user may not see the trash of the company.
+    async fn list_trash(
+        &self,
+        request: Request<ListTrashRequest>,
+    ) -> Result<Response<ListTrashResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received list trash request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        let page_size = match request.page_size {
+            0 => DEFAULT_TRASH_PAGE_SIZE,
+            page_size => page_size.min(MAX_TRASH_PAGE_SIZE),
+        } as u64;
+        let offset = request.page as u64 * page_size;
+
+        // Step 2: Fetch one extra item of the requested scope to find out whether another page exists
+        let mut items = match request.company_id {
+            Some(company_id) => {
+                let user_company_access =
+                    check_company_permission(conn, user_id_from_token as i32, company_id).await?;
+
+                authorize(
+                    &self.roles,
+                    &Principal::new(user_id_from_token as i32, user_company_access.role_id),
+                    Action::TrashRestore,
+                    &Resource::default(),
+                )?;
+
+                TrashQuery::get_company_trash(conn, company_id, page_size + 1, offset).await?
+            }
+            None => {
+                TrashQuery::get_user_trash(conn, user_id_from_token as i32, page_size + 1, offset)
+                    .await?
+            }
+        };
+
+        let has_more = items.len() as u64 > page_size;
+        items.truncate(page_size as usize);
+
+        // Step 3: Transform the database results into the gRPC response format
+        let response = Response::new(ListTrashResponse {
+            items: items.into_iter().filter_map(trash_item_response).collect(),
+            has_more,
+        });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+
+    /// Restores an item from the trash together with everything deleted along with it.
+    ///
+    /// Companies can be restored by their owners, projects, tasks and notes by administrators of
+    /// their company. Authors may also restore their own notes. An item can't be restored while
+    /// one of its parents is in the trash.
+    ///
+    /// # Arguments
+    /// * `request` - A gRPC `Request` object containing the kind and the ID of the item.
+    ///
+    /// # Returns
+    /// * `Result<Response<StatusResponse>, Status>` - Returns a success response once the item is restored.
+    ///
+    /// # Errors
+    /// * Returns `Status::not_found` with `trash_item_not_found` if the item is not in the trash.
+    /// * Returns `Status::permission_denied` if the user may not restore the item.
+    /// * Returns `Status::failed_precondition` with `parent_in_trash` if a parent of the item is in the trash.
+    async fn restore(
+        &self,
+        request: Request<RestoreRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received restore request: {:?}", request);
+
+        // Step 1: Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Unwrap the request to access the inner data
+        let request = request.into_inner();
+
+        let item_type = item_type_from_proto(request.item_type)?;
+
+        // Establish a database connection
+        let conn = &self.connection;
+
+        // Step 2: Load the item from the trash
+        let item = match TrashQuery::get_trash_item(conn, item_type, request.id).await? {
+            Some(item) => item,
+            None => return Err(Status::not_found("trash_item_not_found")),
+        };
+
+        // Step 3: Verify that the user may restore the item
+        check_restore_permission(
+            conn,
+            &self.roles,
+            user_id_from_token as i32,
+            item_type,
+            &item,
+        )
+        .await?;
+
+        // Step 4: Items deleted along with their parent come back with the parent
+        if item.parent_deleted() {
+            return Err(Status::failed_precondition("parent_in_trash"));
+        }
+
+        // Step 5: Restore the item
+        if !TrashQuery::restore(conn, item_type, item.id).await? {
+            return Err(Status::not_found("trash_item_not_found"));
+        }
+
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Response: {:?}", response);
+        Ok(response)
+    }
+}
+
+/// Default page size of `list_trash`.
+const DEFAULT_TRASH_PAGE_SIZE: u32 = 20;
+
+/// Upper bound for the page size of `list_trash`.
+const MAX_TRASH_PAGE_SIZE: u32 = 100;
+
+/// Checks whether a user may restore an item from the trash.
+///
+/// The membership is looked up even if the company itself is in the trash, so administrators
+/// learn that the company has to be restored first.
+///
+/// # Errors
+/// * Returns `Status::permission_denied` if the user may not restore the item.
+async fn check_restore_permission(
+    conn: &DbConn,
+    roles: &RoleHierarchy,
+    user_id: i32,
+    item_type: TrashItemType,
+    item: &TrashItem,
+) -> Result<(), Status> {
+    // Authors may restore their own notes
+    if item_type == TrashItemType::Note && item.author_id == Some(user_id) {
+        return Ok(());
+    }
+
+    let Some(company_id) = item.company_id else {
+        return Err(Status::permission_denied("permission_denied"));
+    };
+
+    let membership =
+        match TrashQuery::get_user_company_membership(conn, user_id, company_id).await? {
+            Some(membership) => membership,
+            None => return Err(Status::permission_denied("permission_denied")),
+        };
+
+    let action = match item_type {
+        TrashItemType::Company => Action::CompanyDelete,
+        TrashItemType::Project | TrashItemType::Task | TrashItemType::Note => Action::TrashRestore,
+    };
+
+    authorize(
+        roles,
+        &Principal::new(user_id, membership.role_id),
+        action,
+        &Resource::default(),
+    )?;
+
+    Ok(())
+}
+
+/// Converts an item in the trash into its gRPC representation.
+fn trash_item_response(item: TrashItem) -> Option<TrashItemResponse> {
+    let item_type = item_type_to_proto(item.kind()?);
+    let purge_at = item.deleted_at + Duration::days(*TRASH_RETENTION_DAYS as i64);
+
+    Some(TrashItemResponse {
+        item_type,
+        id: item.id,
+        company_id: item.company_id,
+        title: item.title,
+        deleted_at: item.deleted_at.to_string(),
+        purge_at: purge_at.to_string(),
+        parent_deleted: item.parent_deleted_at.is_some(),
+    })
+}
+
+/// Converts a kind of trash item into the gRPC `TrashItemType` enum value.
+fn item_type_to_proto(item_type: TrashItemType) -> i32 {
+    match item_type {
+        TrashItemType::Company => TrashItemTypeProto::Company as i32,
+        TrashItemType::Project => TrashItemTypeProto::Project as i32,
+        TrashItemType::Task => TrashItemTypeProto::Task as i32,
+        TrashItemType::Note => TrashItemTypeProto::Note as i32,
+    }
+}
+
+/// Converts a gRPC `TrashItemType` enum value into a kind of trash item.
+fn item_type_from_proto(item_type: i32) -> Result<TrashItemType, CoreErrors> {
+    match TrashItemTypeProto::try_from(item_type) {
+        Ok(TrashItemTypeProto::Company) => Ok(TrashItemType::Company),
+        Ok(TrashItemTypeProto::Project) => Ok(TrashItemType::Project),
+        Ok(TrashItemTypeProto::Task) => Ok(TrashItemType::Task),
+        Ok(TrashItemTypeProto::Note) => Ok(TrashItemType::Note),
+        Err(_) => Err(CoreErrors::DataValidationError(
+            "validator_invalid_trash_item_type".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core_database::{
+        entity::{roles, sea_orm_active_enums::AccessLevelType, user_company},
+        queries::roles_query::{ADMINISTRATOR_LEVEL, OWNER_LEVEL, USER_LEVEL},
+    };
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase};
+    use tonic::Code;
+
+    use super::*;
+
+    const OWNER: i32 = 1;
+    const ADMINISTRATOR: i32 = 2;
+    const USER: i32 = 4;
+    const USER_ID: i32 = 7;
+    const COMPANY_ID: i32 = 3;
+
+    fn role(id: i32, level: i32) -> roles::Model {
+        roles::Model {
+            id,
+            name: format!("role-{}", id),
+            description: None,
+            parent_role_id: None,
+            level,
+        }
+    }
+
+    fn hierarchy() -> RoleHierarchy {
+        RoleHierarchy::new(vec![
+            role(OWNER, OWNER_LEVEL),
+            role(ADMINISTRATOR, ADMINISTRATOR_LEVEL),
+            role(USER, USER_LEVEL),
+        ])
+    }
+
+    fn item(
+        item_type: TrashItemType,
+        company_id: Option<i32>,
+        author_id: Option<i32>,
+    ) -> TrashItem {
+        TrashItem {
+            item_type: item_type.as_str().to_string(),
+            id: 5,
+            company_id,
+            title: "Deleted".to_string(),
+            author_id,
+            deleted_at: Default::default(),
+            parent_deleted_at: None,
+        }
+    }
+
+    /// A database in which the user holds the given role in the company, or is no member.
+    fn membership_database(role_id: Option<i32>) -> DatabaseConnection {
+        let memberships = role_id
+            .map(|role_id| user_company::Model {
+                id: 1,
+                user_id: USER_ID,
+                company_id: COMPANY_ID,
+                role_id,
+                access_level: AccessLevelType::Full,
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([memberships])
+            .into_connection()
+    }
+
+    async fn may_restore(
+        role_id: Option<i32>,
+        item_type: TrashItemType,
+        item: &TrashItem,
+    ) -> Result<(), Status> {
+        let db = membership_database(role_id);
+
+        check_restore_permission(&db, &hierarchy(), USER_ID, item_type, item).await
+    }
+
+    #[tokio::test]
+    async fn companies_are_restored_by_their_owners_only() {
+        let company = item(TrashItemType::Company, Some(COMPANY_ID), None);
+
+        assert!(may_restore(Some(OWNER), TrashItemType::Company, &company)
+            .await
+            .is_ok());
+
+        let status = may_restore(Some(ADMINISTRATOR), TrashItemType::Company, &company)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn company_items_are_restored_by_administrators() {
+        for item_type in [
+            TrashItemType::Project,
+            TrashItemType::Task,
+            TrashItemType::Note,
+        ] {
+            let deleted = item(item_type, Some(COMPANY_ID), None);
+
+            assert!(may_restore(Some(ADMINISTRATOR), item_type, &deleted)
+                .await
+                .is_ok());
+            assert!(may_restore(Some(USER), item_type, &deleted).await.is_err());
+            assert!(may_restore(None, item_type, &deleted).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn authors_restore_their_own_notes() {
+        let own_note = item(TrashItemType::Note, Some(COMPANY_ID), Some(USER_ID));
+        let private_note = item(TrashItemType::Note, None, Some(USER_ID));
+        let foreign_note = item(TrashItemType::Note, None, Some(USER_ID + 1));
+
+        assert!(may_restore(Some(USER), TrashItemType::Note, &own_note)
+            .await
+            .is_ok());
+        assert!(may_restore(None, TrashItemType::Note, &private_note)
+            .await
+            .is_ok());
+
+        let status = may_restore(None, TrashItemType::Note, &foreign_note)
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+    }
+}
@@ -1,15 +1,46 @@
-use core_database::queries::{companies_query::CompaniesQuery, user_query::UserQuery};
+use core_database::{
+    entity::{refresh_tokens, user_totp, users},
+    queries::{
+        companies_query::CompaniesQuery, company_invitations_query::CompanyInvitationsQuery,
+        email_verification_tokens_query::EmailVerificationTokensQuery,
+        oidc_login_requests_query::OidcLoginRequestsQuery,
+        password_reset_tokens_query::PasswordResetTokensQuery,
+        refresh_tokens_query::RefreshTokensQuery, sessions_query::SessionsQuery,
+        totp_query::TotpQuery, user_identities_query::UserIdentitiesQuery, user_query::UserQuery,
+    },
+};
 use core_debugger::tracing::{event, Level};
+use core_error::core_errors::CoreErrors;
 use helai_api_core_service::{
     user_service_server::UserService, AuthUserCompanyProjectsInfoResponse,
     AuthenticateWithPasswordRequest, RefreshSessionTokenRequest, RegisterUserRequest,
     TokenResponse,
 };
 
-use middleware::auth_token::{RefreshClaims, SessionClaims};
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk};
+use lazy_static::lazy_static;
+use middleware::{
+    auth_token::{
+        hash_token, OneTimeToken, RefreshClaims, SessionClaims, TwoFactorChallengeClaims,
+    },
+    client_ip::client_ip,
+    oidc::{IdTokenClaims, OidcProvider},
+    signing_keys::SIGNING_KEYS,
+};
+use rand::Rng;
+use sea_orm::{DbConn, TransactionTrait};
 
-use service::password_validation::{hash_password, verify_hash_password};
+use service::{
+    mailer::{MailMessage, Mailer},
+    password_validation::{hash_password, verify_hash_password},
+    totp::{
+        generate_recovery_codes, generate_totp_secret, normalize_recovery_code, totp_uri,
+        verify_totp_code,
+    },
+};
+use std::{env, sync::Arc};
 use tonic::{Request, Response, Status};
+use uuid::Uuid;
 use validators::{
     empty_validation, max_symbols_validator_20, min_symbols_validator_3,
     no_special_symbols_validator, CompositValidator,
@@ -21,6 +52,13 @@ use crate::{
     my_server::MyServer,
 };
 
+lazy_static! {
+    /// Hash checked against when a login doesn't exist, so failed logins take equally long either way.
+    static ref DUMMY_PASSWORD_HASH: String = hash_password("dummy-password")
+        .map(|(password_hash, _)| password_hash)
+        .unwrap_or_default();
+}
+
 #[tonic::async_trait]
 impl UserService for MyServer {
     /// 🧨 Get user data with creds.
@@ -33,19 +71,35 @@ impl UserService for MyServer {
 
         // Extract connection for database queries
         let conn = &self.connection;
+        let client_info = ClientInfo::from_request(&request);
         let request = request.into_inner();
 
         // Validate login and password formats using custom validators
         let login = validators::login_format_validation(request.login)?;
         let password = validators::password_format_validation(request.password)?;
 
+        // Refuse to check passwords while the login or the client's IP address is locked out
+        login_throttle::check_login_allowed(conn, &login, client_info.ip_address.as_deref())
+            .await?;
+
         // Retrieve user information by login from the database
-        let user_with_password = match UserQuery::get_user_by_login(conn, login).await? {
+        let user_with_password = match UserQuery::get_user_by_login(conn, login.clone()).await? {
             Some(user) => user,
             None => {
-                return Err(Status::invalid_argument(
-                    "User not found: Invalid login credentials",
-                ));
+                // Hash anyway, so the response time doesn't reveal that the login doesn't exist
+                verify_hash_password(&DUMMY_PASSWORD_HASH, &password)?;
+
+                login_throttle::record_login_failure(
+                    conn,
+                    &login,
+                    None,
+                    client_info.ip_address,
+                    client_info.device_info,
+                    "unknown_login",
+                )
+                .await?;
+
+                return Err(Status::unauthenticated("invalid_credentials"));
             }
         };
 
@@ -53,57 +107,41 @@ impl UserService for MyServer {
 
         // Verify the provided password against the stored password hash
         if !verify_hash_password(&user_with_password.1.password_hash, &password)? {
-            return Err(Status::invalid_argument(
-                "Authentication failed: Incorrect password",
-            ));
-        }
+            login_throttle::record_login_failure(
+                conn,
+                &login,
+                Some(user.id),
+                client_info.ip_address,
+                client_info.device_info,
+                "invalid_password",
+            )
+            .await?;
 
-        // Generate session and refresh tokens for the authenticated user
-        let session_claims = SessionClaims::new(user.id as i64);
-        let session_token = session_claims.into_token()?;
+            return Err(Status::unauthenticated("invalid_credentials"));
+        }
 
-        let refresh_claims = RefreshClaims::new(user.id as i64);
-        let refresh_token = refresh_claims.into_token()?;
+        // Deactivated accounts can't sign in, checked after the password so it doesn't leak account state
+        if !user.is_active {
+            return Err(Status::permission_denied("account_deactivated"));
+        }
 
-        // Fetch user's associated company and project information
-        let user_company_with_projects =
-            CompaniesQuery::get_company_with_projects(conn, user.id, None).await?;
+        // Users with two-factor authentication have to confirm the login with a second factor first
+        let reply = if TotpQuery::get_user_totp(conn, user.id)
+            .await?
+            .is_some_and(|totp| totp.confirmed_at.is_some())
+        {
+            event!(target: "hellai_app_core_events", Level::DEBUG, "Two-factor authentication required for user {}", user.id);
 
-        // Format response structure for company and projects, if available
-        let (company_info, projects) = match user_company_with_projects {
-            Some(company) => (
-                Some(CompanyInfoResponse {
-                    id: company.id,
-                    name: company.name,
-                    name_alias: company.name_alias,
-                    description: company.description,
-                    contact_info: company.contact_info,
-                }),
-                company
-                    .company_projects
-                    .into_iter()
-                    .map(|project| ProjectsResponse {
-                        id: project.id,
-                        company_id: project.company_id,
-                        title: project.title,
-                        description: project.description,
-                        decoration_color: project.decoration_color,
-                    })
-                    .collect(),
-            ),
-            None => (None, vec![]), // Empty vector if no projects are found
-        };
+            AuthUserCompanyProjectsInfoResponse {
+                two_factor_required: true,
+                two_factor_challenge_token: TwoFactorChallengeClaims::new(user.id as i64)
+                    .into_token()?,
+                ..Default::default()
+            }
+        } else {
+            login_throttle::record_login_success(conn, &login).await?;
 
-        // Construct response with user and company/project details
-        let reply = AuthUserCompanyProjectsInfoResponse {
-            user_id: user.id,
-            email: user.email,
-            user_name: user.user_name,
-            login: user.login,
-            session_token,
-            refresh_token,
-            company: company_info,
-            user_projects: projects,
+            login_response(conn, user, client_info).await?
         };
 
         // Wrap response in gRPC Response object and log it
@@ -114,6 +152,184 @@ impl UserService for MyServer {
         Ok(response)
     }
 
+    /// 🧨 Finishes a login with two-factor authentication.
+    async fn complete_two_factor_login(
+        &self,
+        request: Request<CompleteTwoFactorLoginRequest>,
+    ) -> Result<Response<AuthUserCompanyProjectsInfoResponse>, Status> {
+        // The request carries a one-time code, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received complete two-factor login request");
+
+        // Extract connection for database queries
+        let conn = &self.connection;
+        let client_info = ClientInfo::from_request(&request);
+        let request = request.into_inner();
+
+        // The challenge token proves the password step succeeded a few minutes ago
+        let challenge_claims = TwoFactorChallengeClaims::from_token(request.challenge_token)?;
+        let user_id = challenge_claims.sub as i32;
+
+        let user = match UserQuery::get_user_by_id(conn, user_id).await? {
+            Some(user) => user,
+            None => return Err(Status::unauthenticated("invalid_token")),
+        };
+
+        if !user.is_active {
+            return Err(Status::permission_denied("account_deactivated"));
+        }
+
+        // Check the second factor
+        let totp = match TotpQuery::get_user_totp(conn, user_id).await? {
+            Some(totp) if totp.confirmed_at.is_some() => totp,
+            _ => return Err(Status::failed_precondition("two_factor_not_enabled")),
+        };
+
+        // Codes are guessed far more easily than passwords, so they are throttled the same way
+        login_throttle::check_login_allowed(conn, &user.login, client_info.ip_address.as_deref())
+            .await?;
+
+        if !verify_second_factor(conn, &totp, &request.code).await? {
+            login_throttle::record_login_failure(
+                conn,
+                &user.login,
+                Some(user.id),
+                client_info.ip_address,
+                client_info.device_info,
+                "invalid_two_factor_code",
+            )
+            .await?;
+
+            return Err(Status::invalid_argument("invalid_two_factor_code"));
+        }
+
+        login_throttle::record_login_success(conn, &user.login).await?;
+
+        let reply = login_response(conn, user, client_info).await?;
+
+        // Wrap response in gRPC Response object and log it
+        let response = Response::new(reply);
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Complete two-factor login response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Starts a login with the external OpenID Connect identity provider.
+    async fn start_oidc_login(
+        &self,
+        request: Request<StartOidcLoginRequest>,
+    ) -> Result<Response<StartOidcLoginResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received start OIDC login request: {:?}", request);
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        let provider = oidc_provider(&self.oidc)?;
+
+        // Keep the nonce and the PKCE code verifier until the user comes back with the state
+        let login = provider.start_login().await?;
+        OidcLoginRequestsQuery::create_login_request(
+            conn,
+            hash_token(&login.state),
+            login.nonce,
+            login.code_verifier,
+            login.expires_at,
+        )
+        .await?;
+
+        let response = Response::new(StartOidcLoginResponse {
+            authorization_url: login.authorization_url,
+            state: login.state,
+        });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Start OIDC login response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Finishes a login with the external OpenID Connect identity provider.
+    ///
+    /// Users with two-factor authentication get a TOTP challenge like on a password login, unless
+    /// the identity provider asserts that the user already signed in with more than one factor.
+    async fn complete_oidc_login(
+        &self,
+        request: Request<CompleteOidcLoginRequest>,
+    ) -> Result<Response<AuthUserCompanyProjectsInfoResponse>, Status> {
+        // The request carries an authorization code, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received complete OIDC login request");
+
+        // Extract connection for database queries
+        let conn = &self.connection;
+        let client_info = ClientInfo::from_request(&request);
+        let request = request.into_inner();
+
+        let provider = oidc_provider(&self.oidc)?;
+
+        // Redeem the state; unknown, used and expired logins are rejected alike
+        let login_request =
+            match OidcLoginRequestsQuery::consume_login_request(conn, &hash_token(&request.state))
+                .await?
+            {
+                Some(login_request) => login_request,
+                None => return Err(Status::invalid_argument("invalid_oidc_state")),
+            };
+
+        // The ID token must have been issued for this very login
+        let id_token = provider
+            .exchange_code(&request.code, &login_request.code_verifier)
+            .await?;
+        let claims = provider
+            .validate_id_token(&id_token, &login_request.nonce)
+            .await?;
+
+        let issuer = provider.issuer().await?;
+        let mfa_asserted = provider.asserts_mfa(&claims);
+
+        // Sign in the linked user, linking the identity on its first login
+        let user = match UserIdentitiesQuery::touch_identity(conn, issuer, &claims.sub).await? {
+            Some(user) => user,
+            None => {
+                link_oidc_identity(
+                    conn,
+                    self.mailer.as_ref(),
+                    issuer,
+                    provider.link_by_email(),
+                    claims,
+                )
+                .await?
+            }
+        };
+
+        if !user.is_active {
+            return Err(Status::permission_denied("account_deactivated"));
+        }
+
+        // The second factor of the user is only skipped if the identity provider checked one
+        let reply = if !mfa_asserted
+            && TotpQuery::get_user_totp(conn, user.id)
+                .await?
+                .is_some_and(|totp| totp.confirmed_at.is_some())
+        {
+            event!(target: "hellai_app_core_events", Level::DEBUG, "Two-factor authentication required for user {}", user.id);
+
+            AuthUserCompanyProjectsInfoResponse {
+                two_factor_required: true,
+                two_factor_challenge_token: TwoFactorChallengeClaims::new(user.id as i64)
+                    .into_token()?,
+                ..Default::default()
+            }
+        } else {
+            login_response(conn, user, client_info).await?
+        };
+
+        // Wrap response in gRPC Response object and log it
+        let response = Response::new(reply);
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Complete OIDC login response: {:?}", response);
+
+        Ok(response)
+    }
+
     /// 🧨 Get user data with auth token.
     async fn get_user_data(
         &self,
@@ -125,8 +341,8 @@ impl UserService for MyServer {
         // Extract database connection
         let conn = &self.connection;
 
-        // Extract user ID from authentication token in request metadata
-        let user_id_from_token = interceptors::check_auth_token(request.metadata())?;
+        // Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
 
         // Unwrap the request to access its inner data
         let request = request.into_inner();
@@ -160,6 +376,7 @@ impl UserService for MyServer {
                         title: project.title,
                         description: project.description,
                         decoration_color: project.decoration_color,
+                        archived_at: None,
                     })
                     .collect(),
             ),
@@ -172,6 +389,7 @@ impl UserService for MyServer {
             email: user.email,
             user_name: user.user_name,
             login: user.login,
+            email_verified: user.email_verified_at.is_some(),
             company: company_info,
             user_projects: projects,
         };
@@ -189,12 +407,15 @@ impl UserService for MyServer {
         &self,
         request: Request<RegisterUserRequest>,
     ) -> Result<Response<NewUserResponse>, Status> {
-        // Log the incoming registration request for debugging purposes
-        event!(target: "hellai_app_core_events", Level::DEBUG, "Received registration request: {:?}", request);
+        // The request carries a password and maybe an invitation token, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received registration request");
 
         // Extract the database connection
         let conn = &self.connection;
 
+        // Read the client description before unwrapping the request
+        let client_info = ClientInfo::from_request(&request);
+
         // Unwrap the request to access its inner data
         let request = request.into_inner();
 
@@ -211,22 +432,55 @@ impl UserService for MyServer {
         let password = validators::password_format_validation(request.password)?;
         let email = validators::email_format_validation(request.email)?;
 
+        // A company invitation link proves access to the email it was sent to
+        let invited = match request.invitation_token {
+            Some(token) => {
+                CompanyInvitationsQuery::get_pending_invitation_by_token(conn, &hash_token(&token))
+                    .await?
+                    .filter(|invitation| invitation.email.to_lowercase() == email.to_lowercase())
+                    .ok_or_else(|| Status::invalid_argument("invalid_invitation"))?;
+                true
+            }
+            None => false,
+        };
+
         // Hash the password for secure storage
         let hashed_password = hash_password(password.as_str())?;
 
-        // Create a new user record in the database
-        let new_user =
-            UserQuery::create_new_user(conn, login, user_name, hashed_password.0, email).await?;
+        let new_user = if invited {
+            // The email is confirmed, create the user and join every company that invited it at once
+            let txn = conn.begin().await.map_err(CoreErrors::from)?;
+            let (new_user, memberships) =
+                UserQuery::create_invited_user(&txn, login, user_name, hashed_password.0, email)
+                    .await?;
+            txn.commit().await.map_err(CoreErrors::from)?;
 
-        // Log the newly created user details
-        event!(target: "hellai_app_core_events", Level::DEBUG, "New user created: {:?}", new_user);
+            event!(target: "hellai_app_core_events", Level::DEBUG, "New user created: {:?}", new_user);
+            event!(target: "hellai_app_core_events", Level::DEBUG, "User {} joined {} companies they were invited to", new_user.id, memberships.len());
 
-        // Generate session and refresh tokens for the new user
-        let session_claims = SessionClaims::new(new_user.id as i64);
-        let session_token = session_claims.into_token()?;
+            new_user
+        } else {
+            // Create a new user record in the database
+            let new_user = UserQuery::create_new_user(
+                conn.as_ref(),
+                login,
+                user_name,
+                hashed_password.0,
+                email,
+            )
+            .await?;
+
+            event!(target: "hellai_app_core_events", Level::DEBUG, "New user created: {:?}", new_user);
+
+            // Ask the user to confirm the email; unverified accounts are limited until they do
+            send_verification_email(conn, self.mailer.as_ref(), new_user.id, &new_user.email)
+                .await?;
+
+            new_user
+        };
 
-        let refresh_claims = RefreshClaims::new(new_user.id as i64);
-        let refresh_token = refresh_claims.into_token()?;
+        // Start a new session and generate its session and refresh tokens for the new user
+        let (session_token, refresh_token) = start_session(conn, new_user.id, client_info).await?;
 
         // Prepare the response with new user details and tokens
         let reply = NewUserResponse {
@@ -238,21 +492,31 @@ impl UserService for MyServer {
             refresh_token,
         };
 
-        // Wrap the response in a gRPC Response object and log it
+        // The response carries the session tokens, so only the user is logged
         let response = Response::new(reply);
-        event!(target: "hellai_app_core_events", Level::DEBUG, "Registration response: {:?}", response);
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Registered user {}", response.get_ref().user_id);
 
         // Return the successful response
         Ok(response)
     }
 
     /// 🧨 Refreshes the user's session token using a provided refresh token.
+    ///
+    /// The refresh token is rotated: a new one is issued for the same session and the
+    /// presented one is revoked. Presenting a token that was already rotated or revoked is
+    /// treated as token theft and signs out the whole session.
     async fn refresh_session_token(
         &self,
         request: Request<RefreshSessionTokenRequest>,
     ) -> Result<Response<TokenResponse>, Status> {
-        // Log the incoming request for debugging purposes
-        event!(target: "hellai_app_core_events", Level::DEBUG, "Received refresh token request: {:?}", request);
+        // The request carries a refresh token, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received refresh token request");
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Read the client description before unwrapping the request
+        let client_info = ClientInfo::from_request(&request);
 
         // Extract the inner data from the gRPC request
         let request = request.into_inner();
@@ -261,20 +525,1429 @@ impl UserService for MyServer {
         let refresh_token = request.refresh_token;
 
         // Decode and validate the refresh token claims
-        let refresh_claims = RefreshClaims::from_token(refresh_token)?;
+        let refresh_claims = RefreshClaims::from_token(refresh_token.clone())?;
+
+        // Look up the stored token; unknown tokens are rejected
+        let stored_token = load_refresh_token(conn, &refresh_token, &refresh_claims).await?;
+
+        // A revoked token is being reused, so the whole session is compromised
+        if stored_token.revoked_at.is_some() {
+            return Err(revoke_reused_session(conn, &stored_token).await);
+        }
 
-        // Generate a new session token using the user ID from the refresh claims
-        let session_claims = SessionClaims::new(refresh_claims.sub);
+        // Deactivated accounts can't extend their sessions
+        match UserQuery::get_user_by_id(conn, stored_token.user_id).await? {
+            Some(user) if user.is_active => {}
+            _ => return Err(Status::unauthenticated("account_deactivated")),
+        }
+
+        // Issue the replacement token for the same session and revoke the presented one
+        let new_refresh_claims = RefreshClaims::new(refresh_claims.sub);
+        let new_jti = new_refresh_claims.jti.clone();
+        let expires_at = new_refresh_claims.expires_at();
+        let new_refresh_token = new_refresh_claims.into_token()?;
+
+        // Another request rotated the token first, which also means reuse
+        let txn = conn.begin().await.map_err(CoreErrors::from)?;
+        if !RefreshTokensQuery::replace_refresh_token(
+            &txn,
+            &stored_token,
+            new_jti,
+            hash_token(&new_refresh_token),
+            client_info.device_info,
+            expires_at,
+        )
+        .await?
+        {
+            // Discard the new token before the session is revoked
+            drop(txn);
+            return Err(revoke_reused_session(conn, &stored_token).await);
+        }
+        txn.commit().await.map_err(CoreErrors::from)?;
+
+        // Generate a new session token for the same session
+        let session_claims = SessionClaims::new(refresh_claims.sub, stored_token.family_id);
         let session_token = session_claims.into_token()?;
 
-        // Prepare the response containing the new session token
-        let reply = TokenResponse { session_token };
+        // Prepare the response containing the new tokens
+        let reply = TokenResponse {
+            session_token,
+            refresh_token: new_refresh_token,
+        };
         let response = Response::new(reply);
 
-        // Log the response for debugging purposes
-        event!(target: "hellai_app_core_events", Level::DEBUG, "Generated new session token response: {:?}", response);
+        // The response carries the new tokens, so only the user is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Refreshed the session tokens of user {}", refresh_claims.sub);
 
         // Return the successful response
         Ok(response)
     }
+
+    /// 🧨 Logs out of the current device by revoking its session and refresh tokens.
+    async fn logout(
+        &self,
+        request: Request<LogoutRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // The request carries a refresh token, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received logout request");
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        // Decode the refresh token and make sure it belongs to the authenticated user
+        let refresh_claims = RefreshClaims::from_token(request.refresh_token.clone())?;
+        let stored_token =
+            load_refresh_token(conn, &request.refresh_token, &refresh_claims).await?;
+
+        if stored_token.user_id as i64 != user_id_from_token {
+            return Err(Status::permission_denied("permission_denied"));
+        }
+
+        // Sign out the session the token was issued for
+        revoke_session(conn, &stored_token.family_id, stored_token.user_id).await?;
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Logout response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Logs out of all devices by revoking every session and refresh token of the user.
+    async fn logout_all_sessions(
+        &self,
+        request: Request<LogoutAllSessionsRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received logout all sessions request: {:?}", request);
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Revoke every session and refresh token of the user, or neither
+        let txn = conn.begin().await.map_err(CoreErrors::from)?;
+        let revoked_sessions =
+            SessionsQuery::revoke_all_user_sessions(&txn, user_id_from_token as i32).await?;
+        RefreshTokensQuery::revoke_all_user_tokens(&txn, user_id_from_token as i32).await?;
+        txn.commit().await.map_err(CoreErrors::from)?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Revoked {} sessions of user {}", revoked_sessions, user_id_from_token);
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Logout all sessions response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Lists the active sessions of the user, most recently used first.
+    async fn list_sessions(
+        &self,
+        request: Request<ListSessionsRequest>,
+    ) -> Result<Response<ListSessionsResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received list sessions request: {:?}", request);
+
+        // Get the user and session authenticated by the auth layer
+        let auth_context = interceptors::auth_context(&request)?;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Fetch the sessions that are neither revoked nor expired
+        let sessions =
+            SessionsQuery::get_active_user_sessions(conn, auth_context.user_id as i32).await?;
+
+        let response = Response::new(ListSessionsResponse {
+            sessions: sessions
+                .into_iter()
+                .map(|session| SessionResponse {
+                    is_current: session.id == auth_context.session_id,
+                    session_id: session.id,
+                    device_info: session.device_info,
+                    ip_address: session.ip_address,
+                    created_at: session.created_at.to_string(),
+                    last_used_at: session.last_used_at.to_string(),
+                })
+                .collect(),
+        });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "List sessions response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Signs out a single session of the user, e.g. a lost device.
+    async fn revoke_session(
+        &self,
+        request: Request<RevokeSessionRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received revoke session request: {:?}", request);
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        // Only the user's own active sessions can be revoked
+        if !revoke_session(conn, &request.session_id, user_id_from_token as i32).await? {
+            return Err(Status::not_found("session_not_found"));
+        }
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Revoke session response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Returns the public keys tokens can be verified with.
+    async fn get_jwks(
+        &self,
+        request: Request<GetJwksRequest>,
+    ) -> Result<Response<JwksResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received get JWKS request: {:?}", request);
+
+        let response = Response::new(JwksResponse {
+            keys: SIGNING_KEYS.jwks().keys.iter().map(json_web_key).collect(),
+        });
+
+        Ok(response)
+    }
+
+    /// 🧨 Emails a single-use password reset link.
+    ///
+    /// Always succeeds for a well-formed email, so the response doesn't reveal whether
+    /// an account is registered with it.
+    async fn request_password_reset(
+        &self,
+        request: Request<RequestPasswordResetRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received password reset request: {:?}", request);
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        let email = validators::email_format_validation(request.email)?;
+
+        // Deactivated accounts get no reset link, they must be reactivated first
+        if let Some(user) = UserQuery::get_user_by_email(conn, &email)
+            .await?
+            .filter(|user| user.is_active)
+        {
+            send_password_reset_email(conn, self.mailer.as_ref(), &user).await?;
+        }
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Password reset response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Sets a new password using a password reset token and signs the user out everywhere.
+    async fn confirm_password_reset(
+        &self,
+        request: Request<ConfirmPasswordResetRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // The request carries the token and the new password, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received confirm password reset request");
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        // Validate the new password before the token is used up
+        let password = validators::password_format_validation(request.new_password)?;
+
+        // Redeem the token; unknown, used and expired tokens are rejected alike
+        let user_id =
+            match PasswordResetTokensQuery::consume_reset_token(conn, &hash_token(&request.token))
+                .await?
+            {
+                Some(user_id) => user_id,
+                None => return Err(Status::invalid_argument("invalid_reset_token")),
+            };
+
+        // Store the new password; whoever knew the old one must not stay signed in
+        let hashed_password = hash_password(password.as_str())?;
+        let txn = conn.begin().await.map_err(CoreErrors::from)?;
+        UserQuery::change_user_password(&txn, user_id, hashed_password.0, None).await?;
+        txn.commit().await.map_err(CoreErrors::from)?;
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Confirm password reset response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Changes the password of the user and signs out all other sessions.
+    async fn change_password(
+        &self,
+        request: Request<ChangePasswordRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // The request carries passwords, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received change password request");
+
+        // Get the user and session authenticated by the auth layer
+        let auth_context = interceptors::auth_context(&request)?.clone();
+        let user_id = auth_context.user_id as i32;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        let new_password = validators::password_format_validation(request.new_password)?;
+
+        // The current password must be confirmed before it can be replaced
+        let stored_password = match UserQuery::get_user_password(conn, user_id).await? {
+            Some(password) => password,
+            None => return Err(Status::not_found("User not found: Invalid user ID")),
+        };
+
+        if !verify_hash_password(&stored_password.password_hash, &request.current_password)? {
+            return Err(Status::invalid_argument(
+                "Authentication failed: Incorrect password",
+            ));
+        }
+
+        // Store the new password and sign out everywhere except the device that changed it
+        let hashed_password = hash_password(new_password.as_str())?;
+        let txn = conn.begin().await.map_err(CoreErrors::from)?;
+        let revoked_sessions = UserQuery::change_user_password(
+            &txn,
+            user_id,
+            hashed_password.0,
+            Some(&auth_context.session_id),
+        )
+        .await?;
+        txn.commit().await.map_err(CoreErrors::from)?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Password changed, revoked {} other sessions of user {}", revoked_sessions, user_id);
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Change password response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Updates the display name and/or email of the user.
+    async fn update_profile(
+        &self,
+        request: Request<UpdateProfileRequest>,
+    ) -> Result<Response<UserProfileResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received update profile request: {:?}", request);
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        if request.user_name.is_none() && request.email.is_none() {
+            return Err(Status::invalid_argument("nothing_to_update"));
+        }
+
+        let composite_validator = CompositValidator::new(vec![
+            empty_validation,
+            min_symbols_validator_3,
+            max_symbols_validator_20,
+            no_special_symbols_validator,
+        ]);
+
+        // Validate the fields that are being changed
+        let user_name = request
+            .user_name
+            .map(|user_name| composite_validator.validate(user_name))
+            .transpose()?;
+        let email = request
+            .email
+            .map(validators::email_format_validation)
+            .transpose()?;
+
+        let current_user = match UserQuery::get_user_by_id(conn, user_id_from_token as i32).await? {
+            Some(user) => user,
+            None => return Err(Status::not_found("User not found: Invalid user ID")),
+        };
+
+        // Re-submitting the current email must not reset its verification
+        let email = email.filter(|email| *email != current_user.email);
+        let email_changed = email.is_some();
+
+        // Update the user; a taken email is reported as duplicate data
+        let user =
+            match UserQuery::update_user_profile(conn, current_user.id, user_name, email).await? {
+                Some(user) => user,
+                None => return Err(Status::not_found("User not found: Invalid user ID")),
+            };
+
+        // The new address has to be confirmed before the account is fully usable again
+        if email_changed {
+            send_verification_email(conn, self.mailer.as_ref(), user.id, &user.email).await?;
+        }
+
+        let response = Response::new(UserProfileResponse {
+            user_id: user.id,
+            login: user.login,
+            user_name: user.user_name,
+            email: user.email,
+            email_verified: user.email_verified_at.is_some(),
+        });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Update profile response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Confirms the email of a user with the token from a verification link.
+    async fn verify_email(
+        &self,
+        request: Request<VerifyEmailRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // The request carries the token, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received verify email request");
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        // Redeem the token; unknown, used and expired tokens are rejected alike
+        let token = match EmailVerificationTokensQuery::consume_verification_token(
+            conn,
+            &hash_token(&request.token),
+        )
+        .await?
+        {
+            Some(token) => token,
+            None => return Err(Status::invalid_argument("invalid_verification_token")),
+        };
+
+        // The link only verifies the address it was sent to
+        if !UserQuery::mark_email_verified(conn.as_ref(), token.user_id, &token.email).await? {
+            return Err(Status::invalid_argument("invalid_verification_token"));
+        }
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Email of user {} verified", token.user_id);
+
+        // Pending invitations to the confirmed email are accepted
+        join_invited_companies(conn, token.user_id, &token.email).await?;
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Verify email response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Sends a new verification link to the email of the user.
+    async fn resend_verification_email(
+        &self,
+        request: Request<ResendVerificationEmailRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received resend verification email request: {:?}", request);
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        let user = match UserQuery::get_user_by_id(conn, user_id_from_token as i32).await? {
+            Some(user) => user,
+            None => return Err(Status::not_found("User not found: Invalid user ID")),
+        };
+
+        if user.email_verified_at.is_some() {
+            return Err(Status::failed_precondition("email_already_verified"));
+        }
+
+        send_verification_email(conn, self.mailer.as_ref(), user.id, &user.email).await?;
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Resend verification email response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Starts enrolling an authenticator app for two-factor authentication.
+    async fn enroll_totp(
+        &self,
+        request: Request<EnrollTotpRequest>,
+    ) -> Result<Response<EnrollTotpResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received enroll TOTP request");
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id = interceptors::auth_context(&request)?.user_id as i32;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        let user = match UserQuery::get_user_by_id(conn, user_id).await? {
+            Some(user) => user,
+            None => return Err(Status::not_found("User not found: Invalid user ID")),
+        };
+
+        // Enrolling again would silently replace the authenticator in use
+        if TotpQuery::get_user_totp(conn, user_id)
+            .await?
+            .is_some_and(|totp| totp.confirmed_at.is_some())
+        {
+            return Err(Status::failed_precondition("two_factor_already_enabled"));
+        }
+
+        // Store a new secret, which stays inactive until it is confirmed
+        let secret = generate_totp_secret();
+        let otpauth_uri = totp_uri(&secret, &user.login)?;
+        TotpQuery::start_enrolment(conn, user_id, secret.clone()).await?;
+
+        // The response carries the secret, so it is not logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "TOTP enrolment started for user {}", user_id);
+
+        Ok(Response::new(EnrollTotpResponse {
+            secret,
+            otpauth_uri,
+        }))
+    }
+
+    /// 🧨 Enables two-factor authentication with a code from the enrolled authenticator app.
+    async fn confirm_totp(
+        &self,
+        request: Request<ConfirmTotpRequest>,
+    ) -> Result<Response<RecoveryCodesResponse>, Status> {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received confirm TOTP request");
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id = interceptors::auth_context(&request)?.user_id as i32;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        let totp = match TotpQuery::get_user_totp(conn, user_id).await? {
+            Some(totp) if totp.confirmed_at.is_none() => totp,
+            Some(_) => return Err(Status::failed_precondition("two_factor_already_enabled")),
+            None => {
+                return Err(Status::failed_precondition(
+                    "two_factor_enrolment_not_started",
+                ))
+            }
+        };
+
+        // The code proves the authenticator app was set up correctly
+        let used_step = match verify_totp_code(&totp.secret, &request.code, None)? {
+            Some(step) => step,
+            None => return Err(Status::invalid_argument("invalid_two_factor_code")),
+        };
+
+        // Issue recovery codes; only their hashes are stored
+        let recovery_codes = generate_recovery_codes();
+        let recovery_code_hashes = recovery_codes.iter().map(|code| hash_token(code)).collect();
+
+        let txn = conn.begin().await.map_err(CoreErrors::from)?;
+        if !TotpQuery::confirm_enrolment(&txn, user_id, used_step, recovery_code_hashes).await? {
+            return Err(Status::failed_precondition(
+                "two_factor_enrolment_not_started",
+            ));
+        }
+        txn.commit().await.map_err(CoreErrors::from)?;
+
+        // The response carries the recovery codes, so it is not logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Two-factor authentication enabled for user {}", user_id);
+
+        Ok(Response::new(RecoveryCodesResponse { recovery_codes }))
+    }
+
+    /// 🧨 Disables two-factor authentication after confirming the current password.
+    async fn disable_totp(
+        &self,
+        request: Request<DisableTotpRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // The request carries a password, so only the method is logged
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received disable TOTP request");
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id = interceptors::auth_context(&request)?.user_id as i32;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        // A stolen session alone must not be enough to turn off the second factor
+        let stored_password = match UserQuery::get_user_password(conn, user_id).await? {
+            Some(password) => password,
+            None => return Err(Status::not_found("User not found: Invalid user ID")),
+        };
+
+        if !verify_hash_password(&stored_password.password_hash, &request.password)? {
+            return Err(Status::invalid_argument(
+                "Authentication failed: Incorrect password",
+            ));
+        }
+
+        let txn = conn.begin().await.map_err(CoreErrors::from)?;
+        if !TotpQuery::disable_totp(&txn, user_id).await? {
+            return Err(Status::failed_precondition("two_factor_not_enabled"));
+        }
+        txn.commit().await.map_err(CoreErrors::from)?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Two-factor authentication disabled for user {}", user_id);
+
+        let response = Response::new(StatusResponse { success: true });
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Disable TOTP response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Deactivates the account of a user, e.g. when it was compromised or abused.
+    ///
+    /// The account is locked out of the whole platform: it can't sign in or refresh tokens, and
+    /// all of its sessions are revoked immediately. Only platform admins may deactivate accounts.
+    async fn deactivate_user(
+        &self,
+        request: Request<UserAccountRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received deactivate user request: {:?}", request);
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id as i32;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        authorize_account_activation(conn, user_id_from_token, request.user_id).await?;
+
+        // Lock the account and sign it out everywhere
+        let txn = conn.begin().await.map_err(CoreErrors::from)?;
+        let revoked_sessions = match UserQuery::deactivate_user(&txn, request.user_id).await? {
+            Some(revoked_sessions) => revoked_sessions,
+            None => return Err(Status::not_found("User not found: Invalid user ID")),
+        };
+        txn.commit().await.map_err(CoreErrors::from)?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "User {} deactivated by {}, revoked {} sessions", request.user_id, user_id_from_token, revoked_sessions);
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Deactivate user response: {:?}", response);
+
+        Ok(response)
+    }
+
+    /// 🧨 Allows a deactivated account to sign in again. Only platform admins may reactivate accounts.
+    async fn reactivate_user(
+        &self,
+        request: Request<UserAccountRequest>,
+    ) -> Result<Response<StatusResponse>, Status> {
+        // Log the incoming request for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Received reactivate user request: {:?}", request);
+
+        // Get the ID of the user authenticated by the auth layer
+        let user_id_from_token = interceptors::auth_context(&request)?.user_id as i32;
+
+        // Extract the database connection
+        let conn = &self.connection;
+
+        // Extract the inner data from the gRPC request
+        let request = request.into_inner();
+
+        authorize_account_activation(conn, user_id_from_token, request.user_id).await?;
+
+        if !UserQuery::set_user_active(conn.as_ref(), request.user_id, true).await? {
+            return Err(Status::not_found("User not found: Invalid user ID"));
+        }
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "User {} reactivated by {}", request.user_id, user_id_from_token);
+
+        let response = Response::new(StatusResponse { success: true });
+
+        // Log the response for debugging purposes
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Reactivate user response: {:?}", response);
+
+        Ok(response)
+    }
+}
+
+/// Checks that a user may deactivate or reactivate the account of another user.
+///
+/// A deactivated account is locked out of every company, so only platform admins may do this,
+/// never company administrators. Nobody can deactivate their own account.
+///
+/// # Errors
+/// * Returns `Status::invalid_argument` if the caller targets themselves.
+/// * Returns `Status::permission_denied` if the caller is not a platform admin.
+async fn authorize_account_activation(
+    conn: &DbConn,
+    user_id: i32,
+    target_user_id: i32,
+) -> Result<(), Status> {
+    if user_id == target_user_id {
+        return Err(Status::invalid_argument("cannot_deactivate_self"));
+    }
+
+    if !UserQuery::is_platform_admin(conn, user_id).await? {
+        return Err(Status::permission_denied("permission_denied"));
+    }
+
+    Ok(())
+}
+
+/// Issues an email verification token and sends the verification link.
+///
+/// A delivery failure is only logged, the user can ask for a new link with `ResendVerificationEmail`.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `mailer` - The mailer delivering the link.
+/// * `user_id` - The ID of the user.
+/// * `email` - The address to verify.
+async fn send_verification_email(
+    conn: &DbConn,
+    mailer: &dyn Mailer,
+    user_id: i32,
+    email: &str,
+) -> Result<(), CoreErrors> {
+    let verification_token = OneTimeToken::email_verification();
+
+    EmailVerificationTokensQuery::create_verification_token(
+        conn,
+        user_id,
+        email.to_string(),
+        verification_token.token_hash,
+        verification_token.expires_at,
+    )
+    .await?;
+
+    if let Err(err) = mailer
+        .send(email_verification_message(
+            email.to_string(),
+            &verification_token.token,
+        ))
+        .await
+    {
+        event!(target: "hellai_app_core_events", Level::ERROR, "Failed to send verification email to user {}: {}", user_id, err);
+    }
+
+    Ok(())
+}
+
+/// Issues a password reset token and sends the reset link.
+///
+/// A new token invalidates the earlier links of the user. A delivery failure is only logged, the
+/// caller must not learn whether the email is registered.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `mailer` - The mailer delivering the link.
+/// * `user` - The user who asked for the reset.
+async fn send_password_reset_email(
+    conn: &DbConn,
+    mailer: &dyn Mailer,
+    user: &users::Model,
+) -> Result<(), CoreErrors> {
+    let reset_token = OneTimeToken::password_reset();
+
+    PasswordResetTokensQuery::create_reset_token(
+        conn,
+        user.id,
+        reset_token.token_hash,
+        reset_token.expires_at,
+    )
+    .await?;
+
+    if let Err(err) = mailer
+        .send(password_reset_message(
+            user.email.clone(),
+            &reset_token.token,
+        ))
+        .await
+    {
+        event!(target: "hellai_app_core_events", Level::ERROR, "Failed to send password reset email to user {}: {}", user.id, err);
+    }
+
+    Ok(())
+}
+
+/// Adds a user to the companies that invited their email, once the user has proven they own it.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `user_id` - The ID of the user.
+/// * `email` - The confirmed email of the user.
+async fn join_invited_companies(
+    conn: &DbConn,
+    user_id: i32,
+    email: &str,
+) -> Result<(), CoreErrors> {
+    let memberships = CompanyInvitationsQuery::accept_all_invitations(conn, user_id, email).await?;
+
+    if !memberships.is_empty() {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "User {} joined {} companies they were invited to", user_id, memberships.len());
+    }
+
+    Ok(())
+}
+
+/// Builds the email containing the email verification link.
+///
+/// The link points to `EMAIL_VERIFICATION_URL` with the token appended as the `token` query parameter.
+fn email_verification_message(email: String, token: &str) -> MailMessage {
+    let verification_url = env::var("EMAIL_VERIFICATION_URL")
+        .unwrap_or("http://localhost:3000/verify-email".to_string());
+
+    MailMessage {
+        to: email,
+        subject: "Confirm your Hellai email".to_string(),
+        body: format!(
+            "Welcome to Hellai!\n\n\
+             Open the link below to confirm your email address:\n{}?token={}\n\n\
+             If you didn't create an account, you can ignore this email.\n",
+            verification_url, token
+        ),
+    }
+}
+
+/// Builds the email containing the password reset link.
+///
+/// The link points to `PASSWORD_RESET_URL` with the token appended as the `token` query parameter.
+fn password_reset_message(email: String, token: &str) -> MailMessage {
+    let reset_url = env::var("PASSWORD_RESET_URL")
+        .unwrap_or("http://localhost:3000/reset-password".to_string());
+
+    MailMessage {
+        to: email,
+        subject: "Reset your Hellai password".to_string(),
+        body: format!(
+            "Someone requested a password reset for your Hellai account.\n\n\
+             Open the link below to choose a new password:\n{}?token={}\n\n\
+             The link can be used once. If you didn't request it, you can ignore this email.\n",
+            reset_url, token
+        ),
+    }
+}
+
+/// Converts a public JWK into its gRPC representation.
+fn json_web_key(jwk: &Jwk) -> JsonWebKey {
+    let mut key = JsonWebKey {
+        kid: jwk.common.key_id.clone().unwrap_or_default(),
+        r#use: "sig".to_string(),
+        ..Default::default()
+    };
+
+    match &jwk.algorithm {
+        AlgorithmParameters::OctetKeyPair(params) => {
+            key.kty = "OKP".to_string();
+            key.alg = "EdDSA".to_string();
+            key.crv = Some("Ed25519".to_string());
+            key.x = Some(params.x.clone());
+        }
+        AlgorithmParameters::RSA(params) => {
+            key.kty = "RSA".to_string();
+            key.alg = "RS256".to_string();
+            key.n = Some(params.n.clone());
+            key.e = Some(params.e.clone());
+        }
+        // Only Ed25519 and RSA keys are loaded
+        _ => {}
+    }
+
+    key
+}
+
+/// Returns the configured external identity provider.
+///
+/// # Errors
+/// * Returns `Status::failed_precondition` if OpenID Connect login is not configured.
+#[allow(clippy::result_large_err)]
+fn oidc_provider(oidc: &Option<Arc<OidcProvider>>) -> Result<&OidcProvider, Status> {
+    oidc.as_deref()
+        .ok_or_else(|| Status::failed_precondition("oidc_not_configured"))
+}
+
+/// Links an external identity that signs in for the first time to a user.
+///
+/// A new user is created for the identity. If `OIDC_LINK_BY_EMAIL` is enabled, the identity is
+/// linked to the user registered with the same email instead, provided the identity provider
+/// verified it. New users get a random password and can set their own with a password reset.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `mailer` - The mailer delivering the verification link to new users with an unverified email.
+/// * `issuer` - The issuer of the identity provider.
+/// * `link_by_email` - Whether the identity may be linked to the user registered with its email.
+/// * `claims` - The claims of the validated ID token.
+///
+/// # Errors
+/// * Returns `Status::invalid_argument` if the identity provider reported no valid email.
+/// * Returns `Status::permission_denied` if the email belongs to a user and linking by email is
+///   disabled or the email was not verified by the identity provider.
+async fn link_oidc_identity(
+    conn: &DbConn,
+    mailer: &dyn Mailer,
+    issuer: &str,
+    link_by_email: bool,
+    claims: IdTokenClaims,
+) -> Result<users::Model, Status> {
+    let email = match claims.email.clone() {
+        Some(email) => validators::email_format_validation(email)?,
+        None => return Err(Status::invalid_argument("oidc_email_missing")),
+    };
+    let email_verified = claims.email_verified();
+
+    if let Some(user) = UserQuery::get_user_by_email(conn, &email).await? {
+        // Accounts are only taken over by an identity provider the operator trusts to do so
+        if !link_by_email {
+            return Err(Status::permission_denied("oidc_email_in_use"));
+        }
+
+        // Anyone can claim an unverified email, so it must not open an existing account
+        if !email_verified {
+            return Err(Status::permission_denied("oidc_email_not_verified"));
+        }
+
+        UserIdentitiesQuery::link_identity(
+            conn,
+            user.id,
+            issuer.to_string(),
+            claims.sub,
+            Some(email),
+        )
+        .await?;
+
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Linked identity of {} to user {}", issuer, user.id);
+
+        return Ok(user);
+    }
+
+    let login = available_login(conn, &claims, &email).await?;
+    let user_name = claims
+        .name
+        .clone()
+        .or_else(|| claims.preferred_username.clone())
+        .unwrap_or_else(|| login.clone());
+
+    // Nobody knows this password, the user signs in through the identity provider
+    let (password_hash, _) = hash_password(&Uuid::new_v4().to_string())?;
+
+    let new_user = UserIdentitiesQuery::create_user_with_identity(
+        conn,
+        login,
+        user_name,
+        email,
+        email_verified,
+        password_hash,
+        issuer.to_string(),
+        claims.sub,
+    )
+    .await?;
+
+    event!(target: "hellai_app_core_events", Level::DEBUG, "New user created on OIDC login: {:?}", new_user);
+
+    if email_verified {
+        join_invited_companies(conn, new_user.id, &new_user.email).await?;
+    } else {
+        send_verification_email(conn, mailer, new_user.id, &new_user.email).await?;
+    }
+
+    Ok(new_user)
+}
+
+/// Derives an unused login for a user created on their first OpenID Connect login.
+///
+/// The login is based on the preferred username or the local part of the email, with a random
+/// suffix if it is taken.
+async fn available_login(
+    conn: &DbConn,
+    claims: &IdTokenClaims,
+    email: &str,
+) -> Result<String, CoreErrors> {
+    let base_login: String = claims
+        .preferred_username
+        .as_deref()
+        .unwrap_or_else(|| email.split('@').next().unwrap_or_default())
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        .take(20)
+        .collect();
+
+    // Logins have at least three characters and are not only digits
+    let base_login = if base_login.len() < 3 || base_login.chars().all(|c| c.is_numeric()) {
+        format!("user{}", base_login)
+    } else {
+        base_login
+    };
+
+    let mut login = base_login.clone();
+    while UserQuery::login_exists(conn, &login).await? {
+        login = format!("{}{}", base_login, rand::thread_rng().gen_range(1..10000));
+    }
+
+    Ok(login)
+}
+
+/// Information about the client a session is created for.
+struct ClientInfo {
+    // User agent of the client
+    device_info: Option<String>,
+    // IP address of the client, as reported by a trusted proxy if there is one
+    ip_address: Option<String>,
+}
+
+impl ClientInfo {
+    fn from_request<T>(request: &Request<T>) -> ClientInfo {
+        let device_info = request
+            .metadata()
+            .get("user-agent")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        ClientInfo {
+            device_info,
+            ip_address: client_ip(request).map(|ip| ip.to_string()),
+        }
+    }
+}
+
+/// Checks the second factor of a login: a TOTP code or one of the user's recovery codes.
+///
+/// Both kinds of codes are accepted only once.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `totp` - The confirmed TOTP enrolment of the user.
+/// * `code` - The code entered by the user.
+///
+/// # Returns
+/// * `Result<bool, CoreErrors>` - Returns `true` if the code is valid and was not used before.
+async fn verify_second_factor(
+    conn: &DbConn,
+    totp: &user_totp::Model,
+    code: &str,
+) -> Result<bool, CoreErrors> {
+    let code = code.trim();
+
+    // TOTP codes are six digits, anything else can only be a recovery code
+    if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+        return match verify_totp_code(&totp.secret, code, totp.last_used_step)? {
+            Some(step) => TotpQuery::record_used_step(conn, totp.user_id, step).await,
+            None => Ok(false),
+        };
+    }
+
+    let code_hash = hash_token(&normalize_recovery_code(code));
+    let consumed = TotpQuery::consume_recovery_code(conn, totp.user_id, &code_hash).await?;
+
+    if consumed {
+        event!(target: "hellai_app_core_events", Level::DEBUG, "Recovery code used by user {}", totp.user_id);
+    }
+
+    Ok(consumed)
+}
+
+/// Starts a session for a user who passed every login step and gathers the data returned on login.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `user` - The user who logged in.
+/// * `client_info` - Information about the client.
+///
+/// # Returns
+/// * `Result<AuthUserCompanyProjectsInfoResponse, CoreErrors>` - Returns the tokens, user details, and one company with its projects.
+async fn login_response(
+    conn: &DbConn,
+    user: users::Model,
+    client_info: ClientInfo,
+) -> Result<AuthUserCompanyProjectsInfoResponse, CoreErrors> {
+    // Start a new session and generate its session and refresh tokens
+    let (session_token, refresh_token) = start_session(conn, user.id, client_info).await?;
+
+    // Fetch user's associated company and project information
+    let user_company_with_projects =
+        CompaniesQuery::get_company_with_projects(conn, user.id, None).await?;
+
+    // Format response structure for company and projects, if available
+    let (company_info, projects) = match user_company_with_projects {
+        Some(company) => (
+            Some(CompanyInfoResponse {
+                id: company.id,
+                name: company.name,
+                name_alias: company.name_alias,
+                description: company.description,
+                contact_info: company.contact_info,
+            }),
+            company
+                .company_projects
+                .into_iter()
+                .map(|project| ProjectsResponse {
+                    id: project.id,
+                    company_id: project.company_id,
+                    title: project.title,
+                    description: project.description,
+                    decoration_color: project.decoration_color,
+                    archived_at: None,
+                })
+                .collect(),
+        ),
+        None => (None, vec![]), // Empty vector if no projects are found
+    };
+
+    // Construct response with user and company/project details
+    Ok(AuthUserCompanyProjectsInfoResponse {
+        user_id: user.id,
+        email: user.email,
+        user_name: user.user_name,
+        login: user.login,
+        email_verified: user.email_verified_at.is_some(),
+        session_token,
+        refresh_token,
+        company: company_info,
+        user_projects: projects,
+        two_factor_required: false,
+        two_factor_challenge_token: String::new(),
+    })
+}
+
+/// Records a new login session and issues its session and refresh tokens.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `user_id` - The ID of the user who logged in.
+/// * `client_info` - Information about the client.
+///
+/// # Returns
+/// * `Result<(String, String), CoreErrors>` - Returns the session token and the refresh token.
+async fn start_session(
+    conn: &DbConn,
+    user_id: i32,
+    client_info: ClientInfo,
+) -> Result<(String, String), CoreErrors> {
+    let session_id = Uuid::new_v4().to_string();
+    let refresh_claims = RefreshClaims::new(user_id as i64);
+
+    SessionsQuery::create_session(
+        conn,
+        session_id.clone(),
+        user_id,
+        client_info.device_info.clone(),
+        client_info.ip_address,
+        refresh_claims.expires_at(),
+    )
+    .await?;
+
+    let (refresh_token, _) = store_refresh_token(
+        conn,
+        refresh_claims,
+        session_id.clone(),
+        client_info.device_info,
+    )
+    .await?;
+
+    let session_token = SessionClaims::new(user_id as i64, session_id).into_token()?;
+
+    Ok((session_token, refresh_token))
+}
+
+/// Signs a refresh token and stores its hash.
+///
+/// # Arguments
+/// * `conn` - A reference to the database connection.
+/// * `refresh_claims` - The claims of the token.
+/// * `session_id` - The session the token is issued for; it also identifies the token family.
+/// * `device_info` - A description of the client, if known.
+///
+/// # Returns
+/// * `Result<(String, String), CoreErrors>` - Returns the signed token and its unique ID.
+async fn store_refresh_token(
+    conn: &DbConn,
+    refresh_claims: RefreshClaims,
+    session_id: String,
+    device_info: Option<String>,
+) -> Result<(String, String), CoreErrors> {
+    let user_id = refresh_claims.sub as i32;
+    let jti = refresh_claims.jti.clone();
+    let expires_at = refresh_claims.expires_at();
+    let refresh_token = refresh_claims.into_token()?;
+
+    RefreshTokensQuery::create_refresh_token(
+        conn,
+        user_id,
+        jti.clone(),
+        session_id,
+        hash_token(&refresh_token),
+        device_info,
+        expires_at,
+    )
+    .await?;
+
+    Ok((refresh_token, jti))
+}
+
+/// Loads the stored record of a decoded refresh token.
+///
+/// # Errors
+/// * Returns `Status::unauthenticated` if the token was never issued by us or does not match the record.
+async fn load_refresh_token(
+    conn: &DbConn,
+    refresh_token: &str,
+    refresh_claims: &RefreshClaims,
+) -> Result<refresh_tokens::Model, Status> {
+    match RefreshTokensQuery::get_refresh_token_by_jti(conn, &refresh_claims.jti).await? {
+        Some(token)
+            if token.token_hash == hash_token(refresh_token)
+                && token.user_id as i64 == refresh_claims.sub =>
+        {
+            Ok(token)
+        }
+        _ => Err(Status::unauthenticated("invalid_token")),
+    }
+}
+
+/// Revokes a session of a user together with its refresh tokens.
+///
+/// # Returns
+/// * `Result<bool, CoreErrors>` - Returns `false` if the user has no active session with this ID.
+async fn revoke_session(conn: &DbConn, session_id: &str, user_id: i32) -> Result<bool, CoreErrors> {
+    let revoked = SessionsQuery::revoke_session(conn, session_id, user_id).await?;
+    RefreshTokensQuery::revoke_token_family(conn, session_id).await?;
+
+    Ok(revoked)
+}
+
+/// Signs out a session after one of its refresh tokens was reused and returns the error to send back.
+async fn revoke_reused_session(conn: &DbConn, token: &refresh_tokens::Model) -> Status {
+    event!(
+        target: "hellai_app_core_events",
+        Level::WARN,
+        "Refresh token reuse detected, revoking session {}",
+        token.family_id
+    );
+
+    match revoke_session(conn, &token.family_id, token.user_id).await {
+        Ok(_) => Status::unauthenticated("refresh_token_reused"),
+        Err(err) => err.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core_database::{
+        entity::password_reset_tokens,
+        test_support::{affected, executed_sql},
+    };
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, Value};
+    use service::mailer::InMemoryMailer;
+    use tonic::Code;
+
+    use super::*;
+
+    fn user() -> users::Model {
+        users::Model {
+            id: 7,
+            login: "user".to_string(),
+            user_name: "User".to_string(),
+            email: "user@example.com".to_string(),
+            is_active: true,
+            email_verified_at: None,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        }
+    }
+
+    /// A database that accepts one password reset token.
+    fn reset_token_database() -> DatabaseConnection {
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(1)])
+            .append_query_results([vec![password_reset_tokens::Model {
+                id: 1,
+                user_id: 7,
+                token_hash: "hash".to_string(),
+                expires_at: Default::default(),
+                used_at: None,
+                created_at: Default::default(),
+            }]])
+            .into_connection()
+    }
+
+    /// Returns the token of the reset link in an email body.
+    fn link_token(body: &str) -> String {
+        body.split("?token=")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("the email contains no reset link")
+            .to_string()
+    }
+
+    /// Returns the values bound to the statement inserting the reset token.
+    fn inserted_token_values(db: DatabaseConnection) -> Vec<Value> {
+        db.into_transaction_log()
+            .iter()
+            .flat_map(|transaction| transaction.statements().iter())
+            .find(|statement| {
+                statement.sql.starts_with("INSERT")
+                    && statement.sql.contains("password_reset_tokens")
+            })
+            .and_then(|statement| statement.values.clone())
+            .expect("no reset token was stored")
+            .0
+    }
+
+    #[tokio::test]
+    async fn password_reset_emails_the_token_whose_hash_is_stored() {
+        let db = reset_token_database();
+        let mailer = InMemoryMailer::default();
+
+        send_password_reset_email(&db, &mailer, &user())
+            .await
+            .unwrap();
+
+        let messages = mailer.sent_messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].to, "user@example.com");
+
+        // The emailed token redeems the stored hash, while the token itself never reaches the database
+        let token = link_token(&messages[0].body);
+        let values = inserted_token_values(db);
+        assert!(values.contains(&Value::from(hash_token(&token))));
+        assert!(!values.contains(&Value::from(token)));
+    }
+
+    #[tokio::test]
+    async fn every_password_reset_sends_a_new_token() {
+        let mailer = InMemoryMailer::default();
+
+        send_password_reset_email(&reset_token_database(), &mailer, &user())
+            .await
+            .unwrap();
+        send_password_reset_email(&reset_token_database(), &mailer, &user())
+            .await
+            .unwrap();
+
+        let messages = mailer.sent_messages();
+        assert_eq!(messages.len(), 2);
+        assert_ne!(link_token(&messages[0].body), link_token(&messages[1].body));
+    }
+
+    fn stored_refresh_token(token: &str) -> refresh_tokens::Model {
+        refresh_tokens::Model {
+            id: 1,
+            user_id: 7,
+            jti: "jti".to_string(),
+            family_id: "session".to_string(),
+            token_hash: hash_token(token),
+            device_info: None,
+            expires_at: Default::default(),
+            created_at: Default::default(),
+            revoked_at: None,
+            replaced_by: None,
+        }
+    }
+
+    fn refresh_token_database(stored_token: Option<refresh_tokens::Model>) -> DatabaseConnection {
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([stored_token.into_iter().collect::<Vec<_>>()])
+            .into_connection()
+    }
+
+    #[tokio::test]
+    async fn refresh_tokens_load_by_id_and_hash() {
+        let db = refresh_token_database(Some(stored_refresh_token("refresh-token")));
+
+        let stored_token = load_refresh_token(&db, "refresh-token", &RefreshClaims::new(7))
+            .await
+            .unwrap();
+
+        assert_eq!(stored_token.family_id, "session");
+    }
+
+    #[tokio::test]
+    async fn refresh_tokens_with_another_hash_or_user_are_rejected() {
+        let stored_token = stored_refresh_token("refresh-token");
+
+        let cases = [
+            (Some(stored_token.clone()), "forged-token", 7),
+            (Some(stored_token), "refresh-token", 8),
+            (None, "refresh-token", 7),
+        ];
+
+        for (stored_token, presented_token, user_id) in cases {
+            let db = refresh_token_database(stored_token);
+
+            let status = load_refresh_token(&db, presented_token, &RefreshClaims::new(user_id))
+                .await
+                .unwrap_err();
+
+            assert_eq!(status.code(), Code::Unauthenticated);
+            assert_eq!(status.message(), "invalid_token");
+        }
+    }
+
+    #[tokio::test]
+    async fn reused_refresh_tokens_sign_out_the_session() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(1), affected(3)])
+            .into_connection();
+
+        let status = revoke_reused_session(&db, &stored_refresh_token("refresh-token")).await;
+
+        assert_eq!(status.code(), Code::Unauthenticated);
+        assert_eq!(status.message(), "refresh_token_reused");
+
+        // The session and every token rotated from it are revoked
+        let sql = executed_sql(db);
+        assert_eq!(sql.len(), 2);
+        assert!(sql[0].contains(r#"UPDATE "sessions""#));
+        assert!(sql[1].contains(r#"UPDATE "refresh_tokens""#));
+    }
 }
@@ -12,10 +12,15 @@ pub struct Model {
     pub name_alias: String,
     pub description: Option<String>,
     pub contact_info: Option<String>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::company_alias_redirects::Entity")]
+    CompanyAliasRedirects,
+    #[sea_orm(has_many = "super::company_invitations::Entity")]
+    CompanyInvitations,
     #[sea_orm(has_many = "super::knowledge_base::Entity")]
     KnowledgeBase,
     #[sea_orm(has_many = "super::notes::Entity")]
@@ -28,6 +33,18 @@ pub enum Relation {
     UserCompany,
 }
 
+impl Related<super::company_alias_redirects::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CompanyAliasRedirects.def()
+    }
+}
+
+impl Related<super::company_invitations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CompanyInvitations.def()
+    }
+}
+
 impl Related<super::knowledge_base::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::KnowledgeBase.def()
@@ -3,14 +3,28 @@
 pub mod prelude;
 
 pub mod companies;
+pub mod company_alias_redirects;
+pub mod company_invitations;
+pub mod email_verification_tokens;
 pub mod knowledge_base;
+pub mod login_attempts;
+pub mod login_throttles;
 pub mod notes;
+pub mod oidc_login_requests;
+pub mod password_reset_tokens;
 pub mod passwords;
+pub mod personal_access_tokens;
+pub mod platform_admins;
 pub mod projects;
+pub mod refresh_tokens;
 pub mod roles;
 pub mod sea_orm_active_enums;
+pub mod sessions;
 pub mod subtasks;
 pub mod tasks;
+pub mod totp_recovery_codes;
 pub mod user_access;
 pub mod user_company;
+pub mod user_identities;
+pub mod user_totp;
 pub mod users;
@@ -16,6 +16,7 @@ pub struct Model {
     pub tags: Option<String>,
     pub decoration_color: Option<String>,
     pub created_at: DateTimeWithTimeZone,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,13 +1,27 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.0-rc.5
 
 pub use super::companies::Entity as Companies;
+pub use super::company_alias_redirects::Entity as CompanyAliasRedirects;
+pub use super::company_invitations::Entity as CompanyInvitations;
+pub use super::email_verification_tokens::Entity as EmailVerificationTokens;
 pub use super::knowledge_base::Entity as KnowledgeBase;
+pub use super::login_attempts::Entity as LoginAttempts;
+pub use super::login_throttles::Entity as LoginThrottles;
 pub use super::notes::Entity as Notes;
+pub use super::oidc_login_requests::Entity as OidcLoginRequests;
+pub use super::password_reset_tokens::Entity as PasswordResetTokens;
 pub use super::passwords::Entity as Passwords;
+pub use super::personal_access_tokens::Entity as PersonalAccessTokens;
+pub use super::platform_admins::Entity as PlatformAdmins;
 pub use super::projects::Entity as Projects;
+pub use super::refresh_tokens::Entity as RefreshTokens;
 pub use super::roles::Entity as Roles;
+pub use super::sessions::Entity as Sessions;
 pub use super::subtasks::Entity as Subtasks;
 pub use super::tasks::Entity as Tasks;
+pub use super::totp_recovery_codes::Entity as TotpRecoveryCodes;
 pub use super::user_access::Entity as UserAccess;
 pub use super::user_company::Entity as UserCompany;
+pub use super::user_identities::Entity as UserIdentities;
+pub use super::user_totp::Entity as UserTotp;
 pub use super::users::Entity as Users;
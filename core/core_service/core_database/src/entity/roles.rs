@@ -16,6 +16,8 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::company_invitations::Entity")]
+    CompanyInvitations,
     #[sea_orm(has_many = "super::knowledge_base::Entity")]
     KnowledgeBase,
     #[sea_orm(
@@ -32,6 +34,12 @@ pub enum Relation {
     UserCompany,
 }
 
+impl Related<super::company_invitations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CompanyInvitations.def()
+    }
+}
+
 impl Related<super::knowledge_base::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::KnowledgeBase.def()
@@ -16,6 +16,7 @@ pub struct Model {
     pub priority: Option<String>,
     pub created_at: DateTimeWithTimeZone,
     pub due_date: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
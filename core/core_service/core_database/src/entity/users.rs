@@ -13,24 +13,65 @@ pub struct Model {
     #[sea_orm(unique)]
     pub email: String,
     pub is_active: bool,
+    pub email_verified_at: Option<DateTimeWithTimeZone>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::company_invitations::Entity")]
+    CompanyInvitations,
+    #[sea_orm(has_many = "super::email_verification_tokens::Entity")]
+    EmailVerificationTokens,
+    #[sea_orm(has_many = "super::login_attempts::Entity")]
+    LoginAttempts,
     #[sea_orm(has_many = "super::notes::Entity")]
     Notes,
+    #[sea_orm(has_many = "super::password_reset_tokens::Entity")]
+    PasswordResetTokens,
     #[sea_orm(has_many = "super::passwords::Entity")]
     Passwords,
+    #[sea_orm(has_many = "super::personal_access_tokens::Entity")]
+    PersonalAccessTokens,
+    #[sea_orm(has_one = "super::platform_admins::Entity")]
+    PlatformAdmins,
+    #[sea_orm(has_many = "super::refresh_tokens::Entity")]
+    RefreshTokens,
+    #[sea_orm(has_many = "super::sessions::Entity")]
+    Sessions,
     #[sea_orm(has_many = "super::subtasks::Entity")]
     Subtasks,
     #[sea_orm(has_many = "super::tasks::Entity")]
     Tasks,
+    #[sea_orm(has_many = "super::totp_recovery_codes::Entity")]
+    TotpRecoveryCodes,
     #[sea_orm(has_many = "super::user_access::Entity")]
     UserAccess,
     #[sea_orm(has_many = "super::user_company::Entity")]
     UserCompany,
+    #[sea_orm(has_many = "super::user_identities::Entity")]
+    UserIdentities,
+    #[sea_orm(has_one = "super::user_totp::Entity")]
+    UserTotp,
+}
+
+impl Related<super::company_invitations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CompanyInvitations.def()
+    }
+}
+
+impl Related<super::email_verification_tokens::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::EmailVerificationTokens.def()
+    }
+}
+
+impl Related<super::login_attempts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LoginAttempts.def()
+    }
 }
 
 impl Related<super::notes::Entity> for Entity {
@@ -39,12 +80,42 @@ impl Related<super::notes::Entity> for Entity {
     }
 }
 
+impl Related<super::password_reset_tokens::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PasswordResetTokens.def()
+    }
+}
+
 impl Related<super::passwords::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Passwords.def()
     }
 }
 
+impl Related<super::personal_access_tokens::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PersonalAccessTokens.def()
+    }
+}
+
+impl Related<super::platform_admins::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PlatformAdmins.def()
+    }
+}
+
+impl Related<super::refresh_tokens::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RefreshTokens.def()
+    }
+}
+
+impl Related<super::sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Sessions.def()
+    }
+}
+
 impl Related<super::subtasks::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Subtasks.def()
@@ -57,6 +128,12 @@ impl Related<super::tasks::Entity> for Entity {
     }
 }
 
+impl Related<super::totp_recovery_codes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TotpRecoveryCodes.def()
+    }
+}
+
 impl Related<super::user_access::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::UserAccess.def()
@@ -69,4 +146,16 @@ impl Related<super::user_company::Entity> for Entity {
     }
 }
 
+impl Related<super::user_identities::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserIdentities.def()
+    }
+}
+
+impl Related<super::user_totp::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserTotp.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
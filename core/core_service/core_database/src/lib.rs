@@ -1,2 +1,5 @@
 pub mod entity;
 pub mod queries;
+/// Helpers for tests running against a `MockDatabase`.
+#[cfg(any(test, feature = "mock"))]
+pub mod test_support;
@@ -0,0 +1,162 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait,
+    DbBackend, DbConn, EntityTrait, QueryFilter, QueryOrder, Set, Statement,
+};
+
+use crate::entity::{personal_access_tokens, prelude};
+
+/// The owner and permissions of an active personal access token.
+#[derive(Clone, Debug)]
+pub struct AccessTokenUser {
+    /// The ID of the token.
+    pub token_id: i32,
+    /// The ID of the user owning the token.
+    pub user_id: i32,
+    /// The space separated scopes of the token.
+    pub scopes: String,
+    /// Whether the user has verified their email address.
+    pub email_verified: bool,
+    /// Whether the account is active; deactivated users must be rejected.
+    pub is_active: bool,
+}
+
+/// Provides methods for issuing, listing and revoking personal access tokens.
+pub struct AccessTokensQuery;
+
+impl AccessTokensQuery {
+    /// Stores a new personal access token.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user the token acts for.
+    /// * `name` - A name the user recognises the token by.
+    /// * `token_hash` - The hash of the token; the token itself is never stored.
+    /// * `token_prefix` - The first characters of the token, shown when listing tokens.
+    /// * `scopes` - The space separated scopes of the token.
+    /// * `expires_at` - The moment the token expires, or `None` if it doesn't.
+    ///
+    /// # Returns
+    /// * `Result<personal_access_tokens::Model, CoreErrors>` - Returns the stored token record.
+    pub async fn create_access_token(
+        db: &DbConn,
+        user_id: i32,
+        name: String,
+        token_hash: String,
+        token_prefix: String,
+        scopes: String,
+        expires_at: Option<DateTimeWithTimeZone>,
+    ) -> Result<personal_access_tokens::Model, CoreErrors> {
+        let new_token = personal_access_tokens::ActiveModel {
+            user_id: Set(user_id),
+            name: Set(name),
+            token_hash: Set(token_hash),
+            token_prefix: Set(token_prefix),
+            scopes: Set(scopes),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        };
+
+        let token = new_token.insert(db).await?;
+
+        Ok(token)
+    }
+
+    /// Retrieves the personal access tokens of a user that were not revoked, newest first.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// * `Result<Vec<personal_access_tokens::Model>, CoreErrors>` - Returns the tokens, including expired ones.
+    pub async fn get_user_access_tokens(
+        db: &DbConn,
+        user_id: i32,
+    ) -> Result<Vec<personal_access_tokens::Model>, CoreErrors> {
+        let tokens = prelude::PersonalAccessTokens::find()
+            .filter(personal_access_tokens::Column::UserId.eq(user_id))
+            .filter(personal_access_tokens::Column::RevokedAt.is_null())
+            .order_by_desc(personal_access_tokens::Column::CreatedAt)
+            .all(db)
+            .await?;
+
+        Ok(tokens)
+    }
+
+    /// Revokes a personal access token of a user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user owning the token.
+    /// * `token_id` - The ID of the token.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the user has no unrevoked token with this ID.
+    pub async fn revoke_access_token(
+        db: &DbConn,
+        user_id: i32,
+        token_id: i32,
+    ) -> Result<bool, CoreErrors> {
+        let result = prelude::PersonalAccessTokens::update_many()
+            .col_expr(
+                personal_access_tokens::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(personal_access_tokens::Column::Id.eq(token_id))
+            .filter(personal_access_tokens::Column::UserId.eq(user_id))
+            .filter(personal_access_tokens::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Marks an active personal access token as used and returns its owner and scopes.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `token_hash` - The hash of the presented token.
+    ///
+    /// # Returns
+    /// * `Result<Option<AccessTokenUser>, CoreErrors>` - Returns the token's owner and scopes, or `None` if the token does not exist, has expired or was revoked.
+    pub async fn touch_access_token(
+        db: &DbConn,
+        token_hash: &str,
+    ) -> Result<Option<AccessTokenUser>, CoreErrors> {
+        // Update the token and read its user in one round trip, since this runs on every request
+        let sql = r#"
+        UPDATE personal_access_tokens
+        SET last_used_at = CURRENT_TIMESTAMP
+        FROM users
+        WHERE personal_access_tokens.token_hash = $1
+            AND users.id = personal_access_tokens.user_id
+            AND personal_access_tokens.revoked_at IS NULL
+            AND (
+                personal_access_tokens.expires_at IS NULL
+                OR personal_access_tokens.expires_at > CURRENT_TIMESTAMP
+            )
+        RETURNING
+            personal_access_tokens.id,
+            personal_access_tokens.user_id,
+            personal_access_tokens.scopes,
+            users.email_verified_at IS NOT NULL AS email_verified,
+            users.is_active
+        "#;
+
+        let stmt = Statement::from_sql_and_values(DbBackend::Postgres, sql, [token_hash.into()]);
+
+        let token_user = match db.query_one(stmt).await? {
+            Some(row) => Some(AccessTokenUser {
+                token_id: row.try_get("", "id")?,
+                user_id: row.try_get("", "user_id")?,
+                scopes: row.try_get("", "scopes")?,
+                email_verified: row.try_get("", "email_verified")?,
+                is_active: row.try_get("", "is_active")?,
+            }),
+            None => None,
+        };
+
+        Ok(token_user)
+    }
+}
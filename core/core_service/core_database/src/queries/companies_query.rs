@@ -2,10 +2,49 @@ use core_error::core_errors::CoreErrors;
 use rand::Rng;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend, DbBackend, DbConn,
-    DeleteResult, EntityTrait, FromQueryResult, IntoActiveModel, QueryFilter, Set, Statement,
+    EntityTrait, FromQueryResult, IntoActiveModel, QueryFilter, QuerySelect, Set, Statement,
 };
 
-use crate::entity::{companies, sea_orm_active_enums::AccessLevelType, user_company};
+use crate::{
+    entity::{
+        companies, company_alias_redirects, sea_orm_active_enums::AccessLevelType, user_company,
+    },
+    queries::roles_query::{role_not_above, OWNER_LEVEL},
+};
+
+/// Aliases no company may use, since they clash with routes of the web application.
+pub const RESERVED_COMPANY_ALIASES: &[&str] = &[
+    "admin",
+    "api",
+    "app",
+    "auth",
+    "companies",
+    "company",
+    "dashboard",
+    "help",
+    "invite",
+    "login",
+    "logout",
+    "new",
+    "register",
+    "root",
+    "settings",
+    "static",
+    "support",
+    "system",
+    "www",
+];
+
+/// The outcome of `CompaniesQuery::change_company_alias`.
+#[derive(Debug)]
+pub enum AliasChange {
+    /// The alias was changed; holds the updated company.
+    Changed(companies::Model),
+    /// The alias is reserved, used by another company or kept as a former alias of another company.
+    Unavailable,
+    /// The company does not exist.
+    CompanyNotFound,
+}
 
 /// User Company with projects
 pub struct UserCompany {
@@ -33,6 +72,18 @@ pub struct UserRole {
     pub level: i32,
 }
 
+/// A member of a company together with their role and access level.
+#[derive(Debug, FromQueryResult)]
+pub struct CompanyMember {
+    pub user_id: i32,
+    pub login: String,
+    pub user_name: String,
+    pub email: String,
+    pub is_active: bool,
+    pub role_id: i32,
+    pub access_level: AccessLevelType,
+}
+
 #[derive(Debug, FromQueryResult)]
 struct UserCompanyQueryResult {
     // Fields from companies
@@ -72,6 +123,7 @@ impl CompaniesQuery {
     /// An `Option<UserCompany>` structure containing information about the company, the user's role in that company,
     /// and a list of projects within the company where the user has access. If the user has access to the company,
     /// they automatically have access to all its projects. Returns `None` if no company is found for the user.
+    /// Archived projects as well as deleted companies and projects are left out.
     ///
     /// Returns an error of type `CoreErrors` if the query fails.
     pub async fn get_company_with_projects(
@@ -101,9 +153,12 @@ impl CompaniesQuery {
                 INNER JOIN user_company uc ON uc.company_id = c.id
                 INNER JOIN roles r ON r.id = uc.role_id
                 LEFT JOIN projects p ON p.company_id = c.id
+                    AND p.archived_at IS NULL
+                    AND p.deleted_at IS NULL
             WHERE
                 uc.user_id = $1
                 AND c.id = $2
+                AND c.deleted_at IS NULL
             "#
         } else {
             // Fetch only the first company without project details if `company_id` is not provided.
@@ -126,8 +181,11 @@ impl CompaniesQuery {
                 INNER JOIN user_company uc ON uc.company_id = c.id
                 LEFT JOIN roles r ON r.id = uc.role_id
                 LEFT JOIN projects p ON p.company_id = c.id
+                    AND p.archived_at IS NULL
+                    AND p.deleted_at IS NULL
             WHERE
             uc.user_id = $1
+            AND c.deleted_at IS NULL
             LIMIT 1;
             "#
         };
@@ -220,11 +278,10 @@ impl CompaniesQuery {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```ignore
     /// let company = create_new_company(&db, user_id, "Tech Corp", "An innovative tech company.", "contact@techcorp.com").await?;
     /// println!("New company created with ID: {}", company.id);
     /// ```
-
     pub async fn create_new_company(
         db: &DbConn,
         user_id: i32,
@@ -246,23 +303,11 @@ impl CompaniesQuery {
                 )
             };
 
-            // Step 1: Check if `name_alias` already exists in the `companies` table.
-            let check_alias_sql =
-                "SELECT EXISTS (SELECT 1 FROM companies WHERE name_alias = $1) AS alias_exists;";
-
-            let check_alias_stmt = Statement::from_sql_and_values(
-                DatabaseBackend::Postgres,
-                check_alias_sql,
-                vec![name_alias.clone().into()], // $1 - Company alias to check
-            );
-
-            if let Some(alias_check_result) = db.query_one(check_alias_stmt).await? {
-                let alias_exists: bool = alias_check_result.try_get("", "alias_exists")?;
-                if alias_exists {
-                    // If alias exists, retry with a new alias
-                    attempts += 1;
-                    continue;
-                }
+            // Step 1: Check if `name_alias` is reserved or already used by another company.
+            if !Self::is_alias_available(db, &name_alias, None).await? {
+                // If alias is not available, retry with a new alias
+                attempts += 1;
+                continue;
             }
 
             // Step 2: Insert the new company and assign the user to the company in `UserCompany` table.
@@ -304,6 +349,7 @@ impl CompaniesQuery {
                         name_alias: row.try_get("", "name_alias")?,
                         description: row.try_get("", "description")?,
                         contact_info: row.try_get("", "contact_info")?,
+                        deleted_at: None,
                     };
                     return Ok(company);
                 }
@@ -318,11 +364,223 @@ impl CompaniesQuery {
         }
     }
 
+    /// Checks whether a company may use an alias.
+    ///
+    /// An alias is unavailable if it is reserved, used by another company or kept as a former alias
+    /// of another company.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `name_alias` - The alias to check.
+    /// * `company_id` - The company that wants the alias, or `None` for a new company.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `true` if the alias is available.
+    pub async fn is_alias_available(
+        db: &impl ConnectionTrait,
+        name_alias: &str,
+        company_id: Option<i32>,
+    ) -> Result<bool, CoreErrors> {
+        if RESERVED_COMPANY_ALIASES.contains(&name_alias) {
+            return Ok(false);
+        }
+
+        let sql = r#"
+            SELECT EXISTS (
+                SELECT 1 FROM companies
+                WHERE name_alias = $1 AND id IS DISTINCT FROM $2
+            ) OR EXISTS (
+                SELECT 1 FROM company_alias_redirects
+                WHERE name_alias = $1 AND company_id IS DISTINCT FROM $2
+            ) AS alias_exists;
+        "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                name_alias.into(), // $1 - Company alias to check
+                company_id.into(), // $2 - Company that wants the alias
+            ],
+        );
+
+        let alias_exists = match db.query_one(stmt).await? {
+            Some(row) => row.try_get::<bool>("", "alias_exists")?,
+            None => false,
+        };
+
+        Ok(!alias_exists)
+    }
+
+    /// Updates the name, description and contact information of a company.
+    ///
+    /// Fields that are `None` are left unchanged; `Some(None)` clears the description or contact information.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `company_id` - The ID of the company.
+    /// * `name` - The new name of the company.
+    /// * `description` - The new description of the company.
+    /// * `contact_info` - The new contact information of the company.
+    ///
+    /// # Returns
+    /// * `Result<Option<companies::Model>, CoreErrors>` - Returns the updated company, or `None` if it does not exist.
+    pub async fn update_company(
+        db: &DbConn,
+        company_id: i32,
+        name: Option<String>,
+        description: Option<Option<String>>,
+        contact_info: Option<Option<String>>,
+    ) -> Result<Option<companies::Model>, CoreErrors> {
+        let Some(company) = companies::Entity::find_by_id(company_id)
+            .filter(companies::Column::DeletedAt.is_null())
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let mut company = company.into_active_model();
+
+        if let Some(name) = name {
+            company.name = Set(name);
+        }
+        if let Some(description) = description {
+            company.description = Set(description);
+        }
+        if let Some(contact_info) = contact_info {
+            company.contact_info = Set(contact_info);
+        }
+
+        let company = company.update(db).await?;
+
+        Ok(Some(company))
+    }
+
+    /// Changes the alias of a company and keeps the former alias as a redirect to the company.
+    ///
+    /// If the company takes back one of its former aliases, the redirect of that alias is removed.
+    /// No constraint spans current and former aliases, so the alias is locked for the rest of the
+    /// transaction before its availability is checked. Run it on a transaction, so the company row
+    /// and the alias stay locked until the change is committed.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `company_id` - The ID of the company.
+    /// * `name_alias` - The new alias of the company.
+    ///
+    /// # Returns
+    /// * `Result<AliasChange, CoreErrors>` - Returns the updated company, or why the alias wasn't changed.
+    pub async fn change_company_alias(
+        db: &impl ConnectionTrait,
+        company_id: i32,
+        name_alias: String,
+    ) -> Result<AliasChange, CoreErrors> {
+        let Some(company) = companies::Entity::find_by_id(company_id)
+            .filter(companies::Column::DeletedAt.is_null())
+            .lock_exclusive()
+            .one(db)
+            .await?
+        else {
+            return Ok(AliasChange::CompanyNotFound);
+        };
+
+        if company.name_alias == name_alias {
+            return Ok(AliasChange::Changed(company));
+        }
+
+        // Concurrent changes to the same alias wait here until this transaction ends
+        let lock_stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT pg_advisory_xact_lock(hashtext($1));",
+            [name_alias.as_str().into()],
+        );
+        db.execute(lock_stmt).await?;
+
+        if !Self::is_alias_available(db, &name_alias, Some(company_id)).await? {
+            return Ok(AliasChange::Unavailable);
+        }
+
+        company_alias_redirects::Entity::delete_many()
+            .filter(company_alias_redirects::Column::CompanyId.eq(company_id))
+            .filter(company_alias_redirects::Column::NameAlias.eq(name_alias.as_str()))
+            .exec(db)
+            .await?;
+
+        company_alias_redirects::ActiveModel {
+            company_id: Set(company_id),
+            name_alias: Set(company.name_alias.clone()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        let mut company = company.into_active_model();
+        company.name_alias = Set(name_alias);
+        let company = company.update(db).await?;
+
+        Ok(AliasChange::Changed(company))
+    }
+
+    /// Retrieves a company by its current or a former alias.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `name_alias` - The alias of the company.
+    ///
+    /// # Returns
+    /// * `Result<Option<(companies::Model, bool)>, CoreErrors>` - Returns the company and whether the alias
+    ///   is a former one, or `None` if no company uses the alias.
+    pub async fn get_company_by_alias(
+        db: &DbConn,
+        name_alias: &str,
+    ) -> Result<Option<(companies::Model, bool)>, CoreErrors> {
+        let company = companies::Entity::find()
+            .filter(companies::Column::NameAlias.eq(name_alias))
+            .filter(companies::Column::DeletedAt.is_null())
+            .one(db)
+            .await?;
+
+        if let Some(company) = company {
+            return Ok(Some((company, false)));
+        }
+
+        let company = companies::Entity::find()
+            .inner_join(company_alias_redirects::Entity)
+            .filter(company_alias_redirects::Column::NameAlias.eq(name_alias))
+            .filter(companies::Column::DeletedAt.is_null())
+            .one(db)
+            .await?;
+
+        Ok(company.map(|company| (company, true)))
+    }
+
+    /// Retrieves a company by its ID.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `company_id` - The ID of the company.
+    ///
+    /// # Returns
+    /// * `Result<Option<companies::Model>, CoreErrors>` - Returns the company, or `None` if it does not exist
+    ///   or is in the trash.
+    pub async fn get_company_by_id(
+        db: &DbConn,
+        company_id: i32,
+    ) -> Result<Option<companies::Model>, CoreErrors> {
+        let company = companies::Entity::find_by_id(company_id)
+            .filter(companies::Column::DeletedAt.is_null())
+            .one(db)
+            .await?;
+
+        Ok(company)
+    }
+
     /// Retrieves a specific user's association with a company based on user and company IDs.
     ///
     /// This function checks if a given user is associated with a specific company by querying
     /// the `user_company` table. If the user-company relationship exists, it returns the `user_company`
-    /// model; otherwise, it returns `None`.
+    /// model; otherwise, it returns `None`. Memberships of companies in the trash are not returned.
     ///
     /// # Arguments
     ///
@@ -341,8 +599,10 @@ impl CompaniesQuery {
         company_id: i32,
     ) -> Result<Option<user_company::Model>, CoreErrors> {
         let company: Option<user_company::Model> = user_company::Entity::find()
+            .inner_join(companies::Entity)
             .filter(user_company::Column::UserId.eq(user_id))
             .filter(user_company::Column::CompanyId.eq(company_id))
+            .filter(companies::Column::DeletedAt.is_null())
             .one(db)
             .await?;
 
@@ -408,6 +668,8 @@ impl CompaniesQuery {
     /// * `db` - A reference to the database connection.
     /// * `user_id` - The ID of the user to be removed from the company.
     /// * `company_id` - The ID of the company from which the user is being removed.
+    /// * `requester_role_id` - The role of the requester. If provided, only members whose role is not
+    ///   more privileged than it are removed.
     ///
     /// # Returns
     ///
@@ -417,21 +679,24 @@ impl CompaniesQuery {
         db: &DbConn,
         user_id: i32,
         company_id: i32,
-        request_user_id_lvl: Option<i32>,
+        requester_role_id: Option<i32>,
     ) -> Result<(), CoreErrors> {
         // Step 1: Check if the user has an existing role in the specified company
-        let existing_access = match request_user_id_lvl {
-            Some(level) => {
-                // If `request_user_id_lvl` is `Some`, include the role level filter
+        let existing_access = match requester_role_id {
+            Some(requester_role_id) => {
+                // If `requester_role_id` is `Some`, include the role level filter
                 user_company::Entity::find()
                     .filter(user_company::Column::UserId.eq(user_id)) // Filter by user ID
                     .filter(user_company::Column::CompanyId.eq(company_id)) // Filter by company ID
-                    .filter(user_company::Column::RoleId.gte(level)) // Ensure role_id <= level
+                    .filter(role_not_above(
+                        user_company::Column::RoleId,
+                        requester_role_id,
+                    ))
                     .one(db)
                     .await?
             }
             None => {
-                // If `request_user_id_lvl` is `None`, exclude the role level filter
+                // If `requester_role_id` is `None`, exclude the role level filter
                 user_company::Entity::find()
                     .filter(user_company::Column::UserId.eq(user_id)) // Filter by user ID
                     .filter(user_company::Column::CompanyId.eq(company_id)) // Filter by company ID
@@ -461,69 +726,74 @@ impl CompaniesQuery {
         Ok(())
     }
 
-    /// Deletes a specified company from the database if it exists.
+    /// Moves a company to the trash together with its projects, tasks and notes.
     ///
-    /// This function checks if the specified company exists in the database. If found, it deletes the company record.
-    /// If the company does not exist, it returns an error indicating the absence of the specified company.
+    /// The company and everything deleted along with it share the same `deleted_at` timestamp,
+    /// so restoring the company brings back exactly what was deleted with it. Memberships are kept
+    /// until the company is purged from the trash.
     ///
     /// # Arguments
     ///
-    /// * `db` - A reference to the database connection.
+    /// * `db` - A reference to the database connection or an open transaction.
     /// * `company_id` - The ID of the company to be deleted.
     ///
     /// # Returns
     ///
     /// * `Result<(), CoreErrors>` - Returns `Ok(())` if the company was successfully deleted,
     ///   or a `CoreErrors::DatabaseServiceError` if the company does not exist or another error occurs.
-    pub async fn delete_company(db: &DbConn, company_id: i32) -> Result<(), CoreErrors> {
-        // Step 1: Attempt to find the company by its ID
-        let company = companies::Entity::find_by_id(company_id).one(db).await?;
-
-        // Step 2: If the company exists, delete it; otherwise, return an error
-        match company {
-            Some(company) => {
-                // Convert the company entity into an active model for deletion
-                let active_company = company.into_active_model();
-
-                // Delete the company record from the database
-                active_company.delete(db).await?;
-            }
-            None => {
-                // Return an error if the company does not exist
-                return Err(CoreErrors::DatabaseServiceError(format!(
-                    "Company with ID {} does not exist",
-                    company_id
-                )));
-            }
-        };
-
-        // Step 3: Return success if the deletion was successful
-        Ok(())
-    }
-
-    /// Deletes all user associations from the specified company.
-    ///
-    /// This function removes all records from the `user_company` table where the `company_id` matches
-    /// the specified company ID, effectively removing all users from the company.
-    ///
-    /// # Arguments
-    ///
-    /// * `db` - A reference to the database connection.
-    /// * `company_id` - The ID of the company from which all users will be removed.
-    ///
-    /// # Returns
-    ///
-    /// * `Result<(), CoreErrors>` - Returns `Ok(())` if all users were successfully removed,
-    ///   or a `CoreErrors` error if the operation fails.
-    pub async fn delete_all_users_from_company(
-        db: &DbConn,
+    pub async fn delete_company(
+        db: &impl ConnectionTrait,
         company_id: i32,
     ) -> Result<(), CoreErrors> {
-        // Delete all user-company associations for the specified company ID
-        let _: DeleteResult = user_company::Entity::delete_many()
-            .filter(user_company::Column::CompanyId.eq(company_id)) // Filter by company ID
-            .exec(db)
-            .await?;
+        // CURRENT_TIMESTAMP is fixed for the whole statement, so all rows get the same deletion time
+        let sql = r#"
+            WITH deleted_projects AS (
+                UPDATE projects SET deleted_at = CURRENT_TIMESTAMP
+                WHERE company_id = $1
+                    AND deleted_at IS NULL
+                    AND EXISTS (SELECT 1 FROM companies WHERE id = $1 AND deleted_at IS NULL)
+                RETURNING id
+            ),
+            deleted_tasks AS (
+                UPDATE tasks SET deleted_at = CURRENT_TIMESTAMP
+                WHERE project_id IN (SELECT id FROM deleted_projects)
+                    AND deleted_at IS NULL
+                RETURNING id
+            ),
+            deleted_notes AS (
+                UPDATE notes SET deleted_at = CURRENT_TIMESTAMP
+                WHERE deleted_at IS NULL
+                    AND (
+                        (company_id = $1 AND EXISTS (SELECT 1 FROM companies WHERE id = $1 AND deleted_at IS NULL))
+                        OR project_id IN (SELECT id FROM deleted_projects)
+                        OR task_id IN (SELECT id FROM deleted_tasks)
+                        OR subtask_id IN (
+                            SELECT id FROM subtasks WHERE task_id IN (SELECT id FROM deleted_tasks)
+                        )
+                    )
+            )
+            UPDATE companies SET deleted_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND deleted_at IS NULL;
+        "#;
+
+        // Step 1: Mark the company and its content as deleted in a single statement
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                company_id.into(), // $1 - Company ID
+            ],
+        );
+        let result = db.execute(stmt).await?;
+
+        // Step 2: Return an error if the company does not exist or is already in the trash
+        if result.rows_affected() == 0 {
+            return Err(CoreErrors::DatabaseServiceError(format!(
+                "Company with ID {} does not exist",
+                company_id
+            )));
+        }
+
         Ok(())
     }
 
@@ -538,7 +808,7 @@ impl CompaniesQuery {
     ///
     /// # Returns
     /// * `Result<Vec<companies::Model>, CoreErrors>` - Returns a list of `companies::Model` on success,
-    /// or an error if the query fails.
+    ///   or an error if the query fails.
     ///
     /// # Errors
     /// * Returns `CoreErrors` for any database operation failures.
@@ -553,7 +823,8 @@ impl CompaniesQuery {
             c.name AS name,
             c.name_alias AS name_alias,
             c.description AS description,
-            c.contact_info AS contact_info
+            c.contact_info AS contact_info,
+            c.deleted_at AS deleted_at
         FROM 
             user_company uc
         JOIN 
@@ -561,7 +832,8 @@ impl CompaniesQuery {
         ON 
             uc.company_id = c.id
         WHERE 
-            uc.user_id = $1;
+            uc.user_id = $1
+            AND c.deleted_at IS NULL;
     "#;
 
         // Step 1: Prepare the SQL statement with the user ID parameter
@@ -580,6 +852,266 @@ impl CompaniesQuery {
         // Step 3: Return the fetched companies
         Ok(companies)
     }
+
+    /// Retrieves a page of the members of a company, most privileged roles first.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `company_id` - The ID of the company.
+    /// * `limit` - The maximum number of members to return.
+    /// * `offset` - The number of members to skip.
+    ///
+    /// # Returns
+    /// * `Result<Vec<CompanyMember>, CoreErrors>` - Returns the members of the requested page.
+    pub async fn get_company_members(
+        db: &DbConn,
+        company_id: i32,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<CompanyMember>, CoreErrors> {
+        let sql = r#"
+        SELECT
+            u.id AS user_id,
+            u.login,
+            u.user_name,
+            u.email,
+            u.is_active,
+            uc.role_id,
+            uc.access_level::text AS access_level
+        FROM
+            user_company uc
+            INNER JOIN users u ON u.id = uc.user_id
+            INNER JOIN roles r ON r.id = uc.role_id
+        WHERE
+            uc.company_id = $1
+        ORDER BY
+            r.level, u.user_name, u.id
+        LIMIT $2
+        OFFSET $3;
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                company_id.into(),      // $1 - Company ID
+                (limit as i64).into(),  // $2 - Page size
+                (offset as i64).into(), // $3 - Offset
+            ],
+        );
+
+        let members = CompanyMember::find_by_statement(stmt).all(db).await?;
+
+        Ok(members)
+    }
+
+    /// Changes the role of a company member.
+    ///
+    /// The last owner of a company can't be given another role. The owner rows of the company are
+    /// locked first, so two owners can't demote each other concurrently. Run it on a transaction,
+    /// so the locks are held until the change is committed.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `user_id` - The ID of the member.
+    /// * `company_id` - The ID of the company.
+    /// * `role_id` - The new role of the member.
+    ///
+    /// # Returns
+    /// * `Result<Option<user_company::Model>, CoreErrors>` - Returns the updated membership, or `None` if the user
+    ///   is not a member of the company or is its last owner.
+    pub async fn change_member_role(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        company_id: i32,
+        role_id: i32,
+    ) -> Result<Option<user_company::Model>, CoreErrors> {
+        lock_company_owners(db, company_id).await?;
+
+        let sql = r#"
+        UPDATE user_company uc
+        SET role_id = $3
+        WHERE
+            uc.user_id = $1
+            AND uc.company_id = $2
+            AND (
+                -- The member is not an owner, stays an owner, or another owner is left
+                NOT EXISTS (SELECT 1 FROM roles r WHERE r.id = uc.role_id AND r.level = $4)
+                OR EXISTS (SELECT 1 FROM roles r WHERE r.id = $3 AND r.level = $4)
+                OR EXISTS (
+                    SELECT 1
+                    FROM user_company o
+                    JOIN roles r ON r.id = o.role_id
+                    WHERE o.company_id = $2
+                      AND o.user_id <> $1
+                      AND r.level = $4
+                )
+            )
+        RETURNING id, user_id, company_id, role_id, access_level::text AS access_level;
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                user_id.into(),     // $1 - Member ID
+                company_id.into(),  // $2 - Company ID
+                role_id.into(),     // $3 - New role ID
+                OWNER_LEVEL.into(), // $4 - Level of the owner role
+            ],
+        );
+
+        let user_company = user_company::Entity::find()
+            .from_raw_sql(stmt)
+            .one(db)
+            .await?;
+
+        Ok(user_company)
+    }
+
+    /// Hands the owner role of a company over from one member to another.
+    ///
+    /// The roles and access levels of the two members are swapped in one statement, so the company
+    /// always has an owner. The recipient must already be a member and must not be an owner. Like
+    /// the last-owner checks, any role at `OWNER_LEVEL` counts as an owner role.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `owner_id` - The ID of the current owner.
+    /// * `recipient_id` - The ID of the member who becomes the owner.
+    /// * `company_id` - The ID of the company.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the current owner doesn't hold an owner role
+    ///   or the recipient is not a member without one.
+    pub async fn transfer_company_ownership(
+        db: &DbConn,
+        owner_id: i32,
+        recipient_id: i32,
+        company_id: i32,
+    ) -> Result<bool, CoreErrors> {
+        let sql = r#"
+        WITH owner AS (
+            SELECT uc.id, uc.role_id, uc.access_level
+            FROM user_company uc
+            JOIN roles r ON r.id = uc.role_id
+            WHERE uc.user_id = $1 AND uc.company_id = $3 AND r.level = $4
+            FOR UPDATE OF uc
+        ),
+        recipient AS (
+            SELECT uc.id, uc.role_id, uc.access_level
+            FROM user_company uc
+            JOIN roles r ON r.id = uc.role_id
+            WHERE uc.user_id = $2 AND uc.company_id = $3 AND r.level <> $4
+            FOR UPDATE OF uc
+        )
+        UPDATE user_company uc
+        SET
+            role_id = CASE WHEN uc.id = owner.id THEN recipient.role_id ELSE owner.role_id END,
+            access_level = CASE
+                WHEN uc.id = owner.id THEN recipient.access_level
+                ELSE owner.access_level
+            END
+        FROM owner, recipient
+        WHERE uc.id IN (owner.id, recipient.id);
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                owner_id.into(),     // $1 - Current owner ID
+                recipient_id.into(), // $2 - New owner ID
+                company_id.into(),   // $3 - Company ID
+                OWNER_LEVEL.into(),  // $4 - Level of the owner role
+            ],
+        );
+
+        let result = db.execute(stmt).await?;
+
+        Ok(result.rows_affected() == 2)
+    }
+
+    /// Removes a user from a company unless they are its last owner.
+    ///
+    /// The owner rows of the company are locked first, so two owners can't leave concurrently.
+    /// Run it on a transaction, so the locks are held until the change is committed.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `user_id` - The ID of the user leaving the company.
+    /// * `company_id` - The ID of the company.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the user is not a member of the company or is its last owner.
+    pub async fn leave_company(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        company_id: i32,
+    ) -> Result<bool, CoreErrors> {
+        lock_company_owners(db, company_id).await?;
+
+        let sql = r#"
+        DELETE FROM user_company uc
+        WHERE
+            uc.user_id = $1
+            AND uc.company_id = $2
+            AND (
+                -- The member is not an owner, or another owner is left
+                NOT EXISTS (SELECT 1 FROM roles r WHERE r.id = uc.role_id AND r.level = $3)
+                OR EXISTS (
+                    SELECT 1
+                    FROM user_company o
+                    JOIN roles r ON r.id = o.role_id
+                    WHERE o.company_id = $2
+                      AND o.user_id <> $1
+                      AND r.level = $3
+                )
+            );
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                user_id.into(),     // $1 - User ID
+                company_id.into(),  // $2 - Company ID
+                OWNER_LEVEL.into(), // $3 - Level of the owner role
+            ],
+        );
+
+        let result = db.execute(stmt).await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+/// Locks the memberships of the owners of a company until the transaction ends.
+///
+/// Statements run afterwards in the transaction see the owners committed by concurrent
+/// transactions that held the lock before, which keeps the last-owner checks race free.
+async fn lock_company_owners(db: &impl ConnectionTrait, company_id: i32) -> Result<(), CoreErrors> {
+    let sql = r#"
+        SELECT uc.id
+        FROM user_company uc
+        JOIN roles r ON r.id = uc.role_id
+        WHERE uc.company_id = $1 AND r.level = $2
+        ORDER BY uc.id
+        FOR UPDATE OF uc;
+    "#;
+
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        sql,
+        vec![
+            company_id.into(),  // $1 - Company ID
+            OWNER_LEVEL.into(), // $2 - Level of the owner role
+        ],
+    );
+
+    db.query_all(stmt).await?;
+
+    Ok(())
 }
 
 fn convert_to_identifier(input: &str) -> String {
@@ -589,3 +1121,257 @@ fn convert_to_identifier(input: &str) -> String {
         .collect::<String>()
         .to_lowercase() // Convert to lowercase
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use sea_orm::{MockDatabase, TransactionTrait, Value};
+
+    use super::*;
+    use crate::{
+        queries::roles_query::OWNER_ROLE_ID,
+        test_support::{affected, executed_sql},
+    };
+
+    const OWNER_ID: i32 = 1;
+    const MEMBER_ID: i32 = 2;
+    const COMPANY_ID: i32 = 3;
+
+    /// The rows returned by `lock_company_owners`.
+    fn owner_locks() -> Vec<BTreeMap<String, Value>> {
+        vec![BTreeMap::from([("id".to_string(), Value::from(1))])]
+    }
+
+    fn membership(role_id: i32) -> user_company::Model {
+        user_company::Model {
+            id: 10,
+            user_id: MEMBER_ID,
+            company_id: COMPANY_ID,
+            role_id,
+            access_level: AccessLevelType::Limited,
+        }
+    }
+
+    /// Removes the user on a transaction, committing it as the service does.
+    async fn leave(db: &DbConn, user_id: i32) -> Result<bool, CoreErrors> {
+        let txn = db.begin().await?;
+        let left = CompaniesQuery::leave_company(&txn, user_id, COMPANY_ID).await?;
+        if left {
+            txn.commit().await?;
+        }
+
+        Ok(left)
+    }
+
+    /// Changes the role on a transaction, committing it as the service does.
+    async fn change_role(
+        db: &DbConn,
+        user_id: i32,
+        role_id: i32,
+    ) -> Result<Option<user_company::Model>, CoreErrors> {
+        let txn = db.begin().await?;
+        let user_company =
+            CompaniesQuery::change_member_role(&txn, user_id, COMPANY_ID, role_id).await?;
+        if user_company.is_some() {
+            txn.commit().await?;
+        }
+
+        Ok(user_company)
+    }
+
+    fn company(name_alias: &str) -> companies::Model {
+        companies::Model {
+            id: COMPANY_ID,
+            name: "Tech Corp".to_string(),
+            name_alias: name_alias.to_string(),
+            description: None,
+            contact_info: None,
+            deleted_at: None,
+        }
+    }
+
+    /// The row returned by `is_alias_available`.
+    fn alias_exists(exists: bool) -> Vec<BTreeMap<String, Value>> {
+        vec![BTreeMap::from([(
+            "alias_exists".to_string(),
+            Value::from(exists),
+        )])]
+    }
+
+    /// Changes the alias on a transaction, committing it as the service does.
+    async fn change_alias(db: &DbConn, name_alias: &str) -> Result<AliasChange, CoreErrors> {
+        let txn = db.begin().await?;
+        let alias_change =
+            CompaniesQuery::change_company_alias(&txn, COMPANY_ID, name_alias.to_string()).await?;
+        if let AliasChange::Changed(_) = alias_change {
+            txn.commit().await?;
+        }
+
+        Ok(alias_change)
+    }
+
+    /// Checks that a last-owner check ran in a transaction after the owners were locked.
+    fn assert_runs_after_locking_the_owners(sql: &[String], statement: &str) {
+        assert_eq!(sql.len(), 4);
+        assert_eq!(sql[0], "BEGIN");
+        assert!(sql[1].contains("FOR UPDATE OF uc"));
+        assert!(sql[2].contains(statement));
+        assert_eq!(sql[3], "COMMIT");
+    }
+
+    #[tokio::test]
+    async fn change_company_alias_checks_the_alias_after_locking_it() {
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![company("techcorp")]])
+            .append_exec_results([affected(1)])
+            .append_query_results([alias_exists(false)])
+            .append_exec_results([affected(0)])
+            .append_query_results([vec![company_alias_redirects::Model {
+                id: 1,
+                company_id: COMPANY_ID,
+                name_alias: "techcorp".to_string(),
+                created_at: Default::default(),
+            }]])
+            .append_query_results([vec![company("newcorp")]])
+            .into_connection();
+
+        let alias_change = change_alias(&db, "newcorp").await.unwrap();
+
+        assert!(
+            matches!(alias_change, AliasChange::Changed(company) if company.name_alias == "newcorp")
+        );
+
+        let sql = executed_sql(db);
+        assert_eq!(sql.len(), 8);
+        assert_eq!(sql[0], "BEGIN");
+        assert!(sql[1].contains("FOR UPDATE"));
+        assert!(sql[2].contains("pg_advisory_xact_lock"));
+        assert!(sql[3].contains("company_alias_redirects"));
+        assert!(sql[3].contains("alias_exists"));
+        assert!(sql[4].starts_with("DELETE FROM \"company_alias_redirects\""));
+        assert!(sql[5].starts_with("INSERT INTO \"company_alias_redirects\""));
+        assert!(sql[6].starts_with("UPDATE \"companies\""));
+        assert_eq!(sql[7], "COMMIT");
+    }
+
+    #[tokio::test]
+    async fn change_company_alias_reports_a_taken_alias() {
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![company("techcorp")]])
+            .append_exec_results([affected(1)])
+            .append_query_results([alias_exists(true)])
+            .into_connection();
+
+        let alias_change = change_alias(&db, "newcorp").await.unwrap();
+
+        assert!(matches!(alias_change, AliasChange::Unavailable));
+
+        // Nothing is written and the transaction is rolled back
+        let sql = executed_sql(db);
+        assert_eq!(sql.len(), 5);
+        assert!(sql[3].contains("alias_exists"));
+        assert_eq!(sql[4], "ROLLBACK");
+    }
+
+    #[tokio::test]
+    async fn change_company_alias_reports_a_reserved_alias() {
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![company("techcorp")]])
+            .append_exec_results([affected(1)])
+            .into_connection();
+
+        let alias_change = change_alias(&db, "admin").await.unwrap();
+
+        assert!(matches!(alias_change, AliasChange::Unavailable));
+        assert_eq!(executed_sql(db).len(), 4);
+    }
+
+    #[tokio::test]
+    async fn leave_company_locks_the_owners_first() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([owner_locks()])
+            .append_exec_results([affected(1)])
+            .into_connection();
+
+        assert!(leave(&db, MEMBER_ID).await.unwrap());
+
+        assert_runs_after_locking_the_owners(&executed_sql(db), "DELETE FROM user_company");
+    }
+
+    /// The last-owner guard runs in the SQL statement; this checks that a refused removal is reported.
+    #[tokio::test]
+    async fn leave_company_reports_a_refused_removal() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([owner_locks()])
+            .append_exec_results([affected(0)])
+            .into_connection();
+
+        assert!(!leave(&db, OWNER_ID).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn change_member_role_locks_the_owners_first() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([owner_locks()])
+            .append_query_results([vec![membership(OWNER_ROLE_ID)]])
+            .into_connection();
+
+        let user_company = change_role(&db, MEMBER_ID, OWNER_ROLE_ID).await.unwrap();
+
+        assert_eq!(
+            user_company.map(|member| member.role_id),
+            Some(OWNER_ROLE_ID)
+        );
+        assert_runs_after_locking_the_owners(&executed_sql(db), "UPDATE user_company uc");
+    }
+
+    /// The last-owner guard runs in the SQL statement; this checks that a refused change is reported.
+    #[tokio::test]
+    async fn change_member_role_reports_a_refused_change() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([owner_locks()])
+            .append_query_results([Vec::<user_company::Model>::new()])
+            .into_connection();
+
+        let user_company = change_role(&db, OWNER_ID, 2).await.unwrap();
+
+        assert_eq!(user_company, None);
+    }
+
+    #[tokio::test]
+    async fn removing_a_member_compares_effective_role_levels() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![membership(4)]])
+            .append_exec_results([affected(1)])
+            .into_connection();
+
+        CompaniesQuery::remove_user_from_company(&db, MEMBER_ID, COMPANY_ID, Some(2))
+            .await
+            .unwrap();
+
+        let sql = executed_sql(db);
+        assert!(sql[0].contains(
+            r#"role_effective_level("user_company"."role_id") >= role_effective_level($3)"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn ownership_transfer_needs_both_memberships_swapped() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(2), affected(0)])
+            .into_connection();
+
+        assert!(
+            CompaniesQuery::transfer_company_ownership(&db, OWNER_ID, MEMBER_ID, COMPANY_ID)
+                .await
+                .unwrap()
+        );
+        // The recipient is no member or already an owner
+        assert!(
+            !CompaniesQuery::transfer_company_ownership(&db, OWNER_ID, MEMBER_ID, COMPANY_ID)
+                .await
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone,
+    sea_query::{Expr, Func, Query},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbBackend, DbConn, EntityTrait,
+    FromQueryResult, QueryFilter, Set, Statement,
+};
+
+use crate::entity::{companies, company_invitations, prelude, user_company};
+
+/// A pending invitation together with the name of the company it invites to.
+#[derive(Debug, FromQueryResult)]
+pub struct CompanyInvitation {
+    pub id: i32,
+    pub company_id: i32,
+    pub company_name: String,
+    pub email: String,
+    pub role_id: i32,
+    pub invited_by: Option<i32>,
+    pub expires_at: DateTimeWithTimeZone,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+/// Provides methods for issuing, listing and redeeming company invitations.
+pub struct CompanyInvitationsQuery;
+
+impl CompanyInvitationsQuery {
+    /// Stores a new invitation and revokes the earlier pending invitations of the email to the same company.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `company_id` - The ID of the company the invitation is for.
+    /// * `email` - The email address the invitation was sent to.
+    /// * `role_id` - The role the invited user gets in the company.
+    /// * `invited_by` - The ID of the user who sent the invitation.
+    /// * `token_hash` - The hash of the invitation token; the token itself is never stored.
+    /// * `expires_at` - The moment the invitation expires.
+    ///
+    /// # Returns
+    /// * `Result<company_invitations::Model, CoreErrors>` - Returns the stored invitation.
+    pub async fn create_invitation(
+        db: &DbConn,
+        company_id: i32,
+        email: String,
+        role_id: i32,
+        invited_by: i32,
+        token_hash: String,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<company_invitations::Model, CoreErrors> {
+        // Only the most recently sent invitation stays valid
+        prelude::CompanyInvitations::update_many()
+            .col_expr(
+                company_invitations::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(company_invitations::Column::CompanyId.eq(company_id))
+            .filter(email_matches(&email))
+            .filter(pending())
+            .exec(db)
+            .await?;
+
+        let new_invitation = company_invitations::ActiveModel {
+            company_id: Set(company_id),
+            email: Set(email),
+            role_id: Set(role_id),
+            invited_by: Set(Some(invited_by)),
+            token_hash: Set(token_hash),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        };
+
+        let invitation = new_invitation.insert(db).await?;
+
+        Ok(invitation)
+    }
+
+    /// Retrieves a pending invitation by its ID.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `invitation_id` - The ID of the invitation.
+    ///
+    /// # Returns
+    /// * `Result<Option<company_invitations::Model>, CoreErrors>` - Returns the invitation, or `None` if it is unknown or no longer pending.
+    pub async fn get_pending_invitation(
+        db: &DbConn,
+        invitation_id: i32,
+    ) -> Result<Option<company_invitations::Model>, CoreErrors> {
+        let invitation = prelude::CompanyInvitations::find_by_id(invitation_id)
+            .filter(pending())
+            .one(db)
+            .await?;
+
+        Ok(invitation)
+    }
+
+    /// Retrieves a pending invitation by the hash of its token.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `token_hash` - The hash of the presented token.
+    ///
+    /// # Returns
+    /// * `Result<Option<company_invitations::Model>, CoreErrors>` - Returns the invitation, or `None` if the token is unknown or the invitation is no longer pending.
+    pub async fn get_pending_invitation_by_token(
+        db: &DbConn,
+        token_hash: &str,
+    ) -> Result<Option<company_invitations::Model>, CoreErrors> {
+        let invitation = prelude::CompanyInvitations::find()
+            .filter(company_invitations::Column::TokenHash.eq(token_hash))
+            .filter(pending())
+            .one(db)
+            .await?;
+
+        Ok(invitation)
+    }
+
+    /// Retrieves the pending invitations of a company, newest first.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `company_id` - The ID of the company.
+    ///
+    /// # Returns
+    /// * `Result<Vec<CompanyInvitation>, CoreErrors>` - Returns the pending invitations.
+    pub async fn get_pending_company_invitations(
+        db: &DbConn,
+        company_id: i32,
+    ) -> Result<Vec<CompanyInvitation>, CoreErrors> {
+        let sql = r#"
+        SELECT
+            ci.id,
+            ci.company_id,
+            c.name AS company_name,
+            ci.email,
+            ci.role_id,
+            ci.invited_by,
+            ci.expires_at,
+            ci.created_at
+        FROM company_invitations ci
+        INNER JOIN companies c ON c.id = ci.company_id
+        WHERE ci.company_id = $1
+            AND ci.accepted_at IS NULL
+            AND ci.declined_at IS NULL
+            AND ci.revoked_at IS NULL
+            AND ci.expires_at > CURRENT_TIMESTAMP
+            AND c.deleted_at IS NULL
+        ORDER BY ci.created_at DESC
+        "#;
+
+        let stmt = Statement::from_sql_and_values(DbBackend::Postgres, sql, [company_id.into()]);
+
+        let invitations = CompanyInvitation::find_by_statement(stmt).all(db).await?;
+
+        Ok(invitations)
+    }
+
+    /// Retrieves the pending invitations sent to an email, newest first.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `email` - The email address of the invited user; compared case-insensitively.
+    ///
+    /// # Returns
+    /// * `Result<Vec<CompanyInvitation>, CoreErrors>` - Returns the pending invitations.
+    pub async fn get_pending_user_invitations(
+        db: &DbConn,
+        email: &str,
+    ) -> Result<Vec<CompanyInvitation>, CoreErrors> {
+        let sql = r#"
+        SELECT
+            ci.id,
+            ci.company_id,
+            c.name AS company_name,
+            ci.email,
+            ci.role_id,
+            ci.invited_by,
+            ci.expires_at,
+            ci.created_at
+        FROM company_invitations ci
+        INNER JOIN companies c ON c.id = ci.company_id
+        WHERE lower(ci.email) = lower($1)
+            AND ci.accepted_at IS NULL
+            AND ci.declined_at IS NULL
+            AND ci.revoked_at IS NULL
+            AND ci.expires_at > CURRENT_TIMESTAMP
+            AND c.deleted_at IS NULL
+        ORDER BY ci.created_at DESC
+        "#;
+
+        let stmt = Statement::from_sql_and_values(DbBackend::Postgres, sql, [email.into()]);
+
+        let invitations = CompanyInvitation::find_by_statement(stmt).all(db).await?;
+
+        Ok(invitations)
+    }
+
+    /// Accepts a pending invitation and adds the user to the company with the invited role.
+    ///
+    /// The invitation is marked as accepted and the membership is created in one statement.
+    /// A user who already belongs to the company keeps their current role.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `invitation_id` - The ID of the invitation.
+    /// * `user_id` - The ID of the user accepting the invitation.
+    /// * `email` - The email address of the user; must match the invited email.
+    ///
+    /// # Returns
+    /// * `Result<Option<user_company::Model>, CoreErrors>` - Returns the user's membership in the company,
+    ///   or `None` if the invitation is not pending or was sent to another email.
+    pub async fn accept_invitation(
+        db: &DbConn,
+        invitation_id: i32,
+        user_id: i32,
+        email: &str,
+    ) -> Result<Option<user_company::Model>, CoreErrors> {
+        let sql = r#"
+        WITH accepted AS (
+            UPDATE company_invitations
+            SET accepted_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+                AND lower(email) = lower($2)
+                AND accepted_at IS NULL
+                AND declined_at IS NULL
+                AND revoked_at IS NULL
+                AND expires_at > CURRENT_TIMESTAMP
+                AND company_id IN (SELECT id FROM companies WHERE deleted_at IS NULL)
+            RETURNING company_id, role_id
+        ),
+        joined AS (
+            INSERT INTO user_company (user_id, company_id, role_id, access_level)
+            SELECT $3, a.company_id, a.role_id, 'limited'::access_level_type
+            FROM accepted a
+            WHERE NOT EXISTS (
+                SELECT 1 FROM user_company uc
+                WHERE uc.user_id = $3 AND uc.company_id = a.company_id
+            )
+            RETURNING id, user_id, company_id, role_id, access_level
+        )
+        SELECT id, user_id, company_id, role_id, access_level::text AS access_level
+        FROM joined
+        UNION ALL
+        SELECT uc.id, uc.user_id, uc.company_id, uc.role_id, uc.access_level::text AS access_level
+        FROM user_company uc
+        INNER JOIN accepted a ON a.company_id = uc.company_id
+        WHERE uc.user_id = $3
+        "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [invitation_id.into(), email.into(), user_id.into()],
+        );
+
+        let user_company = user_company::Entity::find()
+            .from_raw_sql(stmt)
+            .one(db)
+            .await?;
+
+        Ok(user_company)
+    }
+
+    /// Accepts all pending invitations sent to an email, e.g. once a new user confirmed it.
+    ///
+    /// Companies the user already belongs to are skipped.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `user_id` - The ID of the user.
+    /// * `email` - The email address of the user.
+    ///
+    /// # Returns
+    /// * `Result<Vec<user_company::Model>, CoreErrors>` - Returns the memberships that were created.
+    pub async fn accept_all_invitations(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        email: &str,
+    ) -> Result<Vec<user_company::Model>, CoreErrors> {
+        let sql = r#"
+        WITH accepted AS (
+            UPDATE company_invitations
+            SET accepted_at = CURRENT_TIMESTAMP
+            WHERE lower(email) = lower($1)
+                AND accepted_at IS NULL
+                AND declined_at IS NULL
+                AND revoked_at IS NULL
+                AND expires_at > CURRENT_TIMESTAMP
+                AND company_id IN (SELECT id FROM companies WHERE deleted_at IS NULL)
+            RETURNING company_id, role_id, created_at
+        )
+        INSERT INTO user_company (user_id, company_id, role_id, access_level)
+        SELECT DISTINCT ON (a.company_id) $2, a.company_id, a.role_id, 'limited'::access_level_type
+        FROM accepted a
+        WHERE NOT EXISTS (
+            SELECT 1 FROM user_company uc
+            WHERE uc.user_id = $2 AND uc.company_id = a.company_id
+        )
+        ORDER BY a.company_id, a.created_at DESC
+        RETURNING id, user_id, company_id, role_id, access_level::text AS access_level
+        "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [email.into(), user_id.into()],
+        );
+
+        let memberships = user_company::Entity::find()
+            .from_raw_sql(stmt)
+            .all(db)
+            .await?;
+
+        Ok(memberships)
+    }
+
+    /// Declines a pending invitation sent to an email.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `invitation_id` - The ID of the invitation.
+    /// * `email` - The email address of the user; must match the invited email.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the invitation is not pending or was sent to another email.
+    pub async fn decline_invitation(
+        db: &DbConn,
+        invitation_id: i32,
+        email: &str,
+    ) -> Result<bool, CoreErrors> {
+        let result = prelude::CompanyInvitations::update_many()
+            .col_expr(
+                company_invitations::Column::DeclinedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(company_invitations::Column::Id.eq(invitation_id))
+            .filter(email_matches(email))
+            .filter(pending())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Revokes a pending invitation; its link stops working immediately.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `invitation_id` - The ID of the invitation.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the invitation is not pending.
+    pub async fn revoke_invitation(db: &DbConn, invitation_id: i32) -> Result<bool, CoreErrors> {
+        let result = prelude::CompanyInvitations::update_many()
+            .col_expr(
+                company_invitations::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(company_invitations::Column::Id.eq(invitation_id))
+            .filter(pending())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+}
+
+/// Matches invitations that were neither accepted, declined nor revoked and haven't expired,
+/// to companies that are not in the trash.
+fn pending() -> Condition {
+    Condition::all()
+        .add(company_invitations::Column::AcceptedAt.is_null())
+        .add(company_invitations::Column::DeclinedAt.is_null())
+        .add(company_invitations::Column::RevokedAt.is_null())
+        .add(Expr::col(company_invitations::Column::ExpiresAt).gt(Expr::current_timestamp()))
+        .add(
+            company_invitations::Column::CompanyId.in_subquery(
+                Query::select()
+                    .column(companies::Column::Id)
+                    .from(companies::Entity)
+                    .and_where(companies::Column::DeletedAt.is_null())
+                    .to_owned(),
+            ),
+        )
+}
+
+/// Matches invitations sent to an email, ignoring case.
+fn email_matches(email: &str) -> Condition {
+    Condition::all().add(
+        Expr::expr(Func::lower(Expr::col(company_invitations::Column::Email)))
+            .eq(email.to_lowercase()),
+    )
+}
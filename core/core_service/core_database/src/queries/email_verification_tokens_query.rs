@@ -0,0 +1,85 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, sea_query::Expr, ActiveModelTrait, ColumnTrait, DbConn,
+    EntityTrait, QueryFilter, Set,
+};
+
+use crate::entity::{email_verification_tokens, prelude};
+
+/// Provides methods for issuing and redeeming email verification tokens.
+pub struct EmailVerificationTokensQuery;
+
+impl EmailVerificationTokensQuery {
+    /// Stores a new email verification token and invalidates the user's earlier unused ones.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user the token was issued to.
+    /// * `email` - The email address the token was sent to.
+    /// * `token_hash` - The hash of the token; the token itself is never stored.
+    /// * `expires_at` - The moment the token expires.
+    ///
+    /// # Returns
+    /// * `Result<email_verification_tokens::Model, CoreErrors>` - Returns the stored token record.
+    pub async fn create_verification_token(
+        db: &DbConn,
+        user_id: i32,
+        email: String,
+        token_hash: String,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<email_verification_tokens::Model, CoreErrors> {
+        // Only the most recently sent link stays valid
+        prelude::EmailVerificationTokens::update_many()
+            .col_expr(
+                email_verification_tokens::Column::UsedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(email_verification_tokens::Column::UserId.eq(user_id))
+            .filter(email_verification_tokens::Column::UsedAt.is_null())
+            .exec(db)
+            .await?;
+
+        let new_token = email_verification_tokens::ActiveModel {
+            user_id: Set(user_id),
+            email: Set(email),
+            token_hash: Set(token_hash),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        };
+
+        let token = new_token.insert(db).await?;
+
+        Ok(token)
+    }
+
+    /// Marks an unused, unexpired email verification token as used.
+    ///
+    /// The check and the update happen in one statement, so a token can't be redeemed twice.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `token_hash` - The hash of the presented token.
+    ///
+    /// # Returns
+    /// * `Result<Option<email_verification_tokens::Model>, CoreErrors>` - Returns the redeemed token, or `None` if the token is unknown, used or expired.
+    pub async fn consume_verification_token(
+        db: &DbConn,
+        token_hash: &str,
+    ) -> Result<Option<email_verification_tokens::Model>, CoreErrors> {
+        let tokens = prelude::EmailVerificationTokens::update_many()
+            .col_expr(
+                email_verification_tokens::Column::UsedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(email_verification_tokens::Column::TokenHash.eq(token_hash))
+            .filter(email_verification_tokens::Column::UsedAt.is_null())
+            .filter(
+                Expr::col(email_verification_tokens::Column::ExpiresAt)
+                    .gt(Expr::current_timestamp()),
+            )
+            .exec_with_returning(db)
+            .await?;
+
+        Ok(tokens.into_iter().next())
+    }
+}
@@ -0,0 +1,188 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+    Select, Set,
+};
+
+use crate::entity::{companies, knowledge_base, projects, sea_orm_active_enums::AccessLevelType};
+
+/// Provides methods for querying and manipulating knowledge base articles.
+pub struct KnowledgeBaseQuery;
+
+impl KnowledgeBaseQuery {
+    /// Creates a new knowledge base article.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `company_id` - The ID of the owning company.
+    /// * `project_id` - The ID of the project the article belongs to, if any.
+    /// * `title` - The title of the article.
+    /// * `content` - The content of the article.
+    /// * `access_level` - Who may read the article.
+    /// * `role_id` - The minimum role required to read the article, if any.
+    ///
+    /// # Returns
+    /// * `Result<knowledge_base::Model, CoreErrors>` - Returns the created article.
+    pub async fn create_article(
+        db: &DbConn,
+        company_id: i32,
+        project_id: Option<i32>,
+        title: String,
+        content: String,
+        access_level: AccessLevelType,
+        role_id: Option<i32>,
+    ) -> Result<knowledge_base::Model, CoreErrors> {
+        let new_article = knowledge_base::ActiveModel {
+            company_id: Set(company_id),
+            project_id: Set(project_id),
+            title: Set(title),
+            content: Set(Some(content)),
+            access_level: Set(access_level),
+            role_id: Set(role_id),
+            ..Default::default()
+        };
+
+        let article = new_article.insert(db).await?;
+
+        Ok(article)
+    }
+
+    /// Retrieves an article by its ID, unless its company or project is in the trash.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `article_id` - The ID of the article.
+    ///
+    /// # Returns
+    /// * `Result<Option<knowledge_base::Model>, CoreErrors>` - Returns the article if it exists, otherwise `None`.
+    pub async fn get_article_by_id(
+        db: &DbConn,
+        article_id: i32,
+    ) -> Result<Option<knowledge_base::Model>, CoreErrors> {
+        let article = visible_articles()
+            .filter(knowledge_base::Column::Id.eq(article_id))
+            .one(db)
+            .await?;
+
+        Ok(article)
+    }
+
+    /// Retrieves all articles of a company, optionally restricted to a single project.
+    ///
+    /// Articles of companies and projects in the trash are left out. No access filtering is
+    /// applied; the caller must drop the articles the user may not read.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `company_id` - The ID of the company.
+    /// * `project_id` - The ID of the project to restrict the list to, if any.
+    ///
+    /// # Returns
+    /// * `Result<Vec<knowledge_base::Model>, CoreErrors>` - Returns the articles ordered by ID.
+    pub async fn get_articles(
+        db: &DbConn,
+        company_id: i32,
+        project_id: Option<i32>,
+    ) -> Result<Vec<knowledge_base::Model>, CoreErrors> {
+        let mut query = visible_articles().filter(knowledge_base::Column::CompanyId.eq(company_id));
+
+        if let Some(project_id) = project_id {
+            query = query.filter(knowledge_base::Column::ProjectId.eq(project_id));
+        }
+
+        let articles = query
+            .order_by_asc(knowledge_base::Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(articles)
+    }
+
+    /// Updates the editable fields of an article.
+    ///
+    /// Only the fields passed as `Some` are changed. For `role_id`, `Some(None)` removes the
+    /// minimum role requirement.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `article` - The article to update.
+    /// * `title` - The new title, if it should change.
+    /// * `content` - The new content, if it should change.
+    /// * `access_level` - The new access level, if it should change.
+    /// * `role_id` - The new minimum role, if it should change.
+    ///
+    /// # Returns
+    /// * `Result<knowledge_base::Model, CoreErrors>` - Returns the updated article.
+    pub async fn update_article(
+        db: &DbConn,
+        article: knowledge_base::Model,
+        title: Option<String>,
+        content: Option<String>,
+        access_level: Option<AccessLevelType>,
+        role_id: Option<Option<i32>>,
+    ) -> Result<knowledge_base::Model, CoreErrors> {
+        let mut active_article = article.into_active_model();
+
+        if let Some(title) = title {
+            active_article.title = Set(title);
+        }
+
+        if let Some(content) = content {
+            active_article.content = Set(Some(content));
+        }
+
+        if let Some(access_level) = access_level {
+            active_article.access_level = Set(access_level);
+        }
+
+        if let Some(role_id) = role_id {
+            active_article.role_id = Set(role_id);
+        }
+
+        let updated_article = active_article.update(db).await?;
+
+        Ok(updated_article)
+    }
+
+    /// Deletes an article by its ID.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `article_id` - The ID of the article to be deleted.
+    ///
+    /// # Returns
+    /// * `Result<(), CoreErrors>` - Returns `Ok(())` if the article is successfully deleted.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::DatabaseServiceError` if the article does not exist or its company or
+    ///   project is in the trash.
+    pub async fn delete_article(db: &DbConn, article_id: i32) -> Result<(), CoreErrors> {
+        match visible_articles()
+            .filter(knowledge_base::Column::Id.eq(article_id))
+            .one(db)
+            .await?
+        {
+            Some(article) => {
+                article.into_active_model().delete(db).await?;
+            }
+            None => {
+                return Err(CoreErrors::DatabaseServiceError(format!(
+                    "Article with ID {} does not exist",
+                    article_id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Selects the articles whose company and project, if any, are not in the trash.
+fn visible_articles() -> Select<knowledge_base::Entity> {
+    knowledge_base::Entity::find()
+        .inner_join(companies::Entity)
+        .left_join(projects::Entity)
+        .filter(companies::Column::DeletedAt.is_null())
+        // Also matches articles without a project, which have no joined project row
+        .filter(projects::Column::DeletedAt.is_null())
+}
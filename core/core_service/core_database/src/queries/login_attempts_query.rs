@@ -0,0 +1,159 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait,
+    DbBackend, DbConn, EntityTrait, QueryFilter, QueryOrder, Set, Statement,
+};
+
+use crate::entity::{login_attempts, login_throttles, prelude};
+
+/// Provides methods for auditing failed logins and throttling password guessing.
+pub struct LoginAttemptsQuery;
+
+impl LoginAttemptsQuery {
+    /// Records a failed login attempt in the audit log.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `login` - The login as entered by the client.
+    /// * `user_id` - The ID of the user with this login, if one exists.
+    /// * `ip_address` - The IP address of the client, if known.
+    /// * `device_info` - A description of the client, if known.
+    /// * `reason` - Why the attempt failed, e.g. `invalid_password`.
+    ///
+    /// # Returns
+    /// * `Result<(), CoreErrors>` - Returns `Ok(())` once the attempt is recorded.
+    pub async fn record_failed_attempt(
+        db: &DbConn,
+        login: String,
+        user_id: Option<i32>,
+        ip_address: Option<String>,
+        device_info: Option<String>,
+        reason: &str,
+    ) -> Result<(), CoreErrors> {
+        let attempt = login_attempts::ActiveModel {
+            login: Set(login),
+            user_id: Set(user_id),
+            ip_address: Set(ip_address),
+            device_info: Set(device_info),
+            reason: Set(reason.to_string()),
+            ..Default::default()
+        };
+
+        attempt.insert(db).await?;
+
+        Ok(())
+    }
+
+    /// Returns the latest moment any of the given throttle keys is locked out until.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `throttle_keys` - The keys to check, e.g. the login and the IP address of a client.
+    ///
+    /// # Returns
+    /// * `Result<Option<DateTimeWithTimeZone>, CoreErrors>` - Returns the end of the lockout, or `None` if no key is locked.
+    pub async fn get_locked_until(
+        db: &DbConn,
+        throttle_keys: Vec<String>,
+    ) -> Result<Option<DateTimeWithTimeZone>, CoreErrors> {
+        let throttle = prelude::LoginThrottles::find()
+            .filter(login_throttles::Column::ThrottleKey.is_in(throttle_keys))
+            .filter(Expr::col(login_throttles::Column::LockedUntil).gt(Expr::current_timestamp()))
+            .order_by_desc(login_throttles::Column::LockedUntil)
+            .one(db)
+            .await?;
+
+        Ok(throttle.and_then(|throttle| throttle.locked_until))
+    }
+
+    /// Counts a failed attempt against a throttle key.
+    ///
+    /// The counter starts over if the previous failure is older than `window_secs`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `throttle_key` - The key the attempt is counted against.
+    /// * `window_secs` - How long failures are remembered.
+    ///
+    /// # Returns
+    /// * `Result<i32, CoreErrors>` - Returns the number of consecutive failures, including this one.
+    pub async fn register_failure(
+        db: &DbConn,
+        throttle_key: String,
+        window_secs: i64,
+    ) -> Result<i32, CoreErrors> {
+        // Upsert in one statement, so concurrent attempts can't lose an increment
+        let sql = r#"
+        INSERT INTO login_throttles (throttle_key, failed_attempts, last_failed_at)
+        VALUES ($1, 1, CURRENT_TIMESTAMP)
+        ON CONFLICT (throttle_key) DO UPDATE SET
+            failed_attempts = CASE
+                WHEN login_throttles.last_failed_at < CURRENT_TIMESTAMP - make_interval(secs => $2)
+                THEN 1
+                ELSE login_throttles.failed_attempts + 1
+            END,
+            last_failed_at = CURRENT_TIMESTAMP
+        RETURNING failed_attempts
+        "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [throttle_key.into(), (window_secs as f64).into()],
+        );
+
+        let result = db
+            .query_one(stmt)
+            .await?
+            .ok_or(CoreErrors::DatabaseServiceError(
+                "failed_update_data".to_string(),
+            ))?;
+
+        Ok(result.try_get("", "failed_attempts")?)
+    }
+
+    /// Locks a throttle key out for a number of seconds.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `throttle_key` - The key to lock.
+    /// * `lockout_secs` - The duration of the lockout.
+    ///
+    /// # Returns
+    /// * `Result<(), CoreErrors>` - Returns `Ok(())` once the key is locked.
+    pub async fn lock(
+        db: &DbConn,
+        throttle_key: String,
+        lockout_secs: i64,
+    ) -> Result<(), CoreErrors> {
+        prelude::LoginThrottles::update_many()
+            .col_expr(
+                login_throttles::Column::LockedUntil,
+                Expr::cust_with_values(
+                    "CURRENT_TIMESTAMP + make_interval(secs => $1)",
+                    [lockout_secs as f64],
+                ),
+            )
+            .filter(login_throttles::Column::ThrottleKey.eq(throttle_key))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Forgets the failed attempts counted against a throttle key, e.g. after a successful login.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `throttle_key` - The key to reset.
+    ///
+    /// # Returns
+    /// * `Result<(), CoreErrors>` - Returns `Ok(())` once the key is reset.
+    pub async fn clear_failures(db: &DbConn, throttle_key: String) -> Result<(), CoreErrors> {
+        prelude::LoginThrottles::delete_by_id(throttle_key)
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
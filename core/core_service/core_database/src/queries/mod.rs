@@ -1,5 +1,19 @@
+pub mod access_tokens_query;
 pub mod companies_query;
+pub mod company_invitations_query;
+pub mod email_verification_tokens_query;
+pub mod knowledge_base_query;
+pub mod login_attempts_query;
 pub mod notes_query;
+pub mod oidc_login_requests_query;
+pub mod password_reset_tokens_query;
 pub mod projects_query;
+pub mod refresh_tokens_query;
+pub mod roles_query;
+pub mod sessions_query;
+pub mod subtasks_query;
 pub mod tasks_query;
+pub mod totp_query;
+pub mod trash_query;
+pub mod user_identities_query;
 pub mod user_query;
@@ -1,8 +1,26 @@
 use core_error::core_errors::CoreErrors;
-use sea_orm::{ActiveModelTrait, DbBackend, DbConn, EntityTrait, IntoActiveModel, Set, Statement};
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, ActiveModelTrait, ConnectionTrait, DbBackend, DbConn,
+    EntityTrait, IntoActiveModel, Set, Statement,
+};
 
 use crate::entity::notes;
 
+use super::roles_query::ADMINISTRATOR_LEVEL;
+
+/// Filters applied when listing notes. `None` fields are ignored.
+#[derive(Debug, Default)]
+pub struct NotesFilter {
+    pub company_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub task_id: Option<i32>,
+    pub subtask_id: Option<i32>,
+    pub tag: Option<String>,
+    pub author_id: Option<i32>,
+    pub created_from: Option<DateTimeWithTimeZone>,
+    pub created_to: Option<DateTimeWithTimeZone>,
+}
+
 /// Provides methods for querying and manipulating notes.
 pub struct NotesQuery;
 
@@ -28,6 +46,7 @@ impl NotesQuery {
     ///
     /// * `Result<notes::Model, CoreErrors>` - Returns the created note model on success,
     ///   or a `CoreErrors` error if the operation fails.
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_note(
         db: &DbConn,
         user_id: i32,
@@ -72,7 +91,7 @@ impl NotesQuery {
     ///
     /// # Returns
     /// * `Result<Option<notes::Model>, CoreErrors>` - Returns the note model if the user has access,
-    /// or `None` if the user lacks permission or the note does not exist.
+    ///   or `None` if the user lacks permission or the note does not exist or is in the trash.
     ///
     /// # Errors
     /// * Returns `CoreErrors` for any database operation failures.
@@ -90,19 +109,22 @@ impl NotesQuery {
                 notes n
             WHERE 
                 n.id = $1
+                AND n.deleted_at IS NULL
         ),
         access_check AS (
             SELECT
                 ua.*
             FROM 
                 user_access ua
+            JOIN roles r ON ua.role_id = r.id
             WHERE 
                 ua.user_id = $2
+                AND role_effective_level(r.id) <= $3
                 AND (
-                    (ua.project_id = (SELECT project_id FROM note_data) AND ua.role_id <= 2)
-                    OR (ua.company_id = (SELECT company_id FROM note_data) AND ua.role_id <= 2)
-                    OR (ua.task_id = (SELECT task_id FROM note_data) AND ua.role_id <= 2)
-                    OR (ua.subtask_id = (SELECT subtask_id FROM note_data) AND ua.role_id <= 2)
+                    ua.project_id = (SELECT project_id FROM note_data)
+                    OR ua.company_id = (SELECT company_id FROM note_data)
+                    OR ua.task_id = (SELECT task_id FROM note_data)
+                    OR ua.subtask_id = (SELECT subtask_id FROM note_data)
                 )
         ),
         final_note AS (
@@ -127,7 +149,8 @@ impl NotesQuery {
             content,
             tags,
             decoration_color,
-            created_at
+            created_at,
+            deleted_at
         FROM 
             final_note;
     "#;
@@ -137,8 +160,9 @@ impl NotesQuery {
             DbBackend::Postgres,
             sql,
             vec![
-                note_id.into(), // $1 - Note ID
-                user_id.into(), // $2 - User ID
+                note_id.into(),             // $1 - Note ID
+                user_id.into(),             // $2 - User ID
+                ADMINISTRATOR_LEVEL.into(), // $3 - Least privileged role level with access
             ],
         );
 
@@ -149,10 +173,139 @@ impl NotesQuery {
         Ok(note)
     }
 
-    /// Deletes a note from the database by its ID.
+    /// Retrieves the notes visible to a user, filtered and paginated.
+    ///
+    /// A note is visible under the same rules as `check_user_permission`: the user wrote it,
+    /// or is at least an "Administrator" on the note's company, project, task, or subtask.
+    /// Notes in the trash are left out. Notes are ordered from newest to oldest.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user listing the notes.
+    /// * `filter` - The filters to apply.
+    /// * `limit` - The maximum number of notes to return.
+    /// * `offset` - The number of notes to skip.
+    ///
+    /// # Returns
+    /// * `Result<Vec<notes::Model>, CoreErrors>` - Returns the matching notes.
+    pub async fn get_notes(
+        db: &DbConn,
+        user_id: i32,
+        filter: NotesFilter,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<notes::Model>, CoreErrors> {
+        let sql = r#"
+        SELECT
+            n.id,
+            n.user_id,
+            n.company_id,
+            n.project_id,
+            n.task_id,
+            n.subtask_id,
+            n.content,
+            n.tags,
+            n.decoration_color,
+            n.created_at,
+            n.deleted_at
+        FROM
+            notes n
+        WHERE
+            n.deleted_at IS NULL
+            AND (
+                n.user_id = $1
+                OR EXISTS (
+                    SELECT 1
+                    FROM user_access ua
+                    JOIN roles r ON ua.role_id = r.id
+                    WHERE ua.user_id = $1
+                      AND role_effective_level(r.id) <= $12
+                      AND (
+                          ua.project_id = n.project_id
+                          OR ua.company_id = n.company_id
+                          OR ua.task_id = n.task_id
+                          OR ua.subtask_id = n.subtask_id
+                      )
+                )
+            )
+            AND ($2::INT IS NULL OR n.company_id = $2)
+            AND ($3::INT IS NULL OR n.project_id = $3)
+            AND ($4::INT IS NULL OR n.task_id = $4)
+            AND ($5::INT IS NULL OR n.subtask_id = $5)
+            AND ($6::TEXT IS NULL OR $6 = ANY(regexp_split_to_array(n.tags, '\s+')))
+            AND ($7::INT IS NULL OR n.user_id = $7)
+            AND ($8::TIMESTAMPTZ IS NULL OR n.created_at >= $8)
+            AND ($9::TIMESTAMPTZ IS NULL OR n.created_at < $9)
+        ORDER BY
+            n.created_at DESC, n.id DESC
+        LIMIT $10
+        OFFSET $11;
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                user_id.into(),             // $1 - User ID
+                filter.company_id.into(),   // $2 - Company ID filter
+                filter.project_id.into(),   // $3 - Project ID filter
+                filter.task_id.into(),      // $4 - Task ID filter
+                filter.subtask_id.into(),   // $5 - Subtask ID filter
+                filter.tag.into(),          // $6 - Tag filter
+                filter.author_id.into(),    // $7 - Author filter
+                filter.created_from.into(), // $8 - Created at or after
+                filter.created_to.into(),   // $9 - Created before
+                (limit as i64).into(),      // $10 - Page size
+                (offset as i64).into(),     // $11 - Offset
+                ADMINISTRATOR_LEVEL.into(), // $12 - Least privileged role level with access
+            ],
+        );
+
+        let notes = notes::Entity::find().from_raw_sql(stmt).all(db).await?;
+
+        Ok(notes)
+    }
+
+    /// Updates the editable fields of a note.
     ///
-    /// This function checks if the note exists before attempting to delete it. If the note exists,
-    /// it is deleted. If the note does not exist, an error is returned.
+    /// Only the fields passed as `Some` are changed.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `note` - The note to update.
+    /// * `content` - The new content, if it should change.
+    /// * `tags` - The new tags, if they should change.
+    /// * `decoration_color` - The new decoration color, if it should change.
+    ///
+    /// # Returns
+    /// * `Result<notes::Model, CoreErrors>` - Returns the updated note.
+    pub async fn update_note(
+        db: &DbConn,
+        note: notes::Model,
+        content: Option<String>,
+        tags: Option<String>,
+        decoration_color: Option<String>,
+    ) -> Result<notes::Model, CoreErrors> {
+        let mut active_note = note.into_active_model();
+
+        if let Some(content) = content {
+            active_note.content = Set(content);
+        }
+
+        if let Some(tags) = tags {
+            active_note.tags = Set(Some(tags));
+        }
+
+        if let Some(decoration_color) = decoration_color {
+            active_note.decoration_color = Set(Some(decoration_color));
+        }
+
+        let updated_note = active_note.update(db).await?;
+
+        Ok(updated_note)
+    }
+
+    /// Moves a note to the trash.
     ///
     /// # Arguments
     /// * `db` - A reference to the database connection.
@@ -160,31 +313,35 @@ impl NotesQuery {
     ///
     /// # Returns
     /// * `Result<(), CoreErrors>` - Returns `Ok(())` if the note is successfully deleted,
-    /// or an appropriate error if the note does not exist or the deletion fails.
+    ///   or an appropriate error if the note does not exist or the deletion fails.
     ///
     /// # Errors
     /// * Returns `CoreErrors::DatabaseServiceError` if the note does not exist.
     /// * Returns `CoreErrors` for any database operation failures.
     pub async fn delete_note(db: &DbConn, note_id: i32) -> Result<(), CoreErrors> {
-        // Step 1: Attempt to find the note by its ID
-        match notes::Entity::find_by_id(note_id).one(db).await? {
-            Some(note) => {
-                // Step 2: Convert the found note entity into an active model for deletion
-                let active_note = note.into_active_model();
-
-                // Step 3: Delete the note record from the database
-                active_note.delete(db).await?;
-            }
-            None => {
-                // Step 4: Return an error if the note does not exist
-                return Err(CoreErrors::DatabaseServiceError(format!(
-                    "Note with ID {} does not exist",
-                    note_id
-                )));
-            }
+        let sql = r#"
+        UPDATE notes SET deleted_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND deleted_at IS NULL;
+    "#;
+
+        // Step 1: Mark the note as deleted
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                note_id.into(), // $1 - Note ID
+            ],
+        );
+        let result = db.execute(stmt).await?;
+
+        // Step 2: Return an error if the note does not exist or is already in the trash
+        if result.rows_affected() == 0 {
+            return Err(CoreErrors::DatabaseServiceError(format!(
+                "Note with ID {} does not exist",
+                note_id
+            )));
         }
 
-        // Step 5: Return success if the note was deleted successfully
         Ok(())
     }
 }
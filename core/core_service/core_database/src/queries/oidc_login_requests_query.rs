@@ -0,0 +1,71 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, sea_query::Expr, ActiveModelTrait, ColumnTrait, DbConn,
+    EntityTrait, QueryFilter, Set,
+};
+
+use crate::entity::{oidc_login_requests, prelude};
+
+/// Provides methods for starting and completing OpenID Connect logins.
+pub struct OidcLoginRequestsQuery;
+
+impl OidcLoginRequestsQuery {
+    /// Stores a pending OpenID Connect login.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `state_hash` - The hash of the `state` parameter sent to the identity provider.
+    /// * `nonce` - The nonce the ID token must carry.
+    /// * `code_verifier` - The PKCE code verifier the authorization code is redeemed with.
+    /// * `expires_at` - The moment the login can no longer be completed.
+    ///
+    /// # Returns
+    /// * `Result<oidc_login_requests::Model, CoreErrors>` - Returns the stored login request.
+    pub async fn create_login_request(
+        db: &DbConn,
+        state_hash: String,
+        nonce: String,
+        code_verifier: String,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<oidc_login_requests::Model, CoreErrors> {
+        let new_request = oidc_login_requests::ActiveModel {
+            state_hash: Set(state_hash),
+            nonce: Set(nonce),
+            code_verifier: Set(code_verifier),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        };
+
+        let login_request = new_request.insert(db).await?;
+
+        Ok(login_request)
+    }
+
+    /// Marks an unused, unexpired login request as used.
+    ///
+    /// The check and the update happen in one statement, so a login can't be completed twice.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `state_hash` - The hash of the `state` returned by the identity provider.
+    ///
+    /// # Returns
+    /// * `Result<Option<oidc_login_requests::Model>, CoreErrors>` - Returns the login request, or `None` if the state is unknown, used or expired.
+    pub async fn consume_login_request(
+        db: &DbConn,
+        state_hash: &str,
+    ) -> Result<Option<oidc_login_requests::Model>, CoreErrors> {
+        let login_requests = prelude::OidcLoginRequests::update_many()
+            .col_expr(
+                oidc_login_requests::Column::UsedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(oidc_login_requests::Column::StateHash.eq(state_hash))
+            .filter(oidc_login_requests::Column::UsedAt.is_null())
+            .filter(Expr::col(oidc_login_requests::Column::ExpiresAt).gt(Expr::current_timestamp()))
+            .exec_with_returning(db)
+            .await?;
+
+        Ok(login_requests.into_iter().next())
+    }
+}
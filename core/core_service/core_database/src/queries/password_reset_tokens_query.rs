@@ -0,0 +1,81 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, sea_query::Expr, ActiveModelTrait, ColumnTrait, DbConn,
+    EntityTrait, QueryFilter, Set,
+};
+
+use crate::entity::{password_reset_tokens, prelude};
+
+/// Provides methods for issuing and redeeming password reset tokens.
+pub struct PasswordResetTokensQuery;
+
+impl PasswordResetTokensQuery {
+    /// Stores a new password reset token and invalidates the user's earlier unused ones.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user the token was issued to.
+    /// * `token_hash` - The hash of the token; the token itself is never stored.
+    /// * `expires_at` - The moment the token expires.
+    ///
+    /// # Returns
+    /// * `Result<password_reset_tokens::Model, CoreErrors>` - Returns the stored token record.
+    pub async fn create_reset_token(
+        db: &DbConn,
+        user_id: i32,
+        token_hash: String,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<password_reset_tokens::Model, CoreErrors> {
+        // Only the most recently requested link stays valid
+        prelude::PasswordResetTokens::update_many()
+            .col_expr(
+                password_reset_tokens::Column::UsedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(password_reset_tokens::Column::UserId.eq(user_id))
+            .filter(password_reset_tokens::Column::UsedAt.is_null())
+            .exec(db)
+            .await?;
+
+        let new_token = password_reset_tokens::ActiveModel {
+            user_id: Set(user_id),
+            token_hash: Set(token_hash),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        };
+
+        let token = new_token.insert(db).await?;
+
+        Ok(token)
+    }
+
+    /// Marks an unused, unexpired password reset token as used.
+    ///
+    /// The check and the update happen in one statement, so a token can't be redeemed twice.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `token_hash` - The hash of the presented token.
+    ///
+    /// # Returns
+    /// * `Result<Option<i32>, CoreErrors>` - Returns the ID of the token's user, or `None` if the token is unknown, used or expired.
+    pub async fn consume_reset_token(
+        db: &DbConn,
+        token_hash: &str,
+    ) -> Result<Option<i32>, CoreErrors> {
+        let tokens = prelude::PasswordResetTokens::update_many()
+            .col_expr(
+                password_reset_tokens::Column::UsedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(password_reset_tokens::Column::TokenHash.eq(token_hash))
+            .filter(password_reset_tokens::Column::UsedAt.is_null())
+            .filter(
+                Expr::col(password_reset_tokens::Column::ExpiresAt).gt(Expr::current_timestamp()),
+            )
+            .exec_with_returning(db)
+            .await?;
+
+        Ok(tokens.first().map(|token| token.user_id))
+    }
+}
@@ -2,12 +2,14 @@ use core_error::core_errors::CoreErrors;
 
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend, DbBackend, DbConn,
-    DeleteResult, EntityTrait, FromQueryResult, IntoActiveModel, QueryFilter, Set, Statement,
+    EntityTrait, FromQueryResult, IntoActiveModel, QueryFilter, Set, Statement,
 };
 
 use crate::entity::sea_orm_active_enums::AccessLevelType;
 use crate::entity::{projects, user_access};
 
+use super::roles_query::{role_not_above, ADMINISTRATOR_LEVEL};
+
 /// Represents a project associated with a user, along with the user's role in that project.
 pub struct UserProject {
     pub id: i32,
@@ -24,6 +26,19 @@ pub struct UserProjectRole {
     pub name: String,
 }
 
+/// A user with access to a project together with their role.
+#[derive(Debug, FromQueryResult)]
+pub struct ProjectMember {
+    pub user_id: i32,
+    pub login: String,
+    pub user_name: String,
+    pub email: String,
+    pub is_active: bool,
+    pub role_id: i32,
+    /// `true` if the access comes from the user's company role rather than the project itself.
+    pub inherited: bool,
+}
+
 #[derive(Debug, FromQueryResult)]
 struct UserProjectQueryResult {
     // Fields from user_projects
@@ -109,7 +124,7 @@ impl ProjectQuery {
     /// * `description` - A description of the project.
     /// * `decoration_color` - A decoration color for the project.
     /// * `user_id` - The ID of the user creating the project.
-    /// * `role_id` - The role ID of the user. If the role ID is 3 or higher, user access is added.
+    /// * `role_id` - The company role of the user. Unless it grants access to all projects, the user is given access to the new project.
     ///
     /// # Returns
     ///
@@ -122,22 +137,22 @@ impl ProjectQuery {
         description: String,
         decoration_color: String,
         user_id: i32,
-        role_id: i32, // If role_id >= 3, add an entry in UserAccess
+        role_id: i32,
     ) -> Result<projects::Model, CoreErrors> {
         // SQL statement using Common Table Expressions (CTEs)
         let sql = r#"
         WITH new_project AS (
             INSERT INTO projects (company_id, title, description, decoration_color, created_at, updated_at)
             VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP AT TIME ZONE 'UTC', CURRENT_TIMESTAMP AT TIME ZONE 'UTC')
-            RETURNING id, company_id, title, description, decoration_color, created_at, updated_at
+            RETURNING id, company_id, title, description, decoration_color, created_at, updated_at, archived_at, deleted_at
         ),
         user_access AS (
             INSERT INTO user_access (user_id, company_id, project_id, role_id, access_level, created_at)
             SELECT $5, NULL, id, 1, 'full', CURRENT_TIMESTAMP AT TIME ZONE 'UTC'
             FROM new_project
-            WHERE $6 >= 3 -- Add to UserAccess only if role_id is 3 or higher
+            WHERE role_effective_level($6) > $7 -- Add to UserAccess only if the role doesn't see all projects
         )
-        SELECT id, company_id, title, description, decoration_color, created_at, updated_at
+        SELECT id, company_id, title, description, decoration_color, created_at, updated_at, archived_at, deleted_at
         FROM new_project;
     "#;
 
@@ -146,12 +161,13 @@ impl ProjectQuery {
             DatabaseBackend::Postgres,
             sql,
             vec![
-                company_id.into(),       // $1 - Company ID
-                title.into(),            // $2 - Project title
-                description.into(),      // $3 - Project description
-                decoration_color.into(), // $4 - Decoration color
-                user_id.into(),          // $5 - User ID
-                role_id.into(),          // $6 - Role ID
+                company_id.into(),          // $1 - Company ID
+                title.into(),               // $2 - Project title
+                description.into(),         // $3 - Project description
+                decoration_color.into(),    // $4 - Decoration color
+                user_id.into(),             // $5 - User ID
+                role_id.into(),             // $6 - Role ID
+                ADMINISTRATOR_LEVEL.into(), // $7 - Least privileged company role level with access to all projects
             ],
         );
 
@@ -168,6 +184,8 @@ impl ProjectQuery {
                 decoration_color: row.try_get("", "decoration_color")?, // Decoration color (optional)
                 created_at: row.try_get("", "created_at")?,             // Creation timestamp
                 updated_at: row.try_get("", "updated_at")?,             // Update timestamp
+                archived_at: row.try_get("", "archived_at")?, // Archive timestamp (optional)
+                deleted_at: row.try_get("", "deleted_at")?,   // Deletion timestamp (optional)
             }
         } else {
             return Err(CoreErrors::DatabaseServiceError(
@@ -179,10 +197,34 @@ impl ProjectQuery {
         Ok(project)
     }
 
+    /// Retrieves a project by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - A reference to the database connection.
+    /// * `project_id` - The ID of the project.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<projects::Model>, CoreErrors>` - Returns the project if it exists and is not in the trash,
+    ///   otherwise `None`.
+    pub async fn get_project_by_id(
+        db: &DbConn,
+        project_id: i32,
+    ) -> Result<Option<projects::Model>, CoreErrors> {
+        let project = projects::Entity::find_by_id(project_id)
+            .filter(projects::Column::DeletedAt.is_null())
+            .one(db)
+            .await?;
+
+        Ok(project)
+    }
+
     /// Retrieves a user's role and project details for a specific project.
     ///
     /// This function queries both the `user_company` and `user_access` tables to determine the user's role
     /// and their association with the specified project. It returns the user's role and project details if found.
+    /// Projects in the trash are not found.
     ///
     /// # Arguments
     ///
@@ -216,7 +258,8 @@ impl ProjectQuery {
             JOIN projects p ON uc.company_id = p.company_id
             WHERE uc.user_id = $1 
               AND p.id = $2 
-              AND uc.role_id <= 2
+              AND role_effective_level(r.id) <= $3
+              AND p.deleted_at IS NULL
 
             UNION ALL
 
@@ -232,7 +275,7 @@ impl ProjectQuery {
             FROM user_access ua
             JOIN roles r ON ua.role_id = r.id
             JOIN projects p ON ua.project_id = p.id
-            WHERE ua.user_id = $1 AND ua.project_id = $2
+            WHERE ua.user_id = $1 AND ua.project_id = $2 AND p.deleted_at IS NULL
         )
         SELECT 
             ur.project_id AS project_id,
@@ -250,7 +293,11 @@ impl ProjectQuery {
         let stmt = Statement::from_sql_and_values(
             DbBackend::Postgres,
             sql,
-            [user_id.into(), project_id.into()],
+            [
+                user_id.into(),             // $1 - User ID
+                project_id.into(),          // $2 - Project ID
+                ADMINISTRATOR_LEVEL.into(), // $3 - Least privileged company role level with access to all projects
+            ],
         );
 
         // Step 2: Execute the query and retrieve the result
@@ -336,7 +383,7 @@ impl ProjectQuery {
     /// * `db` - A reference to the database connection.
     /// * `user_id` - The ID of the user to be removed from the project.
     /// * `project_id` - The ID of the project from which the user is being removed.
-    /// * `request_user_id_lvl` - The role level of the requester. If provided, limits the deletion to associations with a lower or equal role level.
+    /// * `requester_role_id` - The role of the requester. If provided, limits the deletion to associations whose role is not more privileged than it.
     ///
     /// # Returns
     ///
@@ -346,16 +393,19 @@ impl ProjectQuery {
         db: &DbConn,
         user_id: i32,
         project_id: i32,
-        request_user_id_lvl: Option<i32>,
+        requester_role_id: Option<i32>,
     ) -> Result<(), CoreErrors> {
         // Step 1: Check if the user has an existing association with the specified project
-        let existing_access = match request_user_id_lvl {
-            Some(level) => {
-                // Restrict deletion to associations with a role level less than or equal to the requester's level
+        let existing_access = match requester_role_id {
+            Some(requester_role_id) => {
+                // Restrict deletion to associations whose role is not more privileged than the requester's
                 user_access::Entity::find()
                     .filter(user_access::Column::UserId.eq(user_id))
                     .filter(user_access::Column::ProjectId.eq(project_id)) // Corrected `CompanyId` to `ProjectId`
-                    .filter(user_access::Column::RoleId.gte(level))
+                    .filter(role_not_above(
+                        user_access::Column::RoleId,
+                        requester_role_id,
+                    ))
                     .one(db)
                     .await?
             }
@@ -390,68 +440,66 @@ impl ProjectQuery {
         Ok(())
     }
 
-    /// Deletes a specified project from the database if it exists.
+    /// Moves a project to the trash together with its tasks and notes.
     ///
-    /// This function checks if the project with the specified ID exists in the database. If found, it deletes the project record.
-    /// If the project does not exist, it returns an error indicating the absence of the specified project.
+    /// The project and everything deleted along with it share the same `deleted_at` timestamp,
+    /// so restoring the project brings back exactly what was deleted with it. Access entries are kept
+    /// until the project is purged from the trash.
     ///
     /// # Arguments
     ///
-    /// * `db` - A reference to the database connection.
+    /// * `db` - A reference to the database connection or an open transaction.
     /// * `project_id` - The ID of the project to be deleted.
     ///
     /// # Returns
     ///
     /// * `Result<(), CoreErrors>` - Returns `Ok(())` if the project was successfully deleted,
     ///   or a `CoreErrors::DatabaseServiceError` if the project does not exist or another error occurs.
-    pub async fn delete_project(db: &DbConn, project_id: i32) -> Result<(), CoreErrors> {
-        // Step 1: Attempt to find the project by its ID
-        let project = projects::Entity::find_by_id(project_id).one(db).await?;
-
-        // Step 2: If the project exists, delete it; otherwise, return an error
-        match project {
-            Some(project) => {
-                // Convert the project entity into an active model for deletion
-                let active_project = project.into_active_model();
-
-                // Attempt to delete the project record from the database
-                active_project.delete(db).await?;
-            }
-            None => {
-                // Return an error if the project does not exist
-                return Err(CoreErrors::DatabaseServiceError(format!(
-                    "Project with ID {} does not exist",
-                    project_id
-                )));
-            }
-        };
-
-        // Step 3: Return success if the deletion was successful
-        Ok(())
-    }
-
-    /// Deletes all user associations from a specified project.
-    ///
-    /// This function removes all records in the `user_access` table for the given project ID,
-    /// effectively removing all users associated with the project.
-    ///
-    /// # Arguments
-    ///
-    /// * `db` - A reference to the database connection.
-    /// * `project_id` - The ID of the project whose user associations should be deleted.
-    ///
-    /// # Returns
-    ///
-    /// * `Result<(), CoreErrors>` - Returns `Ok(())` if the associations were successfully deleted,
-    ///   or a `CoreErrors` error if the operation fails.
-    pub async fn delete_all_users_from_project(
-        db: &DbConn,
+    pub async fn delete_project(
+        db: &impl ConnectionTrait,
         project_id: i32,
     ) -> Result<(), CoreErrors> {
-        let _: DeleteResult = user_access::Entity::delete_many()
-            .filter(user_access::Column::ProjectId.eq(project_id)) // Filter by project ID
-            .exec(db)
-            .await?;
+        // CURRENT_TIMESTAMP is fixed for the whole statement, so all rows get the same deletion time
+        let sql = r#"
+        WITH deleted_tasks AS (
+            UPDATE tasks SET deleted_at = CURRENT_TIMESTAMP
+            WHERE project_id = $1
+                AND deleted_at IS NULL
+                AND EXISTS (SELECT 1 FROM projects WHERE id = $1 AND deleted_at IS NULL)
+            RETURNING id
+        ),
+        deleted_notes AS (
+            UPDATE notes SET deleted_at = CURRENT_TIMESTAMP
+            WHERE deleted_at IS NULL
+                AND (
+                    (project_id = $1 AND EXISTS (SELECT 1 FROM projects WHERE id = $1 AND deleted_at IS NULL))
+                    OR task_id IN (SELECT id FROM deleted_tasks)
+                    OR subtask_id IN (
+                        SELECT id FROM subtasks WHERE task_id IN (SELECT id FROM deleted_tasks)
+                    )
+                )
+        )
+        UPDATE projects SET deleted_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND deleted_at IS NULL;
+    "#;
+
+        // Step 1: Mark the project and its content as deleted in a single statement
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                project_id.into(), // $1 - Project ID
+            ],
+        );
+        let result = db.execute(stmt).await?;
+
+        // Step 2: Return an error if the project does not exist or is already in the trash
+        if result.rows_affected() == 0 {
+            return Err(CoreErrors::DatabaseServiceError(format!(
+                "Project with ID {} does not exist",
+                project_id
+            )));
+        }
 
         Ok(())
     }
@@ -459,17 +507,19 @@ impl ProjectQuery {
     /// Retrieves all projects within a company that a user has access to.
     ///
     /// This function executes a SQL query to fetch projects that the user can access, either
-    /// due to full company access (`role_id <= 2` in `user_company`) or explicit assignment
+    /// due to full company access (at least "Administrator" in `user_company`) or explicit assignment
     /// in the `user_access` table for projects within the specified company.
+    /// Archived projects are only included if `include_archived` is set.
     ///
     /// # Arguments
     /// * `db` - A reference to the database connection.
     /// * `company_id` - The ID of the company whose projects are to be retrieved.
     /// * `user_id` - The ID of the user for whom access is being checked.
+    /// * `include_archived` - Whether archived projects are included.
     ///
     /// # Returns
     /// * `Result<Vec<projects::Model>, CoreErrors>` - Returns a list of `projects::Model` on success,
-    /// or an error if the query fails.
+    ///   or an error if the query fails.
     ///
     /// # Errors
     /// * Returns `CoreErrors` for any database operation failures.
@@ -477,6 +527,7 @@ impl ProjectQuery {
         db: &DbConn,
         company_id: i32,
         user_id: i32,
+        include_archived: bool,
     ) -> Result<Vec<projects::Model>, CoreErrors> {
         // SQL query to fetch all accessible projects within the specified company for the user
         let sql = r#"
@@ -487,19 +538,24 @@ impl ProjectQuery {
             p.description AS description,
             p.decoration_color AS decoration_color,
             p.created_at AS created_at,
-            p.updated_at AS updated_at
+            p.updated_at AS updated_at,
+            p.archived_at AS archived_at,
+            p.deleted_at AS deleted_at
         FROM 
             projects p
         WHERE 
             p.company_id = $1
+            AND p.deleted_at IS NULL
+            AND ($4 OR p.archived_at IS NULL)
             AND (
-                -- Full access to the company (role_id <= 2 in user_company)
+                -- Full access to the company (effective role level <= $3 in user_company)
                 EXISTS (
                     SELECT 1 
                     FROM user_company uc 
+                    JOIN roles r ON uc.role_id = r.id
                     WHERE uc.user_id = $2 
                       AND uc.company_id = $1
-                      AND uc.role_id <= 2
+                      AND role_effective_level(r.id) <= $3
                 )
                 OR 
                 -- Explicit user access to the project
@@ -517,8 +573,10 @@ impl ProjectQuery {
             DbBackend::Postgres,
             sql,
             vec![
-                company_id.into(), // $1 - Company ID
-                user_id.into(),    // $2 - User ID
+                company_id.into(),          // $1 - Company ID
+                user_id.into(),             // $2 - User ID
+                ADMINISTRATOR_LEVEL.into(), // $3 - Least privileged company role level with access to all projects
+                include_archived.into(),    // $4 - Whether archived projects are included
             ],
         );
 
@@ -529,4 +587,173 @@ impl ProjectQuery {
         // Step 3: Return the list of projects
         Ok(projects)
     }
+    /// Updates the title, description and decoration color of a project.
+    ///
+    /// Fields that are `None` are left unchanged.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `project_id` - The ID of the project.
+    /// * `title` - The new title of the project.
+    /// * `description` - The new description of the project.
+    /// * `decoration_color` - The new decoration color of the project.
+    ///
+    /// # Returns
+    /// * `Result<Option<projects::Model>, CoreErrors>` - Returns the updated project, or `None` if it does not exist.
+    pub async fn update_project(
+        db: &DbConn,
+        project_id: i32,
+        title: Option<String>,
+        description: Option<String>,
+        decoration_color: Option<String>,
+    ) -> Result<Option<projects::Model>, CoreErrors> {
+        let sql = r#"
+        UPDATE projects
+        SET
+            title = COALESCE($2, title),
+            description = COALESCE($3, description),
+            decoration_color = COALESCE($4, decoration_color),
+            updated_at = CURRENT_TIMESTAMP AT TIME ZONE 'UTC'
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING id, company_id, title, description, decoration_color, created_at, updated_at, archived_at, deleted_at;
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                project_id.into(),       // $1 - Project ID
+                title.into(),            // $2 - New project title
+                description.into(),      // $3 - New project description
+                decoration_color.into(), // $4 - New decoration color
+            ],
+        );
+
+        let project = projects::Entity::find().from_raw_sql(stmt).one(db).await?;
+
+        Ok(project)
+    }
+
+    /// Archives or unarchives a project.
+    ///
+    /// Archiving an archived project keeps the time it was first archived.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `project_id` - The ID of the project.
+    /// * `archived` - Whether the project is archived.
+    ///
+    /// # Returns
+    /// * `Result<Option<projects::Model>, CoreErrors>` - Returns the updated project, or `None` if it does not exist.
+    pub async fn set_project_archived(
+        db: &DbConn,
+        project_id: i32,
+        archived: bool,
+    ) -> Result<Option<projects::Model>, CoreErrors> {
+        let sql = r#"
+        UPDATE projects
+        SET
+            archived_at = CASE WHEN $2 THEN COALESCE(archived_at, CURRENT_TIMESTAMP) ELSE NULL END,
+            updated_at = CURRENT_TIMESTAMP AT TIME ZONE 'UTC'
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING id, company_id, title, description, decoration_color, created_at, updated_at, archived_at, deleted_at;
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                project_id.into(), // $1 - Project ID
+                archived.into(),   // $2 - Whether the project is archived
+            ],
+        );
+
+        let project = projects::Entity::find().from_raw_sql(stmt).one(db).await?;
+
+        Ok(project)
+    }
+
+    /// Retrieves the users with access to a project, most privileged roles first.
+    ///
+    /// Besides users added to the project in `user_access`, company members whose role grants access
+    /// to all projects are listed as inherited members. A user with both kinds of access is listed once
+    /// with the more privileged role.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `project_id` - The ID of the project.
+    /// * `limit` - The maximum number of members to return.
+    /// * `offset` - The number of members to skip.
+    ///
+    /// # Returns
+    /// * `Result<Vec<ProjectMember>, CoreErrors>` - Returns the members of the requested page.
+    pub async fn get_project_members(
+        db: &DbConn,
+        project_id: i32,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<ProjectMember>, CoreErrors> {
+        let sql = r#"
+        SELECT
+            user_id, login, user_name, email, is_active, role_id, inherited
+        FROM (
+            SELECT DISTINCT ON (m.user_id) m.*
+            FROM (
+                -- Users added to the project
+                SELECT
+                    u.id AS user_id,
+                    u.login,
+                    u.user_name,
+                    u.email,
+                    u.is_active,
+                    ua.role_id,
+                    role_effective_level(r.id) AS level,
+                    FALSE AS inherited
+                FROM user_access ua
+                JOIN users u ON u.id = ua.user_id
+                JOIN roles r ON r.id = ua.role_id
+                WHERE ua.project_id = $1
+
+                UNION ALL
+
+                -- Company members with access to all projects of the company
+                SELECT
+                    u.id AS user_id,
+                    u.login,
+                    u.user_name,
+                    u.email,
+                    u.is_active,
+                    uc.role_id,
+                    role_effective_level(r.id) AS level,
+                    TRUE AS inherited
+                FROM projects p
+                JOIN user_company uc ON uc.company_id = p.company_id
+                JOIN users u ON u.id = uc.user_id
+                JOIN roles r ON r.id = uc.role_id
+                WHERE p.id = $1
+                  AND role_effective_level(r.id) <= $2
+            ) m
+            ORDER BY m.user_id, m.level, m.inherited
+        ) members
+        ORDER BY
+            level, user_name, user_id
+        LIMIT $3
+        OFFSET $4;
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                project_id.into(),          // $1 - Project ID
+                ADMINISTRATOR_LEVEL.into(), // $2 - Least privileged company role level with access to all projects
+                (limit as i64).into(),      // $3 - Page size
+                (offset as i64).into(),     // $4 - Offset
+            ],
+        );
+
+        let members = ProjectMember::find_by_statement(stmt).all(db).await?;
+
+        Ok(members)
+    }
 }
@@ -0,0 +1,311 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait,
+    DbConn, EntityTrait, QueryFilter, Set,
+};
+
+use crate::{
+    entity::{prelude, refresh_tokens},
+    queries::sessions_query::SessionsQuery,
+};
+
+/// Provides methods for storing, rotating and revoking refresh tokens.
+pub struct RefreshTokensQuery;
+
+impl RefreshTokensQuery {
+    /// Stores a newly issued refresh token.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `user_id` - The ID of the user the token was issued to.
+    /// * `jti` - The unique ID of the token.
+    /// * `family_id` - The ID shared by all tokens rotated from the same login.
+    /// * `token_hash` - The hash of the token; the token itself is never stored.
+    /// * `device_info` - A description of the client the token was issued to, if known.
+    /// * `expires_at` - The moment the token expires.
+    ///
+    /// # Returns
+    /// * `Result<refresh_tokens::Model, CoreErrors>` - Returns the stored token record.
+    pub async fn create_refresh_token(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        jti: String,
+        family_id: String,
+        token_hash: String,
+        device_info: Option<String>,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<refresh_tokens::Model, CoreErrors> {
+        let new_token = refresh_tokens::ActiveModel {
+            user_id: Set(user_id),
+            jti: Set(jti),
+            family_id: Set(family_id),
+            token_hash: Set(token_hash),
+            device_info: Set(device_info),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        };
+
+        let token = new_token.insert(db).await?;
+
+        Ok(token)
+    }
+
+    /// Retrieves a refresh token record by the token's unique ID.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `jti` - The unique ID of the token.
+    ///
+    /// # Returns
+    /// * `Result<Option<refresh_tokens::Model>, CoreErrors>` - Returns the token record if it exists, otherwise `None`.
+    pub async fn get_refresh_token_by_jti(
+        db: &DbConn,
+        jti: &str,
+    ) -> Result<Option<refresh_tokens::Model>, CoreErrors> {
+        let token = prelude::RefreshTokens::find()
+            .filter(refresh_tokens::Column::Jti.eq(jti))
+            .one(db)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Marks a refresh token as used and records the token that replaced it.
+    ///
+    /// The update only succeeds while the token is still active, so two concurrent refreshes
+    /// with the same token cannot both rotate it.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `token_id` - The ID of the token record.
+    /// * `replaced_by` - The unique ID of the token issued in its place.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `true` if the token was rotated, `false` if it had already been revoked.
+    pub async fn rotate_refresh_token(
+        db: &impl ConnectionTrait,
+        token_id: i32,
+        replaced_by: String,
+    ) -> Result<bool, CoreErrors> {
+        let result = prelude::RefreshTokens::update_many()
+            .col_expr(
+                refresh_tokens::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .col_expr(refresh_tokens::Column::ReplacedBy, Expr::value(replaced_by))
+            .filter(refresh_tokens::Column::Id.eq(token_id))
+            .filter(refresh_tokens::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Replaces a refresh token with a newly issued one.
+    ///
+    /// Stores the new token, marks the presented one as replaced by it and extends the session
+    /// to the new token's expiry. Run it on a transaction, so a failure leaves the presented
+    /// token active and the session unchanged.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `token` - The stored record of the presented token.
+    /// * `jti` - The unique ID of the new token.
+    /// * `token_hash` - The hash of the new token.
+    /// * `device_info` - A description of the client the new token is issued to, if known.
+    /// * `expires_at` - The moment the new token expires.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false`, without storing the new token, if the presented
+    ///   token was rotated or revoked in the meantime.
+    pub async fn replace_refresh_token(
+        db: &impl ConnectionTrait,
+        token: &refresh_tokens::Model,
+        jti: String,
+        token_hash: String,
+        device_info: Option<String>,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<bool, CoreErrors> {
+        RefreshTokensQuery::create_refresh_token(
+            db,
+            token.user_id,
+            jti.clone(),
+            token.family_id.clone(),
+            token_hash,
+            device_info,
+            expires_at,
+        )
+        .await?;
+
+        // Another request rotated the token first; the caller drops the transaction to discard the new one
+        if !RefreshTokensQuery::rotate_refresh_token(db, token.id, jti).await? {
+            return Ok(false);
+        }
+
+        // The session lives as long as its newest refresh token
+        SessionsQuery::extend_session(db, &token.family_id, expires_at).await?;
+
+        Ok(true)
+    }
+
+    /// Revokes all active tokens of a token family.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `family_id` - The ID of the token family.
+    ///
+    /// # Returns
+    /// * `Result<u64, CoreErrors>` - Returns the number of revoked tokens.
+    pub async fn revoke_token_family(db: &DbConn, family_id: &str) -> Result<u64, CoreErrors> {
+        let result = prelude::RefreshTokens::update_many()
+            .col_expr(
+                refresh_tokens::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(refresh_tokens::Column::FamilyId.eq(family_id))
+            .filter(refresh_tokens::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// Revokes all active refresh tokens of a user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// * `Result<u64, CoreErrors>` - Returns the number of revoked tokens.
+    pub async fn revoke_all_user_tokens(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+    ) -> Result<u64, CoreErrors> {
+        let result = prelude::RefreshTokens::update_many()
+            .col_expr(
+                refresh_tokens::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(refresh_tokens::Column::UserId.eq(user_id))
+            .filter(refresh_tokens::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// Revokes all active refresh tokens of a user except those of one token family.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `user_id` - The ID of the user.
+    /// * `kept_family_id` - The ID of the token family that stays valid.
+    ///
+    /// # Returns
+    /// * `Result<u64, CoreErrors>` - Returns the number of revoked tokens.
+    pub async fn revoke_other_user_tokens(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        kept_family_id: &str,
+    ) -> Result<u64, CoreErrors> {
+        let result = prelude::RefreshTokens::update_many()
+            .col_expr(
+                refresh_tokens::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(refresh_tokens::Column::UserId.eq(user_id))
+            .filter(refresh_tokens::Column::FamilyId.ne(kept_family_id))
+            .filter(refresh_tokens::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+
+    use super::*;
+    use crate::test_support::{affected, executed_sql, failure};
+
+    fn token(id: i32, jti: &str) -> refresh_tokens::Model {
+        refresh_tokens::Model {
+            id,
+            user_id: 7,
+            jti: jti.to_string(),
+            family_id: "session".to_string(),
+            token_hash: format!("hash-{}", jti),
+            device_info: None,
+            expires_at: Default::default(),
+            created_at: Default::default(),
+            revoked_at: None,
+            replaced_by: None,
+        }
+    }
+
+    /// Replaces the token on a transaction, committing it as the service does.
+    async fn replace(db: &DbConn) -> Result<bool, CoreErrors> {
+        let txn = db.begin().await?;
+        let replaced = RefreshTokensQuery::replace_refresh_token(
+            &txn,
+            &token(1, "old"),
+            "new".to_string(),
+            "hash-new".to_string(),
+            None,
+            Default::default(),
+        )
+        .await?;
+        if replaced {
+            txn.commit().await?;
+        }
+
+        Ok(replaced)
+    }
+
+    #[tokio::test]
+    async fn replace_refresh_token_commits_all_steps() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![token(2, "new")]])
+            .append_exec_results([affected(1), affected(1)])
+            .into_connection();
+
+        assert!(replace(&db).await.unwrap());
+
+        let sql = executed_sql(db);
+        assert!(sql[1].contains(r#"INSERT INTO "refresh_tokens""#));
+        assert!(sql[3].contains(r#"UPDATE "sessions""#));
+        assert_eq!(sql.last().map(String::as_str), Some("COMMIT"));
+    }
+
+    #[tokio::test]
+    async fn replace_refresh_token_discards_the_new_token_if_already_rotated() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![token(2, "new")]])
+            .append_exec_results([affected(0)])
+            .into_connection();
+
+        assert!(!replace(&db).await.unwrap());
+
+        let sql = executed_sql(db);
+        assert_eq!(sql.len(), 4);
+        assert_eq!(sql.last().map(String::as_str), Some("ROLLBACK"));
+    }
+
+    #[tokio::test]
+    async fn replace_refresh_token_rolls_back_when_extending_the_session_fails() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![token(2, "new")]])
+            .append_exec_results([affected(1)])
+            .append_exec_errors([failure()])
+            .into_connection();
+
+        assert!(replace(&db).await.is_err());
+
+        let sql = executed_sql(db);
+        assert_eq!(sql.last().map(String::as_str), Some("ROLLBACK"));
+        assert!(!sql.iter().any(|sql| sql == "COMMIT"));
+    }
+}
@@ -0,0 +1,51 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    sea_query::{Expr, SimpleExpr},
+    ColumnTrait, DbConn, EntityTrait,
+};
+
+use crate::entity::roles;
+
+/// ID of the "Owner" role, which the creator of a company gets.
+pub const OWNER_ROLE_ID: i32 = 1;
+
+/// Level of the "Owner" role. Lower levels are more privileged.
+pub const OWNER_LEVEL: i32 = 1;
+/// Level of the "Administrator" role.
+pub const ADMINISTRATOR_LEVEL: i32 = 2;
+/// Level of the "Manager" role.
+pub const MANAGER_LEVEL: i32 = 3;
+/// Level of the "User" role.
+pub const USER_LEVEL: i32 = 4;
+/// Level of the "Support" role.
+pub const SUPPORT_LEVEL: i32 = 5;
+/// Level of the "Guest" role.
+pub const GUEST_LEVEL: i32 = 6;
+
+/// Matches the rows whose role in `role_column` is not more privileged than the role `role_id`.
+///
+/// Both roles are compared by their effective level, so privileges inherited from parent roles count.
+pub(crate) fn role_not_above<C: ColumnTrait>(role_column: C, role_id: i32) -> SimpleExpr {
+    Expr::cust_with_exprs(
+        "role_effective_level($1) >= role_effective_level($2)",
+        [role_column.into_expr().into(), Expr::val(role_id).into()],
+    )
+}
+
+/// Provides methods for querying roles.
+pub struct RolesQuery;
+
+impl RolesQuery {
+    /// Retrieves all roles together with their level and parent role.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    ///
+    /// # Returns
+    /// * `Result<Vec<roles::Model>, CoreErrors>` - Returns every role stored in the `roles` table.
+    pub async fn get_all_roles(db: &DbConn) -> Result<Vec<roles::Model>, CoreErrors> {
+        let roles = roles::Entity::find().all(db).await?;
+
+        Ok(roles)
+    }
+}
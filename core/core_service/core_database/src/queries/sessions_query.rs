@@ -0,0 +1,233 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait,
+    DbBackend, DbConn, EntityTrait, QueryFilter, QueryOrder, Set, Statement,
+};
+
+use crate::entity::{prelude, sessions};
+
+/// The state of the user owning an active session.
+#[derive(Clone, Copy, Debug)]
+pub struct SessionUser {
+    /// Whether the user has verified their email address.
+    pub email_verified: bool,
+    /// Whether the account is active; deactivated users must be rejected.
+    pub is_active: bool,
+}
+
+/// Provides methods for recording and revoking login sessions.
+pub struct SessionsQuery;
+
+impl SessionsQuery {
+    /// Records a new login session.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `session_id` - The unique ID of the session.
+    /// * `user_id` - The ID of the user who logged in.
+    /// * `device_info` - A description of the client (e.g. its user agent), if known.
+    /// * `ip_address` - The IP address the login came from, if known.
+    /// * `expires_at` - The moment the session expires unless it is refreshed.
+    ///
+    /// # Returns
+    /// * `Result<sessions::Model, CoreErrors>` - Returns the stored session.
+    pub async fn create_session(
+        db: &DbConn,
+        session_id: String,
+        user_id: i32,
+        device_info: Option<String>,
+        ip_address: Option<String>,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<sessions::Model, CoreErrors> {
+        let new_session = sessions::ActiveModel {
+            id: Set(session_id),
+            user_id: Set(user_id),
+            device_info: Set(device_info),
+            ip_address: Set(ip_address),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        };
+
+        let session = new_session.insert(db).await?;
+
+        Ok(session)
+    }
+
+    /// Marks an active session as used right now.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `session_id` - The ID of the session.
+    /// * `user_id` - The ID of the user owning the session.
+    ///
+    /// # Returns
+    /// * `Result<Option<SessionUser>, CoreErrors>` - Returns the state of the session's user, or `None` if the session does not exist, has expired or was revoked.
+    pub async fn touch_session(
+        db: &DbConn,
+        session_id: &str,
+        user_id: i32,
+    ) -> Result<Option<SessionUser>, CoreErrors> {
+        // Update the session and read its user in one round trip, since this runs on every request
+        let sql = r#"
+        UPDATE sessions
+        SET last_used_at = CURRENT_TIMESTAMP
+        FROM users
+        WHERE sessions.id = $1
+            AND sessions.user_id = $2
+            AND users.id = sessions.user_id
+            AND sessions.revoked_at IS NULL
+            AND sessions.expires_at > CURRENT_TIMESTAMP
+        RETURNING
+            users.email_verified_at IS NOT NULL AS email_verified,
+            users.is_active
+        "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [session_id.into(), user_id.into()],
+        );
+
+        let session_user = match db.query_one(stmt).await? {
+            Some(row) => Some(SessionUser {
+                email_verified: row.try_get("", "email_verified")?,
+                is_active: row.try_get("", "is_active")?,
+            }),
+            None => None,
+        };
+
+        Ok(session_user)
+    }
+
+    /// Extends an active session after its refresh token was rotated.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `session_id` - The ID of the session.
+    /// * `expires_at` - The new expiry of the session.
+    ///
+    /// # Returns
+    /// * `Result<(), CoreErrors>` - Returns `Ok(())` once the session is updated.
+    pub async fn extend_session(
+        db: &impl ConnectionTrait,
+        session_id: &str,
+        expires_at: DateTimeWithTimeZone,
+    ) -> Result<(), CoreErrors> {
+        prelude::Sessions::update_many()
+            .col_expr(
+                sessions::Column::LastUsedAt,
+                Expr::current_timestamp().into(),
+            )
+            .col_expr(sessions::Column::ExpiresAt, Expr::value(expires_at))
+            .filter(sessions::Column::Id.eq(session_id))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Retrieves the active sessions of a user, most recently used first.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// * `Result<Vec<sessions::Model>, CoreErrors>` - Returns the sessions that are neither revoked nor expired.
+    pub async fn get_active_user_sessions(
+        db: &DbConn,
+        user_id: i32,
+    ) -> Result<Vec<sessions::Model>, CoreErrors> {
+        let sessions = prelude::Sessions::find()
+            .filter(sessions::Column::UserId.eq(user_id))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .filter(Expr::col(sessions::Column::ExpiresAt).gt(Expr::current_timestamp()))
+            .order_by_desc(sessions::Column::LastUsedAt)
+            .all(db)
+            .await?;
+
+        Ok(sessions)
+    }
+
+    /// Revokes a session of a user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `session_id` - The ID of the session.
+    /// * `user_id` - The ID of the user owning the session.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the user has no active session with this ID.
+    pub async fn revoke_session(
+        db: &DbConn,
+        session_id: &str,
+        user_id: i32,
+    ) -> Result<bool, CoreErrors> {
+        let result = prelude::Sessions::update_many()
+            .col_expr(
+                sessions::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(sessions::Column::Id.eq(session_id))
+            .filter(sessions::Column::UserId.eq(user_id))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Revokes all active sessions of a user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// * `Result<u64, CoreErrors>` - Returns the number of revoked sessions.
+    pub async fn revoke_all_user_sessions(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+    ) -> Result<u64, CoreErrors> {
+        let result = prelude::Sessions::update_many()
+            .col_expr(
+                sessions::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(sessions::Column::UserId.eq(user_id))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// Revokes all active sessions of a user except one, e.g. after a password change.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `user_id` - The ID of the user.
+    /// * `kept_session_id` - The ID of the session that stays signed in.
+    ///
+    /// # Returns
+    /// * `Result<u64, CoreErrors>` - Returns the number of revoked sessions.
+    pub async fn revoke_other_user_sessions(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        kept_session_id: &str,
+    ) -> Result<u64, CoreErrors> {
+        let result = prelude::Sessions::update_many()
+            .col_expr(
+                sessions::Column::RevokedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(sessions::Column::UserId.eq(user_id))
+            .filter(sessions::Column::Id.ne(kept_session_id))
+            .filter(sessions::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
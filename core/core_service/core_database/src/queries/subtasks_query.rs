@@ -0,0 +1,329 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone,
+    sqlx::types::chrono::{DateTime, Utc},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, DbConn, EntityTrait,
+    FromQueryResult, IntoActiveModel, QueryFilter, Set, Statement,
+};
+
+use crate::entity::{sea_orm_active_enums::TaskStatusType, subtasks, tasks, user_access};
+
+#[derive(Debug, FromQueryResult)]
+pub struct SubtaskModelFromQueryResult {
+    pub id: i32,
+    pub task_id: i32,
+    pub assigned_to_id: Option<i32>,
+    pub assigned_to_name: Option<String>,
+    pub status: TaskStatusType,
+    pub title: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub due_date: Option<DateTime<Utc>>,
+}
+
+/// Provides methods for querying and manipulating subtasks.
+pub struct SubtasksQuery;
+
+impl SubtasksQuery {
+    /// Creates a new subtask under the specified task.
+    ///
+    /// The subtask is created unassigned and with the initial status `pending`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `task_id` - The ID of the parent task.
+    /// * `title` - The title of the subtask.
+    /// * `description` - A detailed description of the subtask.
+    /// * `due_date` - An optional due date for the subtask.
+    ///
+    /// # Returns
+    /// * `Result<subtasks::Model, CoreErrors>` - Returns the created subtask on success,
+    ///   or a `CoreErrors` variant if the insert fails.
+    pub async fn create_subtask(
+        db: &DbConn,
+        task_id: i32,
+        title: String,
+        description: String,
+        due_date: Option<DateTimeWithTimeZone>,
+    ) -> Result<subtasks::Model, CoreErrors> {
+        // Step 1: Define the new subtask with the initial 'pending' status
+        let new_subtask = subtasks::ActiveModel {
+            task_id: Set(task_id),                // Attach the subtask to its parent task
+            status: Set(TaskStatusType::Pending), // Every subtask starts as pending
+            title: Set(title),                    // Set the subtask title
+            description: Set(Some(description)),  // Set the subtask description
+            due_date: Set(due_date),              // Optional due date
+            ..Default::default()                  // Use default values for other fields
+        };
+
+        // Step 2: Insert the subtask and return the stored record
+        let inserted_subtask = new_subtask.insert(db).await?;
+
+        Ok(inserted_subtask)
+    }
+
+    /// Retrieves a subtask by its ID.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `subtask_id` - The ID of the subtask.
+    ///
+    /// # Returns
+    /// * `Result<Option<subtasks::Model>, CoreErrors>` - Returns the subtask if it exists and its task is not
+    ///   in the trash, otherwise `None`.
+    pub async fn get_subtask_by_id(
+        db: &DbConn,
+        subtask_id: i32,
+    ) -> Result<Option<subtasks::Model>, CoreErrors> {
+        let subtask = subtasks::Entity::find_by_id(subtask_id)
+            .inner_join(tasks::Entity)
+            .filter(tasks::Column::DeletedAt.is_null())
+            .one(db)
+            .await?;
+
+        Ok(subtask)
+    }
+
+    /// Retrieves the role a user was granted directly on a subtask through the `user_access` table.
+    ///
+    /// Used as a fallback when the user has no access to the parent task, since subtask-level
+    /// access grants permissions only to that specific subtask.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    /// * `subtask_id` - The ID of the subtask.
+    ///
+    /// # Returns
+    /// * `Result<Option<i32>, CoreErrors>` - Returns the user's role ID on the subtask, or `None` if no access exists.
+    pub async fn get_user_subtask_access_lvl(
+        db: &DbConn,
+        user_id: i32,
+        subtask_id: i32,
+    ) -> Result<Option<i32>, CoreErrors> {
+        let access = user_access::Entity::find()
+            .filter(user_access::Column::UserId.eq(user_id)) // Filter by user ID
+            .filter(user_access::Column::SubtaskId.eq(subtask_id)) // Filter by subtask ID
+            .one(db)
+            .await?;
+
+        Ok(access.and_then(|access| access.role_id))
+    }
+
+    /// Retrieves a subtask together with the name of the assigned user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `subtask_id` - The ID of the subtask.
+    ///
+    /// # Returns
+    /// * `Result<Option<SubtaskModelFromQueryResult>, CoreErrors>` - Returns the subtask if it exists and its task
+    ///   is not in the trash, otherwise `None`.
+    pub async fn get_subtask_with_assignee(
+        db: &DbConn,
+        subtask_id: i32,
+    ) -> Result<Option<SubtaskModelFromQueryResult>, CoreErrors> {
+        let sql = r#"
+        SELECT
+            s.id AS id,
+            s.task_id AS task_id,
+            u.id AS assigned_to_id,
+            u.user_name AS assigned_to_name,
+            s.status::TEXT AS status,
+            s.title AS title,
+            s.description AS description,
+            s.created_at AS created_at,
+            s.due_date AS due_date
+        FROM
+            subtasks s
+        JOIN
+            tasks t ON t.id = s.task_id AND t.deleted_at IS NULL
+        LEFT JOIN
+            users u ON s.assigned_to = u.id
+        WHERE
+            s.id = $1;
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                subtask_id.into(), // $1 - Subtask ID
+            ],
+        );
+
+        let subtask = SubtaskModelFromQueryResult::find_by_statement(stmt)
+            .one(db)
+            .await?;
+
+        Ok(subtask)
+    }
+
+    /// Retrieves all subtasks of a task together with the names of the assigned users.
+    ///
+    /// Access to the parent task must be verified by the caller before invoking this function.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `task_id` - The ID of the parent task.
+    ///
+    /// # Returns
+    /// * `Result<Vec<SubtaskModelFromQueryResult>, CoreErrors>` - Returns the subtasks ordered by creation date.
+    pub async fn get_all_task_subtasks(
+        db: &DbConn,
+        task_id: i32,
+    ) -> Result<Vec<SubtaskModelFromQueryResult>, CoreErrors> {
+        let sql = r#"
+        SELECT
+            s.id AS id,
+            s.task_id AS task_id,
+            u.id AS assigned_to_id,
+            u.user_name AS assigned_to_name,
+            s.status::TEXT AS status,
+            s.title AS title,
+            s.description AS description,
+            s.created_at AS created_at,
+            s.due_date AS due_date
+        FROM
+            subtasks s
+        LEFT JOIN
+            users u ON s.assigned_to = u.id
+        WHERE
+            s.task_id = $1
+        ORDER BY
+            s.created_at, s.id;
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                task_id.into(), // $1 - Task ID
+            ],
+        );
+
+        let subtasks = SubtaskModelFromQueryResult::find_by_statement(stmt)
+            .all(db)
+            .await?;
+
+        Ok(subtasks)
+    }
+
+    /// Updates the editable fields of a subtask.
+    ///
+    /// Only the fields passed as `Some` are changed; `None` leaves the stored value untouched.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `subtask` - The subtask to update.
+    /// * `title` - The new title, if it should change.
+    /// * `description` - The new description, if it should change.
+    /// * `due_date` - The new due date, if it should change.
+    ///
+    /// # Returns
+    /// * `Result<subtasks::Model, CoreErrors>` - Returns the updated subtask.
+    pub async fn update_subtask(
+        db: &DbConn,
+        subtask: subtasks::Model,
+        title: Option<String>,
+        description: Option<String>,
+        due_date: Option<DateTimeWithTimeZone>,
+    ) -> Result<subtasks::Model, CoreErrors> {
+        let mut active_subtask = subtask.into_active_model();
+
+        if let Some(title) = title {
+            active_subtask.title = Set(title);
+        }
+
+        if let Some(description) = description {
+            active_subtask.description = Set(Some(description));
+        }
+
+        if let Some(due_date) = due_date {
+            active_subtask.due_date = Set(Some(due_date));
+        }
+
+        let updated_subtask = active_subtask.update(db).await?;
+
+        Ok(updated_subtask)
+    }
+
+    /// Assigns a subtask to a user, or clears the assignment when `user_id` is `None`.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `subtask` - The subtask to (un)assign.
+    /// * `user_id` - The ID of the new assignee, or `None` to unassign.
+    ///
+    /// # Returns
+    /// * `Result<subtasks::Model, CoreErrors>` - Returns the updated subtask.
+    pub async fn set_subtask_assignee(
+        db: &DbConn,
+        subtask: subtasks::Model,
+        user_id: Option<i32>,
+    ) -> Result<subtasks::Model, CoreErrors> {
+        let mut active_subtask = subtask.into_active_model();
+        active_subtask.assigned_to = Set(user_id);
+
+        let updated_subtask = active_subtask.update(db).await?;
+
+        Ok(updated_subtask)
+    }
+
+    /// Changes the status of a subtask.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `subtask` - The subtask to update.
+    /// * `status` - The new status.
+    ///
+    /// # Returns
+    /// * `Result<subtasks::Model, CoreErrors>` - Returns the updated subtask.
+    pub async fn change_subtask_status(
+        db: &DbConn,
+        subtask: subtasks::Model,
+        status: TaskStatusType,
+    ) -> Result<subtasks::Model, CoreErrors> {
+        let mut active_subtask = subtask.into_active_model();
+        active_subtask.status = Set(status);
+
+        let updated_subtask = active_subtask.update(db).await?;
+
+        Ok(updated_subtask)
+    }
+
+    /// Deletes a subtask from the database by its ID.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `subtask_id` - The ID of the subtask to be deleted.
+    ///
+    /// # Returns
+    /// * `Result<(), CoreErrors>` - Returns `Ok(())` if the subtask is successfully deleted,
+    ///   or an appropriate error if the subtask does not exist or the deletion fails.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::DatabaseServiceError` if the subtask does not exist.
+    /// * Returns `CoreErrors` for any database operation failures.
+    pub async fn delete_subtask(
+        db: &impl ConnectionTrait,
+        subtask_id: i32,
+    ) -> Result<(), CoreErrors> {
+        // Step 1: Attempt to find the subtask by its ID
+        match subtasks::Entity::find_by_id(subtask_id).one(db).await? {
+            Some(subtask) => {
+                // Step 2: Delete the subtask record from the database
+                subtask.into_active_model().delete(db).await?;
+            }
+            None => {
+                // Step 3: Return an error if the subtask does not exist
+                return Err(CoreErrors::DatabaseServiceError(format!(
+                    "Subtask with ID {} does not exist",
+                    subtask_id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
 use core_error::core_errors::CoreErrors;
 use sea_orm::{
+    prelude::DateTimeWithTimeZone,
     sqlx::types::chrono::{DateTime, Utc},
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend, DbBackend, DbConn, DbErr,
-    DeleteResult, EntityTrait, FromQueryResult, IntoActiveModel, QueryFilter, RuntimeErr, Set,
-    Statement,
+    EntityTrait, FromQueryResult, IntoActiveModel, QueryFilter, RuntimeErr, Set, Statement,
 };
 
 use crate::entity::{
@@ -11,6 +11,8 @@ use crate::entity::{
     tasks, user_access,
 };
 
+use super::roles_query::{role_not_above, ADMINISTRATOR_LEVEL, MANAGER_LEVEL, SUPPORT_LEVEL};
+
 #[derive(Debug, FromQueryResult)]
 pub struct TaskModelFromQueryResult {
     pub id: i32,
@@ -96,6 +98,7 @@ impl TasksQuery {
                 priority: None, // Task priority is not part of the query.
                 created_at: row.try_get("", "created_at")?, // Timestamp of task creation.
                 due_date: None, // Task due date is not part of the query.
+                deleted_at: None, // A new task is not in the trash.
             }
         } else {
             // Handle the case where no task was created or returned from the query.
@@ -121,7 +124,8 @@ impl TasksQuery {
     ///
     /// # Returns
     /// * `Result<Option<(tasks::Model, i32)>, CoreErrors>` - Returns the task details as a `tasks::Model`
-    ///   along with the user's role ID if found. Returns `None` if no matching task or access level is found.
+    ///   along with the user's role ID if found. Returns `None` if no matching task or access level is found,
+    ///   or if the task is in the trash.
     ///
     /// # Errors
     /// * Returns `CoreErrors` if the database query fails or if data conversion errors occur.
@@ -148,7 +152,7 @@ impl TasksQuery {
             FROM user_company uc
             JOIN roles r ON uc.role_id = r.id
             JOIN tasks t ON uc.company_id = (SELECT company_id FROM projects WHERE id = t.project_id)
-            WHERE uc.user_id = $1 AND t.id = $2 AND uc.role_id <= 2
+            WHERE uc.user_id = $1 AND t.id = $2 AND role_effective_level(r.id) <= $3 AND t.deleted_at IS NULL
 
             UNION ALL
 
@@ -167,7 +171,7 @@ impl TasksQuery {
             FROM user_access ua
             JOIN roles r ON ua.role_id = r.id
             JOIN tasks t ON ua.project_id = t.project_id
-            WHERE ua.user_id = $1 AND t.id = $2 AND ua.role_id <= 5
+            WHERE ua.user_id = $1 AND t.id = $2 AND role_effective_level(r.id) <= $4 AND t.deleted_at IS NULL
 
             UNION ALL
 
@@ -186,7 +190,7 @@ impl TasksQuery {
             FROM user_access ua
             JOIN roles r ON ua.role_id = r.id
             JOIN tasks t ON ua.task_id = t.id
-            WHERE ua.user_id = $1 AND ua.task_id = $2
+            WHERE ua.user_id = $1 AND ua.task_id = $2 AND t.deleted_at IS NULL
         )
         SELECT 
             ur.task_id AS task_id,
@@ -208,8 +212,10 @@ impl TasksQuery {
             DbBackend::Postgres,
             sql,
             vec![
-                user_id.into(), // $1 - User ID
-                task_id.into(), // $2 - Task ID
+                user_id.into(),             // $1 - User ID
+                task_id.into(),             // $2 - Task ID
+                ADMINISTRATOR_LEVEL.into(), // $3 - Least privileged company role level with access to all tasks
+                SUPPORT_LEVEL.into(), // $4 - Least privileged project role level with access to its tasks
             ],
         );
 
@@ -236,6 +242,7 @@ impl TasksQuery {
                 priority: row.try_get("", "task_priority").ok(),       // Nullable priority
                 created_at: row.try_get("", "task_created_at")?,       // Creation timestamp
                 due_date: row.try_get("", "task_due_date").ok(),       // Nullable due date
+                deleted_at: None,                                      // Not in the trash
             };
 
             // Extract the user's role ID
@@ -249,6 +256,128 @@ impl TasksQuery {
         }
     }
 
+    /// Retrieves a task by its ID.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `task_id` - The ID of the task.
+    ///
+    /// # Returns
+    /// * `Result<Option<tasks::Model>, CoreErrors>` - Returns the task if it exists and is not in the trash,
+    ///   otherwise `None`.
+    pub async fn get_task_by_id(
+        db: &DbConn,
+        task_id: i32,
+    ) -> Result<Option<tasks::Model>, CoreErrors> {
+        let task = tasks::Entity::find_by_id(task_id)
+            .filter(tasks::Column::DeletedAt.is_null())
+            .one(db)
+            .await?;
+
+        Ok(task)
+    }
+
+    /// Retrieves a task together with the name of the assigned user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `task_id` - The ID of the task.
+    ///
+    /// # Returns
+    /// * `Result<Option<TaskModelFromQueryResult>, CoreErrors>` - Returns the task if it exists and is not in
+    ///   the trash, otherwise `None`.
+    pub async fn get_task_with_assignee(
+        db: &DbConn,
+        task_id: i32,
+    ) -> Result<Option<TaskModelFromQueryResult>, CoreErrors> {
+        let sql = r#"
+        SELECT
+            t.id AS id,
+            t.project_id AS project_id,
+            u.id AS assigned_to_id,
+            u.user_name AS assigned_to_name,
+            t.status::TEXT AS status,
+            t.title AS title,
+            t.description AS description,
+            t.priority AS priority,
+            t.created_at AS created_at,
+            t.due_date AS due_date
+        FROM
+            tasks t
+        LEFT JOIN
+            users u ON t.assigned_to = u.id
+        WHERE
+            t.id = $1
+            AND t.deleted_at IS NULL;
+    "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                task_id.into(), // $1 - Task ID
+            ],
+        );
+
+        let task = TaskModelFromQueryResult::find_by_statement(stmt)
+            .one(db)
+            .await?;
+
+        Ok(task)
+    }
+
+    /// Applies a partial update to a task.
+    ///
+    /// Only the fields passed as `Some` are changed. For `due_date`, `Some(None)` clears the stored date.
+    /// Permission and status transition checks must be performed by the caller.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `task` - The task to update.
+    /// * `title` - The new title, if it should change.
+    /// * `description` - The new description, if it should change.
+    /// * `status` - The new status, if it should change.
+    /// * `priority` - The new priority, if it should change.
+    /// * `due_date` - The new due date, if it should change.
+    ///
+    /// # Returns
+    /// * `Result<tasks::Model, CoreErrors>` - Returns the updated task.
+    pub async fn update_task(
+        db: &DbConn,
+        task: tasks::Model,
+        title: Option<String>,
+        description: Option<String>,
+        status: Option<TaskStatusType>,
+        priority: Option<String>,
+        due_date: Option<Option<DateTimeWithTimeZone>>,
+    ) -> Result<tasks::Model, CoreErrors> {
+        let mut active_task = task.into_active_model();
+
+        if let Some(title) = title {
+            active_task.title = Set(title);
+        }
+
+        if let Some(description) = description {
+            active_task.description = Set(Some(description));
+        }
+
+        if let Some(status) = status {
+            active_task.status = Set(status);
+        }
+
+        if let Some(priority) = priority {
+            active_task.priority = Set(Some(priority));
+        }
+
+        if let Some(due_date) = due_date {
+            active_task.due_date = Set(due_date);
+        }
+
+        let updated_task = active_task.update(db).await?;
+
+        Ok(updated_task)
+    }
+
     /// Adds a user to a task by creating or retrieving a `user_access` record.
     ///
     /// This function first checks if the user already has access to the specified task.
@@ -299,7 +428,7 @@ impl TasksQuery {
 
     /// Removes a user's access to a task from the `user_access` table.
     ///
-    /// Depending on the `request_user_id_lvl`, this function checks for a matching user-task association
+    /// Depending on the `requester_role_id`, this function checks for a matching user-task association
     /// with or without a role level constraint. If an association exists, it deletes the record; otherwise,
     /// it returns an error.
     ///
@@ -307,8 +436,8 @@ impl TasksQuery {
     /// * `db` - A reference to the database connection.
     /// * `user_id` - The ID of the user whose access to the task is being removed.
     /// * `task_id` - The ID of the task from which the user's access should be removed.
-    /// * `request_user_id_lvl` - An optional role level constraint. If provided, only associations with a role
-    ///   level greater than or equal to this value will be considered.
+    /// * `requester_role_id` - The role of the requester. If provided, only associations whose role is not
+    ///   more privileged than it will be considered.
     ///
     /// # Returns
     /// * `Result<(), CoreErrors>` - Returns `Ok(())` on successful removal or an error if no matching association is found.
@@ -320,16 +449,19 @@ impl TasksQuery {
         db: &DbConn,
         user_id: i32,
         task_id: i32,
-        request_user_id_lvl: Option<i32>,
+        requester_role_id: Option<i32>,
     ) -> Result<(), CoreErrors> {
         // Step 1: Query the `user_access` table for a matching record
-        let existing_access = match request_user_id_lvl {
+        let existing_access = match requester_role_id {
             // Case 1: Query with role level constraint
-            Some(level) => {
+            Some(requester_role_id) => {
                 user_access::Entity::find()
                     .filter(user_access::Column::UserId.eq(user_id)) // Match user ID
                     .filter(user_access::Column::TaskId.eq(task_id)) // Match task ID
-                    .filter(user_access::Column::RoleId.gte(level)) // Role level constraint
+                    .filter(role_not_above(
+                        user_access::Column::RoleId,
+                        requester_role_id,
+                    )) // Role level constraint
                     .one(db)
                     .await?
             }
@@ -362,64 +494,53 @@ impl TasksQuery {
         Ok(())
     }
 
-    /// Deletes a task from the database by its ID.
+    /// Moves a task to the trash together with its notes.
     ///
-    /// This function checks if the task exists before attempting to delete it. If the task is found,
-    /// it is deleted. If the task does not exist, an error is returned.
+    /// The task and its notes share the same `deleted_at` timestamp, so restoring the task brings back
+    /// exactly the notes deleted with it. Subtasks and access entries are kept until the task is purged
+    /// from the trash.
     ///
     /// # Arguments
-    /// * `db` - A reference to the database connection.
+    /// * `db` - A reference to the database connection or an open transaction.
     /// * `task_id` - The ID of the task to be deleted.
     ///
     /// # Returns
     /// * `Result<(), CoreErrors>` - Returns `Ok(())` if the task is successfully deleted,
-    /// or an appropriate error if the task does not exist or the deletion fails.
+    ///   or an appropriate error if the task does not exist or the deletion fails.
     ///
     /// # Errors
     /// * Returns `CoreErrors::DatabaseServiceError` if the task does not exist.
     /// * Returns `CoreErrors` for any database operation failures.
-    pub async fn delete_task(db: &DbConn, task_id: i32) -> Result<(), CoreErrors> {
-        // Step 1: Attempt to find the task by its ID
-        match tasks::Entity::find_by_id(task_id).one(db).await? {
-            Some(task) => {
-                // Step 2: Convert the found task entity into an active model for deletion
-                let active_task = task.into_active_model();
-
-                // Step 3: Delete the task record from the database
-                active_task.delete(db).await?;
-            }
-            None => {
-                // Step 4: Return an error if the task does not exist
-                return Err(CoreErrors::DatabaseServiceError(format!(
-                    "Task with ID {} does not exist",
-                    task_id
-                )));
-            }
-        }
-
-        // Step 5: Return success if the task was deleted successfully
-        Ok(())
-    }
+    pub async fn delete_task(db: &impl ConnectionTrait, task_id: i32) -> Result<(), CoreErrors> {
+        // CURRENT_TIMESTAMP is fixed for the whole statement, so all rows get the same deletion time
+        let sql = r#"
+        WITH deleted_notes AS (
+            UPDATE notes SET deleted_at = CURRENT_TIMESTAMP
+            WHERE deleted_at IS NULL
+                AND (task_id = $1 OR subtask_id IN (SELECT id FROM subtasks WHERE task_id = $1))
+                AND EXISTS (SELECT 1 FROM tasks WHERE id = $1 AND deleted_at IS NULL)
+        )
+        UPDATE tasks SET deleted_at = CURRENT_TIMESTAMP
+        WHERE id = $1 AND deleted_at IS NULL;
+    "#;
 
-    /// Deletes all user associations for a specific task from the `user_access` table.
-    ///
-    /// This function removes all user-task associations linked to the specified task ID.
-    ///
-    /// # Arguments
-    /// * `db` - A reference to the database connection.
-    /// * `task_id` - The ID of the task for which all user associations should be removed.
-    ///
-    /// # Returns
-    /// * `Result<(), CoreErrors>` - Returns `Ok(())` if the associations are successfully deleted,
-    /// or an appropriate error if the deletion fails.
-    ///
-    /// # Errors
-    /// * Returns `CoreErrors` for any database operation failures.
-    pub async fn delete_all_users_from_task(db: &DbConn, task_id: i32) -> Result<(), CoreErrors> {
-        let _: DeleteResult = user_access::Entity::delete_many()
-            .filter(user_access::Column::TaskId.eq(task_id))
-            .exec(db)
-            .await?;
+        // Step 1: Mark the task and its notes as deleted in a single statement
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                task_id.into(), // $1 - Task ID
+            ],
+        );
+        let result = db.execute(stmt).await?;
+
+        // Step 2: Return an error if the task does not exist or is already in the trash
+        if result.rows_affected() == 0 {
+            return Err(CoreErrors::DatabaseServiceError(format!(
+                "Task with ID {} does not exist",
+                task_id
+            )));
+        }
 
         Ok(())
     }
@@ -428,7 +549,7 @@ impl TasksQuery {
     ///
     /// This function returns a list of tasks that a user can view or modify within a project.
     /// It checks for three types of access:
-    /// 1. Company-wide access for roles with sufficient privileges (`role_id <= 3`).
+    /// 1. Company-wide access for roles with sufficient privileges (at least "Manager").
     /// 2. Explicit project-level access for the user.
     /// 3. Explicit task-level access for the user.
     ///
@@ -452,7 +573,7 @@ impl TasksQuery {
         user_id: i32,
     ) -> Result<Vec<TaskModelFromQueryResult>, CoreErrors> {
         // SQL query to fetch tasks the user has access to based on:
-        // 1. Company-wide access (`role_id <= 3` in `user_company`).
+        // 1. Company-wide access (at least "Manager" in `user_company`).
         // 2. Explicit project-level access (`user_access.project_id` matches).
         // 3. Explicit task-level access (`user_access.task_id` matches).
         let sql = r#"
@@ -473,14 +594,16 @@ impl TasksQuery {
             users u ON t.assigned_to = u.id
         WHERE 
             t.project_id = $1
+            AND t.deleted_at IS NULL
             AND (
-                -- Full access to the company (roles with `role_id <= 3`).
+                -- Full access to the company (roles with an effective level <= $3).
                 EXISTS (
                     SELECT 1 
                     FROM user_company uc 
                     INNER JOIN projects p ON uc.company_id = p.company_id
+                    INNER JOIN roles r ON uc.role_id = r.id
                     WHERE uc.user_id = $2 
-                      AND uc.role_id <= 3
+                      AND role_effective_level(r.id) <= $3
                       AND p.id = $1
                 )
                 OR 
@@ -507,8 +630,9 @@ impl TasksQuery {
             DbBackend::Postgres,
             sql,
             vec![
-                project_id.into(), // Parameter $1: Project ID
-                user_id.into(),    // Parameter $2: User ID
+                project_id.into(),    // Parameter $1: Project ID
+                user_id.into(),       // Parameter $2: User ID
+                MANAGER_LEVEL.into(), // Parameter $3: Least privileged company role level with access to all tasks
             ],
         );
 
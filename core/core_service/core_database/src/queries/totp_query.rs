@@ -0,0 +1,199 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbConn, EntityTrait, QueryFilter,
+    Set,
+};
+
+use crate::entity::{prelude, totp_recovery_codes, user_totp};
+
+/// Provides methods for managing TOTP two-factor authentication and recovery codes.
+pub struct TotpQuery;
+
+impl TotpQuery {
+    /// Retrieves the TOTP secret of a user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// * `Result<Option<user_totp::Model>, CoreErrors>` - Returns the TOTP record, or `None` if the user never enrolled.
+    pub async fn get_user_totp(
+        db: &DbConn,
+        user_id: i32,
+    ) -> Result<Option<user_totp::Model>, CoreErrors> {
+        let totp = prelude::UserTotp::find_by_id(user_id).one(db).await?;
+
+        Ok(totp)
+    }
+
+    /// Stores a new, unconfirmed TOTP secret for a user, replacing an earlier unconfirmed one.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    /// * `secret` - The base32 encoded TOTP secret.
+    ///
+    /// # Returns
+    /// * `Result<(), CoreErrors>` - Returns `Ok(())` once the secret is stored.
+    pub async fn start_enrolment(
+        db: &DbConn,
+        user_id: i32,
+        secret: String,
+    ) -> Result<(), CoreErrors> {
+        let new_totp = user_totp::ActiveModel {
+            user_id: Set(user_id),
+            secret: Set(secret),
+            confirmed_at: Set(None),
+            last_used_step: Set(None),
+            ..Default::default()
+        };
+
+        prelude::UserTotp::insert(new_totp)
+            .on_conflict(
+                OnConflict::column(user_totp::Column::UserId)
+                    .update_columns([
+                        user_totp::Column::Secret,
+                        user_totp::Column::ConfirmedAt,
+                        user_totp::Column::LastUsedStep,
+                    ])
+                    .value(user_totp::Column::CreatedAt, Expr::current_timestamp())
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Enables two-factor authentication for a user and replaces their recovery codes.
+    ///
+    /// Run it on a transaction, so a failure leaves the enrolment unconfirmed.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `user_id` - The ID of the user.
+    /// * `used_step` - The time step of the code the enrolment was confirmed with.
+    /// * `recovery_code_hashes` - The hashes of the new recovery codes.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the user has no unconfirmed enrolment.
+    pub async fn confirm_enrolment(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        used_step: i64,
+        recovery_code_hashes: Vec<String>,
+    ) -> Result<bool, CoreErrors> {
+        let result = prelude::UserTotp::update_many()
+            .col_expr(
+                user_totp::Column::ConfirmedAt,
+                Expr::current_timestamp().into(),
+            )
+            .col_expr(user_totp::Column::LastUsedStep, Expr::value(used_step))
+            .filter(user_totp::Column::UserId.eq(user_id))
+            .filter(user_totp::Column::ConfirmedAt.is_null())
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Ok(false);
+        }
+
+        prelude::TotpRecoveryCodes::delete_many()
+            .filter(totp_recovery_codes::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+
+        for code_hash in recovery_code_hashes {
+            totp_recovery_codes::ActiveModel {
+                user_id: Set(user_id),
+                code_hash: Set(code_hash),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+
+        Ok(true)
+    }
+
+    /// Records that a TOTP code of a time step was used, so the code can't be replayed.
+    ///
+    /// The check and the update happen in one statement, so a code can't be used twice.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    /// * `used_step` - The time step of the used code.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if a code of this or a later time step was already used.
+    pub async fn record_used_step(
+        db: &DbConn,
+        user_id: i32,
+        used_step: i64,
+    ) -> Result<bool, CoreErrors> {
+        let result = prelude::UserTotp::update_many()
+            .col_expr(user_totp::Column::LastUsedStep, Expr::value(used_step))
+            .filter(user_totp::Column::UserId.eq(user_id))
+            .filter(
+                Condition::any()
+                    .add(user_totp::Column::LastUsedStep.is_null())
+                    .add(user_totp::Column::LastUsedStep.lt(used_step)),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Marks an unused recovery code of a user as used.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    /// * `code_hash` - The hash of the presented recovery code.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the code is unknown or was already used.
+    pub async fn consume_recovery_code(
+        db: &DbConn,
+        user_id: i32,
+        code_hash: &str,
+    ) -> Result<bool, CoreErrors> {
+        let result = prelude::TotpRecoveryCodes::update_many()
+            .col_expr(
+                totp_recovery_codes::Column::UsedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(totp_recovery_codes::Column::UserId.eq(user_id))
+            .filter(totp_recovery_codes::Column::CodeHash.eq(code_hash))
+            .filter(totp_recovery_codes::Column::UsedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Disables two-factor authentication for a user and deletes their recovery codes.
+    ///
+    /// Run it on a transaction, so a failure leaves two-factor authentication enabled.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the user never enrolled.
+    pub async fn disable_totp(db: &impl ConnectionTrait, user_id: i32) -> Result<bool, CoreErrors> {
+        prelude::TotpRecoveryCodes::delete_many()
+            .filter(totp_recovery_codes::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+
+        let result = prelude::UserTotp::delete_by_id(user_id).exec(db).await?;
+
+        Ok(result.rows_affected == 1)
+    }
+}
@@ -0,0 +1,607 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, ColumnTrait, ConnectionTrait, DbBackend, DbConn, EntityTrait,
+    FromQueryResult, QueryFilter, Statement,
+};
+
+use crate::entity::user_company;
+
+use super::roles_query::OWNER_LEVEL;
+
+/// The kinds of items that can be moved to the trash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrashItemType {
+    Company,
+    Project,
+    Task,
+    Note,
+}
+
+impl TrashItemType {
+    /// Returns the name of the item type as used in trash queries.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrashItemType::Company => "company",
+            TrashItemType::Project => "project",
+            TrashItemType::Task => "task",
+            TrashItemType::Note => "note",
+        }
+    }
+
+    /// Parses the name of an item type as returned by trash queries.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "company" => Some(TrashItemType::Company),
+            "project" => Some(TrashItemType::Project),
+            "task" => Some(TrashItemType::Task),
+            "note" => Some(TrashItemType::Note),
+            _ => None,
+        }
+    }
+}
+
+/// An item in the trash.
+#[derive(Debug, FromQueryResult)]
+pub struct TrashItem {
+    /// The kind of the item, see `TrashItemType`.
+    pub item_type: String,
+    pub id: i32,
+    /// The company the item belongs to; `None` for notes outside of any company.
+    pub company_id: Option<i32>,
+    /// The name of a company, the title of a project or task, or the beginning of a note.
+    pub title: String,
+    /// The author of a note; `None` for other items.
+    pub author_id: Option<i32>,
+    pub deleted_at: DateTimeWithTimeZone,
+    /// The latest deletion time of the item's parents, if any of them is in the trash.
+    pub parent_deleted_at: Option<DateTimeWithTimeZone>,
+}
+
+impl TrashItem {
+    /// Returns the kind of the item.
+    pub fn kind(&self) -> Option<TrashItemType> {
+        TrashItemType::from_name(&self.item_type)
+    }
+
+    /// Returns whether a parent of the item is in the trash, so the item can't be restored on its own.
+    pub fn parent_deleted(&self) -> bool {
+        self.parent_deleted_at.is_some()
+    }
+}
+
+/// Common table expression `trash` listing every deleted company, project, task and note.
+///
+/// Items deleted along with a parent share its `deleted_at`, which is how listings hide them
+/// and restores bring them back.
+const TRASH_ITEMS: &str = r#"
+    WITH trash AS (
+        SELECT
+            'company' AS item_type,
+            c.id,
+            c.id AS company_id,
+            c.name AS title,
+            NULL::INT AS author_id,
+            c.deleted_at,
+            NULL::TIMESTAMPTZ AS parent_deleted_at
+        FROM companies c
+        WHERE c.deleted_at IS NOT NULL
+
+        UNION ALL
+
+        SELECT
+            'project',
+            p.id,
+            p.company_id,
+            p.title,
+            NULL::INT,
+            p.deleted_at,
+            c.deleted_at
+        FROM projects p
+        JOIN companies c ON c.id = p.company_id
+        WHERE p.deleted_at IS NOT NULL
+
+        UNION ALL
+
+        SELECT
+            'task',
+            t.id,
+            p.company_id,
+            t.title,
+            NULL::INT,
+            t.deleted_at,
+            p.deleted_at
+        FROM tasks t
+        JOIN projects p ON p.id = t.project_id
+        WHERE t.deleted_at IS NOT NULL
+
+        UNION ALL
+
+        SELECT
+            'note',
+            n.id,
+            COALESCE(n.company_id, p.company_id, tp.company_id, sp.company_id),
+            LEFT(n.content, 100),
+            n.user_id,
+            n.deleted_at,
+            GREATEST(c.deleted_at, p.deleted_at, t.deleted_at, st.deleted_at)
+        FROM notes n
+        LEFT JOIN companies c ON c.id = n.company_id
+        LEFT JOIN projects p ON p.id = n.project_id
+        LEFT JOIN tasks t ON t.id = n.task_id
+        LEFT JOIN projects tp ON tp.id = t.project_id
+        LEFT JOIN subtasks s ON s.id = n.subtask_id
+        LEFT JOIN tasks st ON st.id = s.task_id
+        LEFT JOIN projects sp ON sp.id = st.project_id
+        WHERE n.deleted_at IS NOT NULL
+    )
+"#;
+
+/// Provides methods for listing, restoring and purging deleted companies, projects, tasks and notes.
+pub struct TrashQuery;
+
+impl TrashQuery {
+    /// Retrieves an item from the trash.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `item_type` - The kind of the item.
+    /// * `item_id` - The ID of the item.
+    ///
+    /// # Returns
+    /// * `Result<Option<TrashItem>, CoreErrors>` - Returns the item, or `None` if it is not in the trash.
+    pub async fn get_trash_item(
+        db: &DbConn,
+        item_type: TrashItemType,
+        item_id: i32,
+    ) -> Result<Option<TrashItem>, CoreErrors> {
+        let sql = format!(
+            r#"{TRASH_ITEMS}
+            SELECT * FROM trash
+            WHERE item_type = $1 AND id = $2;
+        "#
+        );
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                item_type.as_str().into(), // $1 - Item type
+                item_id.into(),            // $2 - Item ID
+            ],
+        );
+
+        let item = TrashItem::find_by_statement(stmt).one(db).await?;
+
+        Ok(item)
+    }
+
+    /// Retrieves a page of the deleted projects, tasks and notes of a company, most recently deleted first.
+    ///
+    /// Items deleted along with their parent are left out, since they are restored with it.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `company_id` - The ID of the company.
+    /// * `limit` - The maximum number of items to return.
+    /// * `offset` - The number of items to skip.
+    ///
+    /// # Returns
+    /// * `Result<Vec<TrashItem>, CoreErrors>` - Returns the items of the requested page.
+    pub async fn get_company_trash(
+        db: &DbConn,
+        company_id: i32,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<TrashItem>, CoreErrors> {
+        let sql = format!(
+            r#"{TRASH_ITEMS}
+            SELECT * FROM trash
+            WHERE company_id = $1
+                AND item_type <> 'company'
+                AND parent_deleted_at IS DISTINCT FROM deleted_at
+            ORDER BY deleted_at DESC, item_type, id DESC
+            LIMIT $2
+            OFFSET $3;
+        "#
+        );
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                company_id.into(),      // $1 - Company ID
+                (limit as i64).into(),  // $2 - Page size
+                (offset as i64).into(), // $3 - Offset
+            ],
+        );
+
+        let items = TrashItem::find_by_statement(stmt).all(db).await?;
+
+        Ok(items)
+    }
+
+    /// Retrieves a page of the deleted companies a user owns and the user's deleted notes outside
+    /// of any company, most recently deleted first.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    /// * `limit` - The maximum number of items to return.
+    /// * `offset` - The number of items to skip.
+    ///
+    /// # Returns
+    /// * `Result<Vec<TrashItem>, CoreErrors>` - Returns the items of the requested page.
+    pub async fn get_user_trash(
+        db: &DbConn,
+        user_id: i32,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<TrashItem>, CoreErrors> {
+        let sql = format!(
+            r#"{TRASH_ITEMS}
+            SELECT * FROM trash
+            WHERE parent_deleted_at IS DISTINCT FROM deleted_at
+                AND (
+                    (
+                        item_type = 'company'
+                        AND EXISTS (
+                            SELECT 1
+                            FROM user_company uc
+                            JOIN roles r ON r.id = uc.role_id
+                            WHERE uc.user_id = $1
+                              AND uc.company_id = trash.id
+                              AND role_effective_level(r.id) <= $2
+                        )
+                    )
+                    OR (item_type = 'note' AND company_id IS NULL AND author_id = $1)
+                )
+            ORDER BY deleted_at DESC, item_type, id DESC
+            LIMIT $3
+            OFFSET $4;
+        "#
+        );
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                user_id.into(),         // $1 - User ID
+                OWNER_LEVEL.into(),     // $2 - Role level required to restore a company
+                (limit as i64).into(),  // $3 - Page size
+                (offset as i64).into(), // $4 - Offset
+            ],
+        );
+
+        let items = TrashItem::find_by_statement(stmt).all(db).await?;
+
+        Ok(items)
+    }
+
+    /// Retrieves a user's membership in a company, even if the company is in the trash.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    /// * `company_id` - The ID of the company.
+    ///
+    /// # Returns
+    /// * `Result<Option<user_company::Model>, CoreErrors>` - Returns the membership, or `None` if the user
+    ///   does not belong to the company.
+    pub async fn get_user_company_membership(
+        db: &DbConn,
+        user_id: i32,
+        company_id: i32,
+    ) -> Result<Option<user_company::Model>, CoreErrors> {
+        let membership = user_company::Entity::find()
+            .filter(user_company::Column::UserId.eq(user_id))
+            .filter(user_company::Column::CompanyId.eq(company_id))
+            .one(db)
+            .await?;
+
+        Ok(membership)
+    }
+
+    /// Restores an item from the trash together with everything deleted along with it.
+    ///
+    /// An item can't be restored while one of its parents is in the trash.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `item_type` - The kind of the item.
+    /// * `item_id` - The ID of the item.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the item is not in the trash or a parent is.
+    pub async fn restore(
+        db: &DbConn,
+        item_type: TrashItemType,
+        item_id: i32,
+    ) -> Result<bool, CoreErrors> {
+        let sql = match item_type {
+            TrashItemType::Company => {
+                r#"
+                WITH target AS (
+                    SELECT id, deleted_at FROM companies
+                    WHERE id = $1 AND deleted_at IS NOT NULL
+                ),
+                restored_projects AS (
+                    UPDATE projects p SET deleted_at = NULL
+                    FROM target
+                    WHERE p.company_id = target.id AND p.deleted_at = target.deleted_at
+                ),
+                restored_tasks AS (
+                    UPDATE tasks t SET deleted_at = NULL
+                    FROM target
+                    WHERE t.deleted_at = target.deleted_at
+                        AND t.project_id IN (SELECT id FROM projects WHERE company_id = target.id)
+                ),
+                restored_notes AS (
+                    UPDATE notes n SET deleted_at = NULL
+                    FROM target
+                    WHERE n.deleted_at = target.deleted_at
+                        AND (
+                            n.company_id = target.id
+                            OR n.project_id IN (SELECT id FROM projects WHERE company_id = target.id)
+                            OR n.task_id IN (
+                                SELECT t.id FROM tasks t
+                                JOIN projects p ON p.id = t.project_id
+                                WHERE p.company_id = target.id
+                            )
+                            OR n.subtask_id IN (
+                                SELECT s.id FROM subtasks s
+                                JOIN tasks t ON t.id = s.task_id
+                                JOIN projects p ON p.id = t.project_id
+                                WHERE p.company_id = target.id
+                            )
+                        )
+                )
+                UPDATE companies c SET deleted_at = NULL
+                FROM target
+                WHERE c.id = target.id;
+                "#
+            }
+            TrashItemType::Project => {
+                r#"
+                WITH target AS (
+                    SELECT p.id, p.deleted_at FROM projects p
+                    JOIN companies c ON c.id = p.company_id
+                    WHERE p.id = $1 AND p.deleted_at IS NOT NULL AND c.deleted_at IS NULL
+                ),
+                restored_tasks AS (
+                    UPDATE tasks t SET deleted_at = NULL
+                    FROM target
+                    WHERE t.project_id = target.id AND t.deleted_at = target.deleted_at
+                ),
+                restored_notes AS (
+                    UPDATE notes n SET deleted_at = NULL
+                    FROM target
+                    WHERE n.deleted_at = target.deleted_at
+                        AND (
+                            n.project_id = target.id
+                            OR n.task_id IN (SELECT id FROM tasks WHERE project_id = target.id)
+                            OR n.subtask_id IN (
+                                SELECT s.id FROM subtasks s
+                                JOIN tasks t ON t.id = s.task_id
+                                WHERE t.project_id = target.id
+                            )
+                        )
+                )
+                UPDATE projects p SET deleted_at = NULL
+                FROM target
+                WHERE p.id = target.id;
+                "#
+            }
+            TrashItemType::Task => {
+                r#"
+                WITH target AS (
+                    SELECT t.id, t.deleted_at FROM tasks t
+                    JOIN projects p ON p.id = t.project_id
+                    WHERE t.id = $1 AND t.deleted_at IS NOT NULL AND p.deleted_at IS NULL
+                ),
+                restored_notes AS (
+                    UPDATE notes n SET deleted_at = NULL
+                    FROM target
+                    WHERE n.deleted_at = target.deleted_at
+                        AND (
+                            n.task_id = target.id
+                            OR n.subtask_id IN (SELECT id FROM subtasks WHERE task_id = target.id)
+                        )
+                )
+                UPDATE tasks t SET deleted_at = NULL
+                FROM target
+                WHERE t.id = target.id;
+                "#
+            }
+            TrashItemType::Note => {
+                r#"
+                UPDATE notes n SET deleted_at = NULL
+                WHERE n.id = $1
+                    AND n.deleted_at IS NOT NULL
+                    AND NOT EXISTS (
+                        SELECT 1 FROM companies c
+                        WHERE c.id = n.company_id AND c.deleted_at IS NOT NULL
+                    )
+                    AND NOT EXISTS (
+                        SELECT 1 FROM projects p
+                        WHERE p.id = n.project_id AND p.deleted_at IS NOT NULL
+                    )
+                    AND NOT EXISTS (
+                        SELECT 1 FROM tasks t
+                        WHERE t.id = n.task_id AND t.deleted_at IS NOT NULL
+                    )
+                    AND NOT EXISTS (
+                        SELECT 1 FROM subtasks s
+                        JOIN tasks t ON t.id = s.task_id
+                        WHERE s.id = n.subtask_id AND t.deleted_at IS NOT NULL
+                    );
+                "#
+            }
+        };
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                item_id.into(), // $1 - Item ID
+            ],
+        );
+
+        let result = db.execute(stmt).await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Permanently deletes the items that have been in the trash for longer than the retention period.
+    ///
+    /// Children are deleted before their parents; memberships, access entries and subtasks
+    /// of the purged items are removed by the foreign key cascades. Run it on a transaction,
+    /// so a failure purges nothing.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `retention_days` - The number of days items are kept in the trash.
+    ///
+    /// # Returns
+    /// * `Result<u64, CoreErrors>` - Returns the number of purged items.
+    pub async fn purge_expired(
+        db: &impl ConnectionTrait,
+        retention_days: i32,
+    ) -> Result<u64, CoreErrors> {
+        let mut purged = 0;
+
+        for table in ["notes", "tasks", "projects", "companies"] {
+            let sql = format!(
+                "DELETE FROM {table} WHERE deleted_at < CURRENT_TIMESTAMP - make_interval(days => $1);"
+            );
+
+            let stmt = Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                vec![
+                    retention_days.into(), // $1 - Retention period in days
+                ],
+            );
+
+            purged += db.execute(stmt).await?.rows_affected();
+        }
+
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait, Value};
+
+    use super::*;
+    use crate::test_support::{affected, executed_statements, failure};
+
+    #[test]
+    fn item_type_names_round_trip() {
+        for item_type in [
+            TrashItemType::Company,
+            TrashItemType::Project,
+            TrashItemType::Task,
+            TrashItemType::Note,
+        ] {
+            assert_eq!(
+                TrashItemType::from_name(item_type.as_str()),
+                Some(item_type)
+            );
+        }
+
+        assert_eq!(TrashItemType::from_name("subtask"), None);
+    }
+
+    #[tokio::test]
+    async fn restore_updates_the_table_of_the_item() {
+        let cases = [
+            (
+                TrashItemType::Company,
+                "UPDATE companies c SET deleted_at = NULL",
+            ),
+            (
+                TrashItemType::Project,
+                "UPDATE projects p SET deleted_at = NULL",
+            ),
+            (TrashItemType::Task, "UPDATE tasks t SET deleted_at = NULL"),
+            (TrashItemType::Note, "UPDATE notes n SET deleted_at = NULL"),
+        ];
+
+        for (item_type, update) in cases {
+            let db = MockDatabase::new(DatabaseBackend::Postgres)
+                .append_exec_results([affected(1)])
+                .into_connection();
+
+            assert!(TrashQuery::restore(&db, item_type, 5).await.unwrap());
+
+            let statements = executed_statements(db);
+            assert_eq!(statements.len(), 1);
+            assert!(statements[0].0.contains(update));
+            assert_eq!(statements[0].1, vec![Value::from(5)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn restore_reports_items_that_were_not_restored() {
+        // The item is not in the trash or one of its parents is
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(0)])
+            .into_connection();
+
+        assert!(!TrashQuery::restore(&db, TrashItemType::Task, 5)
+            .await
+            .unwrap());
+    }
+
+    /// Purges the trash on a transaction, committing it as the purge task does.
+    async fn purge(db: &DbConn) -> Result<u64, CoreErrors> {
+        let txn = db.begin().await?;
+        let purged = TrashQuery::purge_expired(&txn, 30).await?;
+        txn.commit().await?;
+
+        Ok(purged)
+    }
+
+    #[tokio::test]
+    async fn purge_deletes_children_before_their_parents() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(4), affected(3), affected(2), affected(1)])
+            .into_connection();
+
+        assert_eq!(purge(&db).await.unwrap(), 10);
+
+        let statements = executed_statements(db);
+        let sql = statements
+            .iter()
+            .map(|(sql, _)| sql.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(sql.len(), 6);
+        assert_eq!(sql[0], "BEGIN");
+        assert!(sql[1].starts_with("DELETE FROM notes"));
+        assert!(sql[2].starts_with("DELETE FROM tasks"));
+        assert!(sql[3].starts_with("DELETE FROM projects"));
+        assert!(sql[4].starts_with("DELETE FROM companies"));
+        assert_eq!(sql[5], "COMMIT");
+        assert!(statements[1..5]
+            .iter()
+            .all(|(_, values)| *values == vec![Value::from(30)]));
+    }
+
+    #[tokio::test]
+    async fn purge_rolls_back_when_a_delete_fails() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(4), affected(3)])
+            .append_exec_errors([failure()])
+            .into_connection();
+
+        assert!(purge(&db).await.is_err());
+
+        let statements = executed_statements(db);
+        assert_eq!(
+            statements.last().map(|(sql, _)| sql.as_str()),
+            Some("ROLLBACK")
+        );
+        assert!(!statements.iter().any(|(sql, _)| sql == "COMMIT"));
+    }
+}
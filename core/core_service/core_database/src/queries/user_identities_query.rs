@@ -0,0 +1,187 @@
+use core_error::core_errors::CoreErrors;
+use sea_orm::{ConnectionTrait, DbBackend, DbConn, EntityTrait, QueryResult, Statement};
+
+use crate::entity::{user_identities, users};
+
+/// Provides methods for linking users to the accounts of external identity providers.
+pub struct UserIdentitiesQuery;
+
+impl UserIdentitiesQuery {
+    /// Records a login with an external identity and returns the linked user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `issuer` - The issuer of the identity provider.
+    /// * `subject` - The ID the identity provider assigned to the user.
+    ///
+    /// # Returns
+    /// * `Result<Option<users::Model>, CoreErrors>` - Returns the linked user, or `None` if the identity is not linked to any user.
+    pub async fn touch_identity(
+        db: &DbConn,
+        issuer: &str,
+        subject: &str,
+    ) -> Result<Option<users::Model>, CoreErrors> {
+        let sql = r#"
+        UPDATE user_identities
+        SET last_login_at = CURRENT_TIMESTAMP
+        FROM users
+        WHERE user_identities.issuer = $1
+            AND user_identities.subject = $2
+            AND users.id = user_identities.user_id
+        RETURNING
+            users.id,
+            users.login,
+            users.user_name,
+            users.email,
+            users.is_active,
+            users.email_verified_at,
+            users.created_at,
+            users.updated_at
+        "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [issuer.into(), subject.into()],
+        );
+
+        let user = users::Entity::find().from_raw_sql(stmt).one(db).await?;
+
+        Ok(user)
+    }
+
+    /// Links an external identity to an existing user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    /// * `issuer` - The issuer of the identity provider.
+    /// * `subject` - The ID the identity provider assigned to the user.
+    /// * `email` - The email the identity provider reported, if any.
+    ///
+    /// # Returns
+    /// * `Result<user_identities::Model, CoreErrors>` - Returns the stored identity.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::DatabaseServiceError` with `duplicate_db_data` if the identity is already linked.
+    pub async fn link_identity(
+        db: &DbConn,
+        user_id: i32,
+        issuer: String,
+        subject: String,
+        email: Option<String>,
+    ) -> Result<user_identities::Model, CoreErrors> {
+        let sql = r#"
+        INSERT INTO user_identities (user_id, issuer, subject, email, last_login_at)
+        VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)
+        RETURNING id, user_id, issuer, subject, email, last_login_at, created_at
+        "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [user_id.into(), issuer.into(), subject.into(), email.into()],
+        );
+
+        let identity = user_identities::Entity::find()
+            .from_raw_sql(stmt)
+            .one(db)
+            .await?
+            .ok_or(CoreErrors::DatabaseServiceError(
+                "failed_update_data".to_string(),
+            ))?;
+
+        Ok(identity)
+    }
+
+    /// Creates a user together with its password and a linked external identity.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_login` - The unique login of the new user.
+    /// * `user_name` - The display name of the new user.
+    /// * `user_email` - The email address of the new user.
+    /// * `email_verified` - Whether the identity provider verified the email.
+    /// * `password_hash` - The hashed password of the new user.
+    /// * `issuer` - The issuer of the identity provider.
+    /// * `subject` - The ID the identity provider assigned to the user.
+    ///
+    /// # Returns
+    /// * `Result<users::Model, CoreErrors>` - Returns the new user.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::DatabaseServiceError` with `duplicate_db_data` if the login, email or identity is taken.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_user_with_identity(
+        db: &DbConn,
+        user_login: String,
+        user_name: String,
+        user_email: String,
+        email_verified: bool,
+        password_hash: String,
+        issuer: String,
+        subject: String,
+    ) -> Result<users::Model, CoreErrors> {
+        // Insert the user, its password and the identity in one statement
+        let sql = r#"
+        WITH inserted_user AS (
+            INSERT INTO users (login, user_name, email, is_active, email_verified_at)
+            VALUES ($1, $2, $3, TRUE, CASE WHEN $4 THEN CURRENT_TIMESTAMP END)
+            RETURNING id, login, user_name, email, is_active, email_verified_at, created_at, updated_at
+        ),
+        inserted_password AS (
+            INSERT INTO passwords (user_id, password_hash)
+            SELECT id, $5 FROM inserted_user
+            RETURNING id
+        ),
+        inserted_identity AS (
+            INSERT INTO user_identities (user_id, issuer, subject, email, last_login_at)
+            SELECT id, $6, $7, email, CURRENT_TIMESTAMP FROM inserted_user
+            RETURNING id
+        )
+        SELECT
+            id,
+            login,
+            user_name,
+            email,
+            is_active,
+            email_verified_at,
+            created_at,
+            updated_at
+        FROM inserted_user
+        "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [
+                user_login.into(),
+                user_name.into(),
+                user_email.into(),
+                email_verified.into(),
+                password_hash.into(),
+                issuer.into(),
+                subject.into(),
+            ],
+        );
+
+        let new_user: Option<QueryResult> = db.query_one(stmt).await?;
+
+        let new_user = new_user.ok_or(CoreErrors::DatabaseServiceError(
+            "User creation failed: No user record returned from database.".to_string(),
+        ))?;
+
+        let user_model = users::Model {
+            id: new_user.try_get("", "id")?,
+            login: new_user.try_get("", "login")?,
+            user_name: new_user.try_get("", "user_name")?,
+            email: new_user.try_get("", "email")?,
+            is_active: new_user.try_get("", "is_active")?,
+            email_verified_at: new_user.try_get("", "email_verified_at")?,
+            created_at: new_user.try_get("", "created_at")?,
+            updated_at: new_user.try_get("", "updated_at")?,
+        };
+
+        Ok(user_model)
+    }
+}
@@ -1,10 +1,16 @@
 use core_error::core_errors::CoreErrors;
 use sea_orm::{
-    ColumnTrait, ConnectionTrait, DbBackend, DbConn, EntityTrait, QueryFilter, QueryResult,
-    Statement,
+    prelude::DateTimeWithTimeZone, sea_query::Expr, ColumnTrait, ConnectionTrait, DbBackend,
+    DbConn, EntityTrait, QueryFilter, QueryResult, Statement,
 };
 
-use crate::entity::{passwords, prelude, users};
+use crate::{
+    entity::{passwords, prelude, user_company, users},
+    queries::{
+        company_invitations_query::CompanyInvitationsQuery,
+        refresh_tokens_query::RefreshTokensQuery, sessions_query::SessionsQuery,
+    },
+};
 
 pub struct UserQuery {}
 
@@ -18,6 +24,63 @@ impl UserQuery {
         Ok(user)
     }
 
+    /// Retrieves a user by email address.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_email` - The email address of the user.
+    ///
+    /// # Returns
+    /// * `Result<Option<users::Model>, CoreErrors>` - Returns the user if one is registered with this email, otherwise `None`.
+    pub async fn get_user_by_email(
+        db: &DbConn,
+        user_email: &str,
+    ) -> Result<Option<users::Model>, CoreErrors> {
+        let user = prelude::Users::find()
+            .filter(users::Column::Email.eq(user_email))
+            .one(db)
+            .await?;
+
+        Ok(user)
+    }
+
+    /// Retrieves the stored password of a user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// * `Result<Option<passwords::Model>, CoreErrors>` - Returns the password record if the user has one, otherwise `None`.
+    pub async fn get_user_password(
+        db: &DbConn,
+        user_id: i32,
+    ) -> Result<Option<passwords::Model>, CoreErrors> {
+        let password = prelude::Passwords::find()
+            .filter(passwords::Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+
+        Ok(password)
+    }
+
+    /// Checks whether a login is already taken.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_login` - The login to check.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `true` if a user with this login exists.
+    pub async fn login_exists(db: &DbConn, user_login: &str) -> Result<bool, CoreErrors> {
+        let user = prelude::Users::find()
+            .filter(users::Column::Login.eq(user_login))
+            .one(db)
+            .await?;
+
+        Ok(user.is_some())
+    }
+
     pub async fn get_user_by_login(
         db: &DbConn,
         user_login: String,
@@ -52,7 +115,7 @@ impl UserQuery {
     ///
     /// # Arguments
     ///
-    /// * `db` - A reference to the database connection, used to execute the query.
+    /// * `db` - A reference to the database connection or an open transaction, used to execute the query.
     /// * `user_login` - The unique login identifier for the new user.
     /// * `user_name` - The full name of the new user.
     /// * `user_password` - The hashed password of the new user.
@@ -63,9 +126,8 @@ impl UserQuery {
     /// Returns a `Result<users::Model, CoreErrors>`, where:
     /// - On success, it contains the newly created `users::Model` with user details.
     /// - On failure, it returns a `CoreErrors::DatabaseServiceError` with a relevant error message.
-
     pub async fn create_new_user(
-        db: &DbConn,
+        db: &impl ConnectionTrait,
         user_login: String,
         user_name: String,
         user_password: String,
@@ -76,7 +138,7 @@ impl UserQuery {
         WITH inserted_user AS (
             INSERT INTO users (login, user_name, email, is_active)
             VALUES ($1, $2, $3, $4)
-            RETURNING id, login, user_name, email, is_active, created_at, updated_at
+            RETURNING id, login, user_name, email, is_active, email_verified_at, created_at, updated_at
         ),
         inserted_password AS (
             INSERT INTO passwords (user_id, password_hash)
@@ -92,6 +154,7 @@ impl UserQuery {
             user_name,
             email,
             is_active,
+            email_verified_at,
             created_at,
             updated_at
         FROM inserted_user
@@ -125,10 +188,412 @@ impl UserQuery {
             user_name: new_user.try_get("", "user_name")?,
             email: new_user.try_get("", "email")?,
             is_active: new_user.try_get("", "is_active")?,
+            email_verified_at: new_user.try_get("", "email_verified_at")?,
             created_at: new_user.try_get("", "created_at")?,
             updated_at: new_user.try_get("", "updated_at")?,
         };
 
         Ok(user_model)
     }
+
+    /// Creates a user who registered through a company invitation.
+    ///
+    /// The invitation proves the email, so it is marked as verified and every pending invitation
+    /// sent to it is accepted. Run it on a transaction, so a failure creates no user.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `user_login` - The unique login identifier for the new user.
+    /// * `user_name` - The full name of the new user.
+    /// * `user_password` - The hashed password of the new user.
+    /// * `user_email` - The invited email address.
+    ///
+    /// # Returns
+    /// * `Result<(users::Model, Vec<user_company::Model>), CoreErrors>` - Returns the new user and the
+    ///   memberships of the companies that invited them.
+    pub async fn create_invited_user(
+        db: &impl ConnectionTrait,
+        user_login: String,
+        user_name: String,
+        user_password: String,
+        user_email: String,
+    ) -> Result<(users::Model, Vec<user_company::Model>), CoreErrors> {
+        let new_user =
+            UserQuery::create_new_user(db, user_login, user_name, user_password, user_email)
+                .await?;
+        UserQuery::mark_email_verified(db, new_user.id, &new_user.email).await?;
+        let memberships =
+            CompanyInvitationsQuery::accept_all_invitations(db, new_user.id, &new_user.email)
+                .await?;
+
+        Ok((new_user, memberships))
+    }
+
+    /// Replaces the password hash of a user and bumps `updated_at` of both the password and the user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `user_id` - The ID of the user.
+    /// * `password_hash` - The new hashed password.
+    ///
+    /// # Returns
+    /// * `Result<(), CoreErrors>` - Returns `Ok(())` once the password is updated.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::DatabaseServiceError` if the user has no password record.
+    pub async fn update_user_password(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        password_hash: String,
+    ) -> Result<(), CoreErrors> {
+        // Both rows are updated in one statement
+        let sql = r#"
+        WITH updated_user AS (
+            UPDATE users
+            SET updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING id
+        )
+        UPDATE passwords
+        SET password_hash = $2, updated_at = CURRENT_TIMESTAMP
+        WHERE user_id IN (SELECT id FROM updated_user)
+        "#;
+
+        let stmt = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            vec![
+                user_id.into(),       // $1 - ID of the user
+                password_hash.into(), // $2 - New password hash
+            ],
+        );
+
+        let result = db.execute(stmt).await?;
+
+        if result.rows_affected() == 0 {
+            return Err(CoreErrors::DatabaseServiceError(
+                "user_data_broken".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the password of a user and signs out the sessions that used the old one.
+    ///
+    /// The sessions and their refresh tokens are revoked, except for `kept_session_id` if given.
+    /// Run it on a transaction, so a failure leaves the old password and sessions in place.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `user_id` - The ID of the user.
+    /// * `password_hash` - The new hashed password.
+    /// * `kept_session_id` - The ID of the session that stays signed in, or `None` to revoke all sessions.
+    ///
+    /// # Returns
+    /// * `Result<u64, CoreErrors>` - Returns the number of revoked sessions.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::DatabaseServiceError` if the user has no password record.
+    pub async fn change_user_password(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        password_hash: String,
+        kept_session_id: Option<&str>,
+    ) -> Result<u64, CoreErrors> {
+        UserQuery::update_user_password(db, user_id, password_hash).await?;
+
+        let revoked_sessions = match kept_session_id {
+            Some(kept_session_id) => {
+                let revoked_sessions =
+                    SessionsQuery::revoke_other_user_sessions(db, user_id, kept_session_id).await?;
+                RefreshTokensQuery::revoke_other_user_tokens(db, user_id, kept_session_id).await?;
+                revoked_sessions
+            }
+            None => {
+                let revoked_sessions = SessionsQuery::revoke_all_user_sessions(db, user_id).await?;
+                RefreshTokensQuery::revoke_all_user_tokens(db, user_id).await?;
+                revoked_sessions
+            }
+        };
+
+        Ok(revoked_sessions)
+    }
+
+    /// Updates the display name and/or email of a user.
+    ///
+    /// Changing the email marks it as unverified; setting the current email again keeps its verification.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    /// * `user_name` - The new display name, or `None` to keep the current one.
+    /// * `user_email` - The new email address, or `None` to keep the current one.
+    ///
+    /// # Returns
+    /// * `Result<Option<users::Model>, CoreErrors>` - Returns the updated user, or `None` if the user does not exist.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::DatabaseServiceError` with `duplicate_db_data` if the email is already taken.
+    pub async fn update_user_profile(
+        db: &DbConn,
+        user_id: i32,
+        user_name: Option<String>,
+        user_email: Option<String>,
+    ) -> Result<Option<users::Model>, CoreErrors> {
+        let mut update = prelude::Users::update_many()
+            .col_expr(users::Column::UpdatedAt, Expr::current_timestamp().into())
+            .filter(users::Column::Id.eq(user_id));
+
+        if let Some(user_name) = user_name {
+            update = update.col_expr(users::Column::UserName, Expr::value(user_name));
+        }
+
+        // A new address has to be verified again, an unchanged one keeps its verification
+        if let Some(user_email) = user_email {
+            let email_verified_at = Expr::case(
+                Expr::col(users::Column::Email).eq(user_email.as_str()),
+                Expr::col(users::Column::EmailVerifiedAt),
+            )
+            .finally(Expr::value(Option::<DateTimeWithTimeZone>::None));
+
+            update = update
+                .col_expr(users::Column::Email, Expr::value(user_email))
+                .col_expr(users::Column::EmailVerifiedAt, email_verified_at.into());
+        }
+
+        let user = update.exec_with_returning(db).await?.into_iter().next();
+
+        Ok(user)
+    }
+
+    /// Marks the email of a user as verified.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `user_id` - The ID of the user.
+    /// * `user_email` - The verified email address.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the user's email has changed since the verification was requested.
+    pub async fn mark_email_verified(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        user_email: &str,
+    ) -> Result<bool, CoreErrors> {
+        let result = prelude::Users::update_many()
+            .col_expr(
+                users::Column::EmailVerifiedAt,
+                Expr::current_timestamp().into(),
+            )
+            .filter(users::Column::Id.eq(user_id))
+            .filter(users::Column::Email.eq(user_email))
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Activates or deactivates the account of a user.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection or an open transaction.
+    /// * `user_id` - The ID of the user.
+    /// * `is_active` - Whether the user may sign in.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `false` if the user does not exist.
+    pub async fn set_user_active(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+        is_active: bool,
+    ) -> Result<bool, CoreErrors> {
+        let result = prelude::Users::update_many()
+            .col_expr(users::Column::IsActive, Expr::value(is_active))
+            .col_expr(users::Column::UpdatedAt, Expr::current_timestamp().into())
+            .filter(users::Column::Id.eq(user_id))
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Deactivates the account of a user and revokes all of their sessions and refresh tokens.
+    ///
+    /// Run it on a transaction, so a failure leaves the account active and signed in.
+    ///
+    /// # Arguments
+    /// * `db` - An open transaction.
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// * `Result<Option<u64>, CoreErrors>` - Returns the number of revoked sessions, or `None` if the user does not exist.
+    pub async fn deactivate_user(
+        db: &impl ConnectionTrait,
+        user_id: i32,
+    ) -> Result<Option<u64>, CoreErrors> {
+        if !UserQuery::set_user_active(db, user_id, false).await? {
+            return Ok(None);
+        }
+
+        let revoked_sessions = SessionsQuery::revoke_all_user_sessions(db, user_id).await?;
+        RefreshTokensQuery::revoke_all_user_tokens(db, user_id).await?;
+
+        Ok(Some(revoked_sessions))
+    }
+
+    /// Checks whether a user is an operator of the platform.
+    ///
+    /// # Arguments
+    /// * `db` - A reference to the database connection.
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// * `Result<bool, CoreErrors>` - Returns `true` if the user is listed in `platform_admins`.
+    pub async fn is_platform_admin(db: &DbConn, user_id: i32) -> Result<bool, CoreErrors> {
+        let platform_admin = prelude::PlatformAdmins::find_by_id(user_id).one(db).await?;
+
+        Ok(platform_admin.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, MockDatabase, TransactionTrait};
+
+    use super::*;
+    use crate::test_support::{affected, executed_sql, failure};
+
+    /// Deactivates the user on a transaction, committing it as the service does.
+    async fn deactivate(db: &DbConn) -> Result<Option<u64>, CoreErrors> {
+        let txn = db.begin().await?;
+        let revoked_sessions = UserQuery::deactivate_user(&txn, 7).await?;
+        if revoked_sessions.is_some() {
+            txn.commit().await?;
+        }
+
+        Ok(revoked_sessions)
+    }
+
+    /// Changes the password on a transaction, committing it as the service does.
+    async fn change_password(db: &DbConn) -> Result<u64, CoreErrors> {
+        let txn = db.begin().await?;
+        let revoked_sessions =
+            UserQuery::change_user_password(&txn, 7, "new-hash".to_string(), Some("session"))
+                .await?;
+        txn.commit().await?;
+
+        Ok(revoked_sessions)
+    }
+
+    fn new_user() -> users::Model {
+        users::Model {
+            id: 7,
+            login: "new_user".to_string(),
+            user_name: "New User".to_string(),
+            email: "new@example.com".to_string(),
+            is_active: true,
+            email_verified_at: None,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn deactivate_user_commits_all_steps() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(1), affected(2), affected(3)])
+            .into_connection();
+
+        let revoked_sessions = deactivate(&db).await.unwrap();
+
+        assert_eq!(revoked_sessions, Some(2));
+        let sql = executed_sql(db);
+        assert_eq!(sql.first().map(String::as_str), Some("BEGIN"));
+        assert_eq!(sql.last().map(String::as_str), Some("COMMIT"));
+    }
+
+    #[tokio::test]
+    async fn deactivate_user_rolls_back_when_revoking_tokens_fails() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(1), affected(2)])
+            .append_exec_errors([failure()])
+            .into_connection();
+
+        assert!(deactivate(&db).await.is_err());
+
+        let sql = executed_sql(db);
+        assert_eq!(sql.len(), 5);
+        assert_eq!(sql.last().map(String::as_str), Some("ROLLBACK"));
+        assert!(!sql.iter().any(|sql| sql == "COMMIT"));
+    }
+
+    #[tokio::test]
+    async fn deactivate_user_changes_nothing_for_unknown_users() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(0)])
+            .into_connection();
+
+        assert_eq!(deactivate(&db).await.unwrap(), None);
+
+        let sql = executed_sql(db);
+        assert_eq!(sql.len(), 3);
+        assert_eq!(sql.last().map(String::as_str), Some("ROLLBACK"));
+    }
+
+    #[tokio::test]
+    async fn change_user_password_rolls_back_when_revoking_sessions_fails() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(1)])
+            .append_exec_errors([failure()])
+            .into_connection();
+
+        assert!(change_password(&db).await.is_err());
+
+        let sql = executed_sql(db);
+        assert!(sql[1].contains("UPDATE passwords"));
+        assert_eq!(sql.last().map(String::as_str), Some("ROLLBACK"));
+        assert!(!sql.iter().any(|sql| sql == "COMMIT"));
+    }
+
+    #[tokio::test]
+    async fn change_user_password_keeps_only_the_current_session() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([affected(1), affected(3), affected(3)])
+            .into_connection();
+
+        let revoked_sessions = change_password(&db).await.unwrap();
+
+        assert_eq!(revoked_sessions, 3);
+        let sql = executed_sql(db);
+        assert!(sql[2].contains(r#""sessions"."id" <> $"#));
+        assert_eq!(sql.last().map(String::as_str), Some("COMMIT"));
+    }
+
+    #[tokio::test]
+    async fn create_invited_user_rolls_back_when_joining_companies_fails() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![new_user()]])
+            .append_exec_results([affected(1)])
+            .append_query_errors([failure()])
+            .into_connection();
+
+        let txn = db.begin().await.unwrap();
+        let result = UserQuery::create_invited_user(
+            &txn,
+            "new_user".to_string(),
+            "New User".to_string(),
+            "hash".to_string(),
+            "new@example.com".to_string(),
+        )
+        .await;
+        drop(txn);
+
+        assert!(result.is_err());
+        let sql = executed_sql(db);
+        assert!(sql[1].contains("INSERT INTO users"));
+        assert!(sql[3].contains("INSERT INTO user_company"));
+        assert_eq!(sql.last().map(String::as_str), Some("ROLLBACK"));
+        assert!(!sql.iter().any(|sql| sql == "COMMIT"));
+    }
 }
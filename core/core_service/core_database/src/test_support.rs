@@ -0,0 +1,34 @@
+use sea_orm::{DatabaseConnection, DbErr, MockExecResult, Value};
+
+/// The result of a statement that changed `rows_affected` rows.
+pub fn affected(rows_affected: u64) -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }
+}
+
+/// An error standing in for a database that failed partway through.
+pub fn failure() -> DbErr {
+    DbErr::Custom("connection lost".to_string())
+}
+
+/// Returns the SQL of every statement the database received, in order.
+pub fn executed_sql(db: DatabaseConnection) -> Vec<String> {
+    executed_statements(db)
+        .into_iter()
+        .map(|(sql, _)| sql)
+        .collect()
+}
+
+/// Returns the SQL and the bound values of every statement the database received, in order.
+pub fn executed_statements(db: DatabaseConnection) -> Vec<(String, Vec<Value>)> {
+    db.into_transaction_log()
+        .iter()
+        .flat_map(|transaction| transaction.statements().iter())
+        .map(|statement| {
+            let values = statement.values.clone().map(|values| values.0);
+            (statement.sql.clone(), values.unwrap_or_default())
+        })
+        .collect()
+}
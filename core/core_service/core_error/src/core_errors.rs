@@ -12,6 +12,12 @@ pub enum CoreErrors {
     DatabaseServiceError(String),
     #[error("Data validation Error : {0}")]
     DataValidationError(String),
+    #[error("permission denied : {0}")]
+    PermissionDenied(String),
+    #[error("mail delivery error : {0}")]
+    MailDeliveryError(String),
+    #[error("identity provider error : {0}")]
+    IdentityProviderError(String),
     #[error("unknown data store error")]
     Unknown,
 }
@@ -28,6 +34,10 @@ impl From<jsonwebtoken::errors::Error> for CoreErrors {
             jsonwebtoken::errors::ErrorKind::InvalidToken => "invalid_token".to_string(),
             jsonwebtoken::errors::ErrorKind::InvalidSignature => "invalid_token".to_string(),
             jsonwebtoken::errors::ErrorKind::ExpiredSignature => "token_expired".to_string(),
+            jsonwebtoken::errors::ErrorKind::ImmatureSignature => "invalid_token".to_string(),
+            jsonwebtoken::errors::ErrorKind::InvalidIssuer => "invalid_token".to_string(),
+            jsonwebtoken::errors::ErrorKind::InvalidAudience => "invalid_token".to_string(),
+            jsonwebtoken::errors::ErrorKind::MissingRequiredClaim(_) => "invalid_token".to_string(),
             jsonwebtoken::errors::ErrorKind::Json(_) => "wrong_token_format".to_string(),
             _ => err.to_string(),
         };
@@ -117,6 +127,9 @@ impl From<CoreErrors> for Status {
             CoreErrors::HashPasswordError(message) => Status::permission_denied(message),
             CoreErrors::DatabaseServiceError(message) => Status::permission_denied(message),
             CoreErrors::DataValidationError(message) => Status::invalid_argument(message),
+            CoreErrors::PermissionDenied(message) => Status::permission_denied(message),
+            CoreErrors::MailDeliveryError(message) => Status::unavailable(message),
+            CoreErrors::IdentityProviderError(message) => Status::unavailable(message),
             CoreErrors::Unknown => Status::internal("Internal Server Error".to_string()),
             // _ => Status::internal("Internal Server Error".to_string()),
         }
@@ -2,6 +2,20 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
 mod m20240919_201951_add_test_data;
+mod m20261016_000001_create_refresh_tokens;
+mod m20261016_000002_create_sessions;
+mod m20261016_000003_create_password_reset_tokens;
+mod m20261016_000004_add_email_verification;
+mod m20261016_000005_create_totp;
+mod m20261016_000006_create_login_attempts;
+mod m20261016_000007_create_personal_access_tokens;
+mod m20261016_000008_create_user_identities;
+mod m20261016_000009_create_company_invitations;
+mod m20261016_000010_create_company_alias_redirects;
+mod m20261016_000011_add_project_archiving;
+mod m20261016_000012_add_soft_delete;
+mod m20261016_000013_create_platform_admins;
+mod m20261016_000014_create_role_effective_level;
 
 pub struct Migrator;
 
@@ -11,6 +25,20 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20220101_000001_create_table::Migration),
             Box::new(m20240919_201951_add_test_data::Migration),
+            Box::new(m20261016_000001_create_refresh_tokens::Migration),
+            Box::new(m20261016_000002_create_sessions::Migration),
+            Box::new(m20261016_000003_create_password_reset_tokens::Migration),
+            Box::new(m20261016_000004_add_email_verification::Migration),
+            Box::new(m20261016_000005_create_totp::Migration),
+            Box::new(m20261016_000006_create_login_attempts::Migration),
+            Box::new(m20261016_000007_create_personal_access_tokens::Migration),
+            Box::new(m20261016_000008_create_user_identities::Migration),
+            Box::new(m20261016_000009_create_company_invitations::Migration),
+            Box::new(m20261016_000010_create_company_alias_redirects::Migration),
+            Box::new(m20261016_000011_add_project_archiving::Migration),
+            Box::new(m20261016_000012_add_soft_delete::Migration),
+            Box::new(m20261016_000013_create_platform_admins::Migration),
+            Box::new(m20261016_000014_create_role_effective_level::Migration),
         ]
     }
 }
@@ -0,0 +1,142 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(
+                        ColumnDef::new(Users::EmailVerifiedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Accounts registered before verification existed keep working without confirming
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE users SET email_verified_at = created_at;")
+            .await?;
+
+        // Email verification tokens table. The address a token was sent to is stored with it,
+        // so a link sent before an email change can't verify the new address.
+        manager
+            .create_table(
+                Table::create()
+                    .table(EmailVerificationTokens::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EmailVerificationTokens::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerificationTokens::UserId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerificationTokens::Email)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerificationTokens::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerificationTokens::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerificationTokens::UsedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailVerificationTokens::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_emailverificationtokens_user")
+                            .from(
+                                EmailVerificationTokens::Table,
+                                EmailVerificationTokens::UserId,
+                            )
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_emailverificationtokens_user_id")
+                    .table(EmailVerificationTokens::Table)
+                    .col(EmailVerificationTokens::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(EmailVerificationTokens::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::EmailVerifiedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+// Definitions of table and column enums for code clarity
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+    EmailVerifiedAt,
+}
+
+#[derive(Iden)]
+enum EmailVerificationTokens {
+    Table,
+    Id,
+    UserId,
+    Email,
+    TokenHash,
+    ExpiresAt,
+    UsedAt,
+    CreatedAt,
+}
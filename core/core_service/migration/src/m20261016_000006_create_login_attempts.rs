@@ -0,0 +1,133 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Audit log of failed login attempts. The login is stored as entered, so attempts
+        // against logins that don't exist are recorded as well.
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginAttempts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LoginAttempts::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LoginAttempts::Login).string().not_null())
+                    .col(ColumnDef::new(LoginAttempts::UserId).integer().null())
+                    .col(ColumnDef::new(LoginAttempts::IpAddress).string().null())
+                    .col(ColumnDef::new(LoginAttempts::DeviceInfo).string().null())
+                    .col(ColumnDef::new(LoginAttempts::Reason).string().not_null())
+                    .col(
+                        ColumnDef::new(LoginAttempts::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_loginattempts_user")
+                            .from(LoginAttempts::Table, LoginAttempts::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_loginattempts_login_created_at")
+                    .table(LoginAttempts::Table)
+                    .col(LoginAttempts::Login)
+                    .col(LoginAttempts::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Failed attempt counters per login and per IP address, used to slow down and
+        // temporarily lock out password guessing.
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginThrottles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LoginThrottles::ThrottleKey)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(LoginThrottles::FailedAttempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(LoginThrottles::LastFailedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(LoginThrottles::LockedUntil)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LoginThrottles::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(LoginAttempts::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+// Definitions of table and column enums for code clarity
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum LoginAttempts {
+    Table,
+    Id,
+    Login,
+    UserId,
+    IpAddress,
+    DeviceInfo,
+    Reason,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum LoginThrottles {
+    Table,
+    ThrottleKey,
+    FailedAttempts,
+    LastFailedAt,
+    LockedUntil,
+}
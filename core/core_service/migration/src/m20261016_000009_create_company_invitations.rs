@@ -0,0 +1,166 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Invitations to join a company, addressed to an email. The invited role is stored with
+        // the invitation and only the hash of the token sent in the invite link is kept. An
+        // invitation is pending until it is accepted, declined, revoked or expires.
+        manager
+            .create_table(
+                Table::create()
+                    .table(CompanyInvitations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CompanyInvitations::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::CompanyId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::Email)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::RoleId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::InvitedBy)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::AcceptedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::DeclinedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::RevokedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyInvitations::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_companyinvitations_company")
+                            .from(CompanyInvitations::Table, CompanyInvitations::CompanyId)
+                            .to(Companies::Table, Companies::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_companyinvitations_role")
+                            .from(CompanyInvitations::Table, CompanyInvitations::RoleId)
+                            .to(Roles::Table, Roles::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_companyinvitations_invited_by")
+                            .from(CompanyInvitations::Table, CompanyInvitations::InvitedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_companyinvitations_company_id")
+                    .table(CompanyInvitations::Table)
+                    .col(CompanyInvitations::CompanyId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Invitations are looked up by the email of the invited user
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE INDEX IF NOT EXISTS idx_companyinvitations_email \
+                 ON company_invitations (lower(email));",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CompanyInvitations::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+// Definitions of table and column enums for code clarity
+
+#[derive(Iden)]
+enum Companies {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Roles {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum CompanyInvitations {
+    Table,
+    Id,
+    CompanyId,
+    Email,
+    RoleId,
+    InvitedBy,
+    TokenHash,
+    ExpiresAt,
+    AcceptedAt,
+    DeclinedAt,
+    RevokedAt,
+    CreatedAt,
+}
@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Former aliases of companies. An alias stays reserved for its company after it was
+        // changed, so old links keep resolving to the company instead of to a newcomer.
+        manager
+            .create_table(
+                Table::create()
+                    .table(CompanyAliasRedirects::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CompanyAliasRedirects::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyAliasRedirects::CompanyId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyAliasRedirects::NameAlias)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CompanyAliasRedirects::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_companyaliasredirects_company")
+                            .from(
+                                CompanyAliasRedirects::Table,
+                                CompanyAliasRedirects::CompanyId,
+                            )
+                            .to(Companies::Table, Companies::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_companyaliasredirects_company_id")
+                    .table(CompanyAliasRedirects::Table)
+                    .col(CompanyAliasRedirects::CompanyId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CompanyAliasRedirects::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+// Definitions of table and column enums for code clarity
+
+#[derive(Iden)]
+enum Companies {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum CompanyAliasRedirects {
+    Table,
+    Id,
+    CompanyId,
+    NameAlias,
+    CreatedAt,
+}
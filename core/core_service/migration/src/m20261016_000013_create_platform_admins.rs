@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Operators of the platform. Only they may deactivate accounts, since a deactivated
+        // account is locked out of every company.
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlatformAdmins::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PlatformAdmins::UserId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PlatformAdmins::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_platformadmins_user")
+                            .from(PlatformAdmins::Table, PlatformAdmins::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The initial admin account becomes the first platform admin
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                INSERT INTO platform_admins (user_id)
+                SELECT id FROM users WHERE login = 'admin'
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PlatformAdmins::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+// Definitions of table and column enums for code clarity
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum PlatformAdmins {
+    Table,
+    UserId,
+    CreatedAt,
+}
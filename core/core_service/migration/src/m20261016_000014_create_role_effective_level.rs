@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A role inherits the privileges of its parents, so queries compare the lowest level
+        // found on its `parent_role_id` chain instead of its own level. The path guards
+        // against cycles in the chain.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                CREATE OR REPLACE FUNCTION role_effective_level(role_id INTEGER)
+                RETURNS INTEGER
+                LANGUAGE sql
+                STABLE
+                AS $$
+                    WITH RECURSIVE role_chain (id, parent_role_id, level, path) AS (
+                        SELECT r.id, r.parent_role_id, r.level, ARRAY[r.id]
+                        FROM roles r
+                        WHERE r.id = role_id
+
+                        UNION ALL
+
+                        SELECT p.id, p.parent_role_id, p.level, c.path || p.id
+                        FROM role_chain c
+                        JOIN roles p ON p.id = c.parent_role_id
+                        WHERE NOT p.id = ANY(c.path)
+                    )
+                    SELECT MIN(level) FROM role_chain
+                $$
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP FUNCTION IF EXISTS role_effective_level(INTEGER)")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1 +1,3 @@
+pub mod mailer;
 pub mod password_validation;
+pub mod totp;
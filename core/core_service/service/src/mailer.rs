@@ -0,0 +1,241 @@
+use std::{
+    env,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use core_error::core_errors::CoreErrors;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+/// An email to be delivered to a single recipient.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MailMessage {
+    /// The address of the recipient.
+    pub to: String,
+    pub subject: String,
+    /// The plain text body.
+    pub body: String,
+}
+
+/// Delivers emails, e.g. password reset links.
+///
+/// The transport is chosen with `MAIL_TRANSPORT`, see `mailer_from_env`.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Sends a message.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::MailDeliveryError` if the message can't be built or delivered.
+    async fn send(&self, message: MailMessage) -> Result<(), CoreErrors>;
+}
+
+/// Whether the `file` and `memory` transports may be used. They keep emails, including password
+/// reset links, readable on the server, so release builds only send through SMTP.
+const DEV_TRANSPORTS_ALLOWED: bool = cfg!(debug_assertions);
+
+/// Creates the mailer configured in the environment.
+///
+/// * `MAIL_TRANSPORT` - `smtp`, or `file` and `memory` in debug builds. Required.
+/// * `MAIL_FROM` - The sender address, e.g. `Hellai <no-reply@hellai.app>`.
+/// * `SMTP_HOST`, `SMTP_PORT`, `SMTP_USERNAME`, `SMTP_PASSWORD`, `SMTP_TLS` - See `SmtpMailer::from_env`.
+/// * `MAIL_OUTBOX_DIR` - Directory the `file` transport writes to, `outbox` by default.
+///
+/// # Errors
+/// * Returns `CoreErrors::MailDeliveryError` if no transport is configured, the transport is not
+///   available in this build or the configuration is invalid.
+pub fn mailer_from_env() -> Result<Arc<dyn Mailer>, CoreErrors> {
+    let from =
+        mailbox(&env::var("MAIL_FROM").unwrap_or("Hellai <no-reply@localhost>".to_string()))?;
+
+    match env::var("MAIL_TRANSPORT").as_deref() {
+        Ok("smtp") => Ok(Arc::new(SmtpMailer::from_env(from)?)),
+        Ok("file") if DEV_TRANSPORTS_ALLOWED => Ok(Arc::new(FileMailer::new(
+            from,
+            env::var("MAIL_OUTBOX_DIR").unwrap_or("outbox".to_string()),
+        ))),
+        Ok("memory") if DEV_TRANSPORTS_ALLOWED => Ok(Arc::new(InMemoryMailer::default())),
+        Ok(transport @ ("file" | "memory")) => Err(CoreErrors::MailDeliveryError(format!(
+            "MAIL_TRANSPORT {} is only available in debug builds",
+            transport
+        ))),
+        Ok(other) => Err(CoreErrors::MailDeliveryError(format!(
+            "unsupported MAIL_TRANSPORT {}",
+            other
+        ))),
+        Err(_) => Err(CoreErrors::MailDeliveryError(
+            "MAIL_TRANSPORT environment variable is not set".to_string(),
+        )),
+    }
+}
+
+/// Sends emails through an SMTP relay.
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    /// Configures the SMTP relay from the environment.
+    ///
+    /// * `SMTP_HOST` - The relay host, required.
+    /// * `SMTP_PORT` - The relay port; defaults to the port of the TLS mode.
+    /// * `SMTP_USERNAME`, `SMTP_PASSWORD` - Credentials, if the relay requires them.
+    /// * `SMTP_TLS` - `starttls` (default), `tls` for implicit TLS or `none`.
+    ///
+    /// # Errors
+    /// * Returns `CoreErrors::MailDeliveryError` if the configuration is invalid.
+    pub fn from_env(from: Mailbox) -> Result<SmtpMailer, CoreErrors> {
+        let host = env::var("SMTP_HOST").map_err(|_| {
+            CoreErrors::MailDeliveryError("SMTP_HOST environment variable is not set".to_string())
+        })?;
+
+        let mut builder = match env::var("SMTP_TLS").as_deref() {
+            Ok("starttls") | Err(_) => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host),
+            Ok("tls") => AsyncSmtpTransport::<Tokio1Executor>::relay(&host),
+            Ok("none") => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &host,
+            )),
+            Ok(other) => {
+                return Err(CoreErrors::MailDeliveryError(format!(
+                    "unsupported SMTP_TLS {}",
+                    other
+                )))
+            }
+        }
+        .map_err(|err| CoreErrors::MailDeliveryError(err.to_string()))?;
+
+        if let Ok(port) = env::var("SMTP_PORT") {
+            let port = port.parse().map_err(|_| {
+                CoreErrors::MailDeliveryError(
+                    "SMTP_PORT environment variable is not valid".to_string(),
+                )
+            })?;
+            builder = builder.port(port);
+        }
+
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD"))
+        {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(SmtpMailer {
+            from,
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), CoreErrors> {
+        let email = build_email(&self.from, message)?;
+
+        self.transport
+            .send(email)
+            .await
+            .map_err(|err| CoreErrors::MailDeliveryError(err.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Writes every email as an `.eml` file into a directory instead of sending it.
+///
+/// Meant for local development, where the outbox can be opened with any mail client. Not available
+/// in release builds.
+pub struct FileMailer {
+    from: Mailbox,
+    directory: PathBuf,
+    counter: AtomicU64,
+}
+
+impl FileMailer {
+    pub fn new(from: Mailbox, directory: impl Into<PathBuf>) -> FileMailer {
+        FileMailer {
+            from,
+            directory: directory.into(),
+            counter: AtomicU64::new(0),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), CoreErrors> {
+        let email = build_email(&self.from, message)?;
+
+        // Timestamp first so the files sort in the order they were sent
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let file_name = format!(
+            "{}-{}.eml",
+            timestamp,
+            self.counter.fetch_add(1, Ordering::Relaxed)
+        );
+
+        tokio::fs::create_dir_all(&self.directory)
+            .await
+            .map_err(|err| CoreErrors::MailDeliveryError(err.to_string()))?;
+        tokio::fs::write(self.directory.join(file_name), email.formatted())
+            .await
+            .map_err(|err| CoreErrors::MailDeliveryError(err.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Keeps every email in memory instead of sending it, so tests can inspect them. Not available in
+/// release builds.
+#[derive(Default)]
+pub struct InMemoryMailer {
+    messages: Mutex<Vec<MailMessage>>,
+}
+
+impl InMemoryMailer {
+    /// Returns the messages sent so far, oldest first.
+    pub fn sent_messages(&self) -> Vec<MailMessage> {
+        self.messages
+            .lock()
+            .map(|messages| messages.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl Mailer for InMemoryMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), CoreErrors> {
+        self.messages
+            .lock()
+            .map_err(|_| CoreErrors::Unknown)?
+            .push(message);
+
+        Ok(())
+    }
+}
+
+fn mailbox(address: &str) -> Result<Mailbox, CoreErrors> {
+    address
+        .parse()
+        .map_err(|_| CoreErrors::MailDeliveryError(format!("invalid mail address {}", address)))
+}
+
+fn build_email(from: &Mailbox, message: MailMessage) -> Result<Message, CoreErrors> {
+    Message::builder()
+        .from(from.clone())
+        .to(mailbox(&message.to)?)
+        .subject(message.subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(message.body)
+        .map_err(|err| CoreErrors::MailDeliveryError(err.to_string()))
+}
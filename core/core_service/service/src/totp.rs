@@ -0,0 +1,239 @@
+use std::{env, time::SystemTime};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use core_error::core_errors::CoreErrors;
+use lazy_static::lazy_static;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// Duration of a TOTP time step in seconds, as used by authenticator apps.
+const TOTP_STEP: u64 = 30;
+/// Number of digits of a TOTP code.
+const TOTP_DIGITS: usize = 6;
+/// Number of steps before and after the current one a code is accepted for, to allow for clock drift.
+const TOTP_SKEW: u64 = 1;
+/// Number of recovery codes issued when two-factor authentication is enabled.
+const RECOVERY_CODES_COUNT: usize = 10;
+
+lazy_static! {
+    static ref TOTP_ISSUER: String = env::var("TOTP_ISSUER").unwrap_or("Hellai".to_string());
+}
+
+/// Generates a new random TOTP secret (160 bits), encoded in base32.
+pub fn generate_totp_secret() -> String {
+    let mut secret = [0u8; 20];
+    OsRng.fill_bytes(&mut secret);
+
+    Secret::Raw(secret.to_vec()).to_encoded().to_string()
+}
+
+/// Builds the `otpauth://` URI an authenticator app is set up with, usually shown as a QR code.
+///
+/// # Arguments
+/// * `secret` - The base32 encoded TOTP secret.
+/// * `account_name` - The account the code is for, shown in the authenticator app.
+///
+/// # Errors
+/// * Returns `CoreErrors::DataValidationError` if the secret or the account name is invalid.
+pub fn totp_uri(secret: &str, account_name: &str) -> Result<String, CoreErrors> {
+    Ok(totp(secret, account_name)?.get_url())
+}
+
+/// Checks a TOTP code against a secret.
+///
+/// Each code can only be used once: codes of the time step `last_used_step` or earlier are rejected.
+///
+/// # Arguments
+/// * `secret` - The base32 encoded TOTP secret.
+/// * `code` - The code entered by the user.
+/// * `last_used_step` - The time step of the last code accepted for this secret, if any.
+///
+/// # Returns
+/// * `Result<Option<i64>, CoreErrors>` - Returns the time step of the code if it is valid, otherwise `None`.
+pub fn verify_totp_code(
+    secret: &str,
+    code: &str,
+    last_used_step: Option<i64>,
+) -> Result<Option<i64>, CoreErrors> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    verify_totp_code_at(secret, code, last_used_step, now)
+}
+
+/// Checks a TOTP code as `verify_totp_code` does, at the given time in seconds since the epoch.
+fn verify_totp_code_at(
+    secret: &str,
+    code: &str,
+    last_used_step: Option<i64>,
+    now: u64,
+) -> Result<Option<i64>, CoreErrors> {
+    let totp = totp(secret, "")?;
+
+    let current_step = now / TOTP_STEP;
+
+    let matched_step = (current_step.saturating_sub(TOTP_SKEW)..=current_step + TOTP_SKEW)
+        .find(|step| totp.check(code.trim(), step * TOTP_STEP))
+        .map(|step| step as i64);
+
+    Ok(matched_step.filter(|step| last_used_step.is_none_or(|last| *step > last)))
+}
+
+/// Generates a set of single-use recovery codes, e.g. `k3j9d-x7q2m`.
+///
+/// Recovery codes let a user sign in when they lose their authenticator. Only their hashes should be stored.
+pub fn generate_recovery_codes() -> Vec<String> {
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+    (0..RECOVERY_CODES_COUNT)
+        .map(|_| {
+            let code: String = (0..10)
+                .map(|_| ALPHABET[(OsRng.next_u32() as usize) % ALPHABET.len()] as char)
+                .collect();
+
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+/// Brings a recovery code entered by the user into the form it was issued in.
+pub fn normalize_recovery_code(code: &str) -> String {
+    let code: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    match code.len() {
+        10 => format!("{}-{}", &code[..5], &code[5..]),
+        _ => code,
+    }
+}
+
+fn totp(secret: &str, account_name: &str) -> Result<TOTP, CoreErrors> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|_| CoreErrors::DataValidationError("invalid_totp_secret".to_string()))?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        0,
+        TOTP_STEP,
+        secret,
+        Some(TOTP_ISSUER.clone()),
+        account_name.to_string(),
+    )
+    .map_err(|err| CoreErrors::DataValidationError(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP";
+    const NOW: u64 = 1_760_000_000;
+
+    /// Returns the code an authenticator app shows `steps` time steps from `NOW`.
+    fn code_at(steps: i64) -> String {
+        totp(SECRET, "")
+            .unwrap()
+            .generate((NOW as i64 + steps * TOTP_STEP as i64) as u64)
+    }
+
+    fn current_step() -> i64 {
+        (NOW / TOTP_STEP) as i64
+    }
+
+    #[test]
+    fn accepts_the_current_code() {
+        let step = verify_totp_code_at(SECRET, &code_at(0), None, NOW).unwrap();
+
+        assert_eq!(step, Some(current_step()));
+    }
+
+    #[test]
+    fn accepts_codes_of_neighbouring_steps_only() {
+        for steps in [-1, 1] {
+            let step = verify_totp_code_at(SECRET, &code_at(steps), None, NOW).unwrap();
+
+            assert_eq!(step, Some(current_step() + steps));
+        }
+
+        for steps in [-2, 2] {
+            assert_eq!(
+                verify_totp_code_at(SECRET, &code_at(steps), None, NOW).unwrap(),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_codes_of_the_last_used_step_or_earlier() {
+        let last_used_step = Some(current_step());
+
+        assert_eq!(
+            verify_totp_code_at(SECRET, &code_at(0), last_used_step, NOW).unwrap(),
+            None
+        );
+        assert_eq!(
+            verify_totp_code_at(SECRET, &code_at(-1), last_used_step, NOW).unwrap(),
+            None
+        );
+        assert_eq!(
+            verify_totp_code_at(SECRET, &code_at(1), last_used_step, NOW).unwrap(),
+            Some(current_step() + 1)
+        );
+    }
+
+    #[test]
+    fn ignores_surrounding_whitespace_and_rejects_wrong_codes() {
+        let code = format!(" {} ", code_at(0));
+        let wrong_code = format!(
+            "{:06}",
+            (code_at(0).parse::<u32>().unwrap() + 1) % 1_000_000
+        );
+
+        assert!(verify_totp_code_at(SECRET, &code, None, NOW)
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            verify_totp_code_at(SECRET, &wrong_code, None, NOW).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_secrets() {
+        assert!(verify_totp_code_at("not base32!", "123456", None, NOW).is_err());
+    }
+
+    #[test]
+    fn generated_secrets_verify_their_codes() {
+        let secret = generate_totp_secret();
+        let code = totp(&secret, "").unwrap().generate(NOW);
+
+        assert!(verify_totp_code_at(&secret, &code, None, NOW)
+            .unwrap()
+            .is_some());
+        assert!(totp_uri(&secret, "user@example.com")
+            .unwrap()
+            .starts_with("otpauth://totp/"));
+    }
+
+    #[test]
+    fn recovery_codes_are_unique_and_normalized() {
+        let codes = generate_recovery_codes();
+
+        assert_eq!(codes.len(), RECOVERY_CODES_COUNT);
+        for code in &codes {
+            assert_eq!(normalize_recovery_code(code), *code);
+            assert_eq!(codes.iter().filter(|other| *other == code).count(), 1);
+        }
+
+        assert_eq!(normalize_recovery_code(" K3J9D X7Q2M "), "k3j9d-x7q2m");
+        assert_eq!(normalize_recovery_code("k3j9dx7q2m"), "k3j9d-x7q2m");
+        assert_eq!(normalize_recovery_code("short"), "short");
+    }
+}